@@ -5,9 +5,29 @@ use colored::Colorize;
 use std::io::{self, Write};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
-use crate::shared::types::{Clients, Client, ClientState, Rooms, Roles, PublicKeys};
+use crate::shared::types::{Clients, Client, ClientState, Room, Rooms, Roles, PublicKeys};
 use crate::backend::parser::Command;
-use crate::shared::utils::{lock_client, lock_clients, lock_room, lock_rooms, save_rooms_to_disk};
+use crate::shared::utils::{find_room, send_failure, lock_client, lock_clients, lock_room, lock_rooms, save_rooms_to_disk};
+
+pub const ROOM_CREATE_COOLDOWN_SECS: u64 = 60;
+pub const MAX_OWNED_ROOMS: usize = 10;
+pub const MAX_MESSAGE_BYTES: usize = 4096;
+pub const MAX_ROOM_NAME_LEN: usize = 32;
+pub const MAX_NICKNAME_LEN: usize = 24;
+pub const MAX_PINNED: usize = 10;
+pub const AUDIT_LOG_DEFAULT_ENTRIES: usize = 10;
+pub const AUDIT_LOG_MAX_ENTRIES: usize = 50;
+pub const MOD_INFO_PAGE_SIZE: usize = 20;
+pub const MAX_ROLE_LABEL_LEN: usize = 20;
+pub const MAX_SCRIPT_LINES: usize = 100;
+pub const MAX_MOTD_LEN: usize = 300;
+pub const MAX_LOGIN_FAILURES: u32 = 5;
+pub const LOGIN_FAILURE_WINDOW_SECS: u64 = 300;
+pub const LOGIN_LOCKOUT_SECS: u64 = 600;
+pub const EXPORT_ROLES_KEYS: [&str; 2] = ["roles", "role_labels"];
+pub const EXPORT_CONFIG_KEYS: [&str; 14] = ["whitelist_enabled", "whitelist", "msg_rate", "session_timeout", "slowmode_secs", "roles", "role_labels", "tags", "locked", "filter_enabled", "blocked_words", "announce_staff", "require_ban_reason", "motd"];
+pub const DEFAULT_MODERATOR_COMMANDS: [&str; 7] = ["afk", "seen", "msg", "me", "super.users", "user", "mod"];
+pub const DEFAULT_USER_COMMANDS: [&str; 5] = ["afk", "seen", "msg", "me", "user"];
 
 pub static DESCRIPTIONS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
     HashMap::from([
@@ -17,16 +37,27 @@ pub static DESCRIPTIONS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|
         ("seen",            "> /seen <user>      See when a user was last online"),
         ("announce",        "> /announce <msg>   Announce a room message, bypass ignores"),
         ("super",           "> /super            Administrator commands"),
+        ("super.info",      "> /super info       Show room configuration overview"),
         ("super.users",     "> /super users      Show all room user data"),
         ("super.rename",    "> /super rename     Changes room name"),
+        ("super.lock",      "> /super lock       Toggles room rename protection"),
+        ("super.staffalerts", "> /super staffalerts Toggles staff online alerts"),
+        ("super.policy",    "> /super policy     Sets room moderation policies"),
+        ("super.motd",      "> /super motd       Sets the room welcome message"),
+        ("super.log",       "> /super log        Show recent moderation actions"),
+        ("super.slowmode",  "> /super slowmode   Sets the room slowmode delay"),
         ("super.export",    "> /super export     Saves room data"),
         ("super.whitelist", "> /super whitelist  Manage room whitelist"),
         ("super.limit",     "> /super limit      Manage room rate limits"),
         ("super.roles",     "> /super roles      Manage room roles and permissions"),
+        ("super.tags",      "> /super tags       Manage room tags"),
+        ("super.filter",    "> /super filter     Manage room word filter"),
+        ("super.pin",       "> /super pin        Manage pinned room notes"),
         ("user",            "> /user             Manage user settings"),
         ("user.list",       "> /user list        Show all visible room users"),
         ("user.rename",     "> /user rename      Changes your name in the room"),
         ("user.recolor",    "> /user recolor     Changes your name color in the room"),
+        ("user.profile",    "> /user profile     Sets your nickname and color together"),
         ("user.hide",       "> /user hide        Hides you from /user list"),
         ("mod",             "> /mod              Use chat moderation tools"),
         ("mod.info",        "> /mod info         Show who is muted and banned"),
@@ -34,83 +65,152 @@ pub static DESCRIPTIONS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|
         ("mod.mute",        "> /mod mute         Disable certain users from speaking"),
         ("mod.unmute",      "> /mod unmute       Allow certain users to speak again"),
         ("mod.ban",         "> /mod ban          Disable certain users from joining"),
-        ("mod.unban",       "> /mod unban        Allow certain users to join again")
+        ("mod.unban",       "> /mod unban        Allow certain users to join again"),
+        ("mod.whois",       "> /whois <user>     Show a user's records across all rooms"),
+        ("mod.history",     "> /mod history      Show a user's past bans in the room")
     ])
 });
 
 pub static RESTRICTED_COMMANDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     HashSet::from([
         "afk", "msg", "me", "seen", "announce",
-        "super", "super.users", "super.rename", "super.export", 
-        "super.whitelist", "super.whitelist.info", "super.whitelist.toggle", "super.whitelist.add", "super.whitelist.remove",
+        "super", "super.info", "super.users", "super.rename", "super.lock", "super.staffalerts", "super.policy", "super.motd", "super.log", "super.slowmode", "super.export", 
+        "super.whitelist", "super.whitelist.info", "super.whitelist.toggle", "super.whitelist.add", "super.whitelist.remove", "super.whitelist.import",
         "super.limit", "super.limit.info", "super.limit.rate", "super.limit.session",
         "super.roles", "super.roles.list", "super.roles.add", "super.roles.revoke", "super.roles.assign", "super.roles.recolor",
-        "user", "user.list", "user.rename", "user.recolor", "user.hide",
-        "mod", "mod.info", "mod.kick", "mod.ban", "mod.unban", "mod.mute", "mod.unmute",
+        "super.tags", "super.filter", "super.pin",
+        "user", "user.list", "user.rename", "user.recolor", "user.profile", "user.hide",
+        "mod", "mod.info", "mod.kick", "mod.ban", "mod.unban", "mod.mute", "mod.unmute", "mod.whois", "mod.history",
     ])
 });
 
 pub fn command_order() -> Vec<&'static str> {
     vec![
-        "help", "clear", "ping", "quit", "leave", "status", "ignore",
+        "help", "clear", "ping", "quit", "leave", "status", "ignore", "block",
         "afk", "msg", "me", "seen", "announce",
-        "super", "super.users", "super.rename", "super.export", 
-        "super.whitelist", "super.whitelist.info", "super.whitelist.toggle", "super.whitelist.add", "super.whitelist.remove",
+        "super", "super.info", "super.users", "super.rename", "super.lock", "super.staffalerts", "super.policy", "super.motd", "super.log", "super.slowmode", "super.export", 
+        "super.whitelist", "super.whitelist.info", "super.whitelist.toggle", "super.whitelist.add", "super.whitelist.remove", "super.whitelist.import",
         "super.limit", "super.limit.info", "super.limit.rate", "super.limit.session",
         "super.roles", "super.roles.list", "super.roles.add", "super.roles.revoke", "super.roles.assign", "super.roles.recolor",
-        "user", "user.list", "user.rename", "user.recolor", "user.hide",
-        "mod", "mod.info", "mod.kick", "mod.ban", "mod.unban", "mod.mute", "mod.unmute"
+        "super.tags", "super.filter", "super.pin",
+        "user", "user.list", "user.rename", "user.recolor", "user.profile", "user.hide",
+        "mod", "mod.info", "mod.kick", "mod.ban", "mod.unban", "mod.mute", "mod.unmute", "mod.whois", "mod.history"
     ]
 }
 
-pub fn always_visible() -> Vec<&'static str> {
+pub fn always_visible() -> Vec<(&'static str, &'static str)> {
     vec![
-        "Available commands:",
-        "> /help             Show this help menu",
-        "> /clear            Clear the chat screen",
-        "> /ping             Check connection to the server",
-        "> /quit             Exit the application",
-        "> /leave            Leave your current room",
-        "> /status           Show your current room info",
-        "> /ignore           Manage ignore list"
+        ("help",       "> /help             Show this help menu"),
+        ("help.roles", "> /help roles       Show commands your role can use"),
+        ("clear",      "> /clear            Clear the chat screen"),
+        ("time",       "> /time             Toggle message timestamps"),
+        ("theme",      "> /theme <name>     Switch the client color theme"),
+        ("ping",       "> /ping             Check connection to the server"),
+        ("quit",       "> /quit             Exit the application"),
+        ("leave",      "> /leave            Leave your current room"),
+        ("status",     "> /status           Show your current room info"),
+        ("stats",      "> /stats            Show server statistics"),
+        ("ignore",     "> /ignore           Manage ignore list"),
+        ("block",      "> /block            Manage DM block list")
     ]
 }
 
-pub fn help_msg_guest() -> &'static str {
-r#"Available commands:
-> /help             Show this help menu
-> /clear            Clear the chat screen
-> /ping             Check connection to the server
-> /quit             Exit the application
-> /account          Manage your account"#
+pub fn help_entries_guest() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("help",    "> /help             Show this help menu"),
+        ("clear",   "> /clear            Clear the chat screen"),
+        ("time",    "> /time             Toggle message timestamps"),
+        ("theme",   "> /theme <name>     Switch the client color theme"),
+        ("ping",    "> /ping             Check connection to the server"),
+        ("quit",    "> /quit             Exit the application"),
+        ("account", "> /account          Manage your account")
+    ]
 }
 
-pub fn help_msg_loggedin() -> &'static str {
-r#"Available commands:
-> /help             Show this help menu
-> /clear            Clear the chat screen
-> /ping             Check connection to the server
-> /quit             Exit the application
-> /account          Manage your account
-> /room             Manage chat rooms
-> /ignore           Manage ignore list"#
+pub fn help_entries_loggedin() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("help",    "> /help             Show this help menu"),
+        ("clear",   "> /clear            Clear the chat screen"),
+        ("time",    "> /time             Toggle message timestamps"),
+        ("theme",   "> /theme <name>     Switch the client color theme"),
+        ("ping",    "> /ping             Check connection to the server"),
+        ("quit",    "> /quit             Exit the application"),
+        ("account", "> /account          Manage your account"),
+        ("stats",   "> /stats            Show server statistics"),
+        ("room",    "> /room             Manage chat rooms"),
+        ("ignore",  "> /ignore           Manage ignore list"),
+        ("block",   "> /block            Manage DM block list")
+    ]
 }
 
-pub fn help_msg_inroom(extra_cmds: Vec<&str>) -> String {
+pub fn help_entries_inroom(extra_cmds: Vec<&str>) -> Vec<(&'static str, &'static str)> {
     let shown_cmds: HashSet<String> = extra_cmds.into_iter().map(|s| s.to_string()).collect();
     let descriptions = &*DESCRIPTIONS;
 
-    let mut ordered_filtered = always_visible().into_iter().map(String::from).collect::<Vec<_>>();
+    let mut entries = always_visible();
 
     for cmd in command_order() {
         if shown_cmds.contains(cmd) {
             if let Some(desc) = descriptions.get(cmd) {
-                ordered_filtered.push(desc.to_string());
+                entries.push((cmd, *desc));
             }
         }
     }
 
-    ordered_filtered.join("\n")
+    entries
+}
+
+pub fn command_aliases(cmd: &str) -> &'static [&'static str] {
+    match cmd {
+        "help" => &["h"],
+        "account" => &["a"],
+        "room" => &["r"],
+        "ignore" => &["i"],
+        "block" => &["b"],
+        "msg" => &["message", "dm"],
+        "announce" => &["an"],
+        "super" => &["s"],
+        "user" => &["u"],
+        "mod" => &["m"],
+        _ => &[],
+    }
+}
+
+pub fn help_msg(entries: Vec<(&str, &str)>, query: &str) -> String {
+    let query = query.trim().trim_start_matches('/').to_lowercase();
+    if query.is_empty() {
+        let lines: Vec<&str> = entries.into_iter().map(|(_, line)| line).collect();
+        return format!("Available commands:\n{}", lines.join("\n"));
+    }
+
+    let is_alias = |cmd: &str| command_aliases(cmd.split('.').next().unwrap_or(cmd)).contains(&query.as_str());
+    let alias_hit = entries.iter().any(|(cmd, _)| is_alias(cmd));
+    let matches: Vec<&str> = entries.into_iter()
+        .filter(|(cmd, line)| if alias_hit { is_alias(cmd) } else { line.to_lowercase().contains(&query) })
+        .map(|(_, line)| line)
+        .collect();
+
+    if matches.is_empty() {
+        return format!("No commands match '{query}'");
+    }
+    format!("Commands matching '{query}':\n{}", matches.join("\n"))
+}
+
+pub fn help_msg_role(role: &str, roles: &Roles) -> String {
+    if role == "admin" || role == "owner" {
+        return format!("As {role}, you can use all commands in this room");
+    }
+
+    let permitted: Vec<String> = command_order().into_iter()
+        .filter(|cmd| RESTRICTED_COMMANDS.contains(cmd) && check_role_permissions(role, cmd, roles))
+        .map(|cmd| format!("> /{}", cmd.replace('.', " ")))
+        .collect();
+
+    if permitted.is_empty() {
+        return format!("As {role}, you can only use the always-available commands in this room");
+    }
+
+    format!("Commands available to you as {role}:\n{}", permitted.join("\n"))
 }
 
 
@@ -124,6 +224,94 @@ pub fn hash_password(password: &str) -> Result<String, io::Error> {
         .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
 }
 
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < 8 {
+        return Err("Error: Password must be at least 8 characters long".to_string());
+    }
+    if password.chars().all(|c| c.is_alphabetic()) {
+        return Err("Error: Password must contain at least one non-letter character".to_string());
+    }
+    Ok(())
+}
+
+pub fn resolve_username(room: &Room, name: &str) -> Option<String> {
+    if room.users.contains_key(name) {
+        return Some(name.to_string());
+    }
+    room.users.keys().find(|u| u.eq_ignore_ascii_case(name)).cloned()
+}
+
+pub fn resolve_room_username(rooms: &Rooms, room: &str, name: &str) -> io::Result<String> {
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match rooms_map.get(room) {
+            Some(r) => Arc::clone(r),
+            None => return Ok(name.to_string()),
+        }
+    };
+    let room_guard = lock_room(&room_arc);
+    Ok(resolve_username(&room_guard, name).unwrap_or_else(|| name.to_string()))
+}
+
+pub fn validate_room_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > MAX_ROOM_NAME_LEN {
+        return Err(format!("Error: Room names must be between 1 and {MAX_ROOM_NAME_LEN} characters long"));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Error: Room names may only contain letters, numbers, dashes, and underscores".to_string());
+    }
+    if matches!(name.to_lowercase().as_str(), "members" | "force" | "whitelist") {
+        return Err(format!("Error: '{name}' is reserved and cannot be used as a room name"));
+    }
+    Ok(())
+}
+
+// File names given to vault commands must stay inside their vault directory
+pub fn is_safe_filename(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+pub fn validate_nickname(nick: &str) -> Result<(), String> {
+    if nick.is_empty() || nick.chars().count() > MAX_NICKNAME_LEN {
+        return Err(format!("Error: Nicknames must be between 1 and {MAX_NICKNAME_LEN} characters long"));
+    }
+    if nick.chars().any(|c| c.is_control()) {
+        return Err("Error: Nicknames cannot contain control characters".to_string());
+    }
+    if nick.contains(':') {
+        return Err("Error: Nicknames cannot contain ':'".to_string());
+    }
+    Ok(())
+}
+
+// Fills in {user}, {room}, and {count} in a room MOTD. Any other braces are left as written.
+pub fn expand_motd(motd: &str, user: &str, room: &str, count: usize) -> String {
+    let mut out = String::with_capacity(motd.len());
+    let mut rest = motd;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| match &after[..end] {
+            "user" => Some((user.to_string(), end)),
+            "room" => Some((room.to_string(), end)),
+            "count" => Some((count.to_string(), end)),
+            _ => None,
+        });
+        match value {
+            Some((v, end)) => {
+                out.push_str(&v);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
 pub fn verify_password(password: &str, hash: &str) -> bool {
     let parsed_hash = match argon2::PasswordHash::new(hash) {
         Ok(h) => h,
@@ -173,7 +361,7 @@ pub fn check_role_permissions(role: &str, command: &str, roles: &Roles) -> bool
     }
 }
 
-pub fn has_permission(cmd: &Command, client_arc: Arc<Mutex<Client>>, rooms: &Rooms, username: &String, room: &String) -> io::Result<bool> {
+pub fn has_permission(cmd: &Command, client_arc: Arc<Mutex<Client>>, rooms: &Rooms, username: &str, room: &str) -> io::Result<bool> {
     let cmd_str = cmd.to_string();
 
     if cmd_str.is_empty() || !RESTRICTED_COMMANDS.contains(cmd_str.as_str()) {
@@ -181,18 +369,17 @@ pub fn has_permission(cmd: &Command, client_arc: Arc<Mutex<Client>>, rooms: &Roo
     }
 
     let role = {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let client = lock_client(&client_arc)?;
-                writeln!(&client.stream, "{}", format!("Room {room} not found").yellow())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client_arc, &e)?;
                 return Ok(false)
             }
         };
 
-        let room_guard = lock_room(&room_arc)?;
-        let client = lock_client(&client_arc)?;
+        let room_guard = lock_room(&room_arc);
+        let client = lock_client(&client_arc);
         match room_guard.users.get(username) {
             Some(u) => u.role.clone(),
             None => {
@@ -202,12 +389,12 @@ pub fn has_permission(cmd: &Command, client_arc: Arc<Mutex<Client>>, rooms: &Roo
         }
     };
 
-    let rooms_map = lock_rooms(rooms)?;
+    let rooms_map = lock_rooms(rooms);
     let room_arc = rooms_map.get(room).ok_or_else(|| io::Error::other("Room disappeared"))?;
-    let room_guard = lock_room(room_arc)?;
+    let room_guard = lock_room(room_arc);
 
     if !check_role_permissions(&role, cmd_str.as_str(), &room_guard.roles) {
-        let client = lock_client(&client_arc)?;
+        let client = lock_client(&client_arc);
         writeln!(&client.stream, "{}", "You don't have permission to run this command".red())?;
         return Ok(false)
     }
@@ -219,12 +406,12 @@ pub fn has_permission(cmd: &Command, client_arc: Arc<Mutex<Client>>, rooms: &Roo
 
 pub fn sync_user_commands(client_arc: &Arc<Mutex<Client>>, rooms: &Rooms, username: &str, room_name: &str) -> io::Result<()> {
     let extra_cmds = {
-        let rooms_map = lock_rooms(rooms)?;
+        let rooms_map = lock_rooms(rooms);
         let room_arc = match rooms_map.get(room_name) {
             Some(arc) => arc,
             None => return Ok(()),
         };
-        let room_guard = lock_room(room_arc)?;
+        let room_guard = lock_room(room_arc);
         let role = match room_guard.users.get(username) {
             Some(u) => u.role.as_str(),
             None => "user",
@@ -233,7 +420,21 @@ pub fn sync_user_commands(client_arc: &Arc<Mutex<Client>>, rooms: &Rooms, userna
             "moderator" => room_guard.roles.moderator.clone(),
             "user" => room_guard.roles.user.clone(),
             "admin" | "owner" => {
-                RESTRICTED_COMMANDS.iter().map(|s| s.to_string()).collect()
+                let mut cmds: Vec<String> = RESTRICTED_COMMANDS.iter().map(|s| s.to_string()).collect();
+                cmds.push("mod.export".to_string());
+                cmds.push("super.kickall".to_string());
+                cmds.push("super.import-profile".to_string());
+                if role == "owner" {
+                    cmds.push("super.purge".to_string());
+                    cmds.push("super.roles.reset".to_string());
+                    cmds.push("super.roles.copy".to_string());
+                    cmds.push("super.roles.import".to_string());
+                    cmds.push("super.archive".to_string());
+                    cmds.push("super.kick-role".to_string());
+                    cmds.push("super.run".to_string());
+                    cmds.push("super.handoff".to_string());
+                }
+                cmds
             },
             _ => Vec::new(),
         };
@@ -258,7 +459,7 @@ pub fn sync_user_commands(client_arc: &Arc<Mutex<Client>>, rooms: &Rooms, userna
     let mut extra_cmds = extra_cmds;
     extra_cmds.sort();
 
-    let mut c = lock_client(client_arc)?;
+    let mut c = lock_client(client_arc);
     if !extra_cmds.is_empty() {
         writeln!(c.stream, "/CMDS {}", extra_cmds.join(" "))?;
         let _ = c.stream.flush();
@@ -272,7 +473,7 @@ pub fn sync_user_commands(client_arc: &Arc<Mutex<Client>>, rooms: &Rooms, userna
 
 pub fn sync_room_commands(rooms: &Rooms, clients: &Clients, room_name: &str) -> io::Result<()> {
     let affected_clients = {
-        let clients_guard = lock_clients(clients)?;
+        let clients_guard = lock_clients(clients);
         let mut list = Vec::new();
         for client_arc in clients_guard.values() {
             if let Ok(target_c) = client_arc.try_lock() {
@@ -294,12 +495,12 @@ pub fn sync_room_commands(rooms: &Rooms, clients: &Clients, room_name: &str) ->
 
 pub fn sync_room_members(rooms: &Rooms, clients: &Clients, pubkeys: &PublicKeys, room_name: &str) -> io::Result<()> {
     let (online_users, visibility, user_roles) = {
-        let rooms_map = lock_rooms(rooms)?;
+        let rooms_map = lock_rooms(rooms);
         let room_arc = match rooms_map.get(room_name) {
             Some(arc) => Arc::clone(arc),
             None => return Ok(()),
         };
-        let room_guard = lock_room(&room_arc)?;
+        let room_guard = lock_room(&room_arc);
         let mut vis = HashMap::new();
         let mut roles = HashMap::new();
         for uname in &room_guard.online_users {
@@ -323,7 +524,7 @@ pub fn sync_room_members(rooms: &Rooms, clients: &Clients, pubkeys: &PublicKeys,
     };
 
     let client_arcs: Vec<Arc<Mutex<Client>>> = {
-        let clients_guard = lock_clients(clients)?;
+        let clients_guard = lock_clients(clients);
         clients_guard.values().cloned().collect()
     };
 
@@ -339,7 +540,7 @@ pub fn sync_room_members(rooms: &Rooms, clients: &Clients, pubkeys: &PublicKeys,
     }
 
     for arc in client_arcs {
-        let mut c = lock_client(&arc)?;
+        let mut c = lock_client(&arc);
         if let ClientState::InRoom { username: recipient, room: rname, .. } = &c.state {
             if rname != room_name {
                 continue;
@@ -378,7 +579,7 @@ pub fn unix_timestamp(rooms: &Rooms, room_name: &str, username: &str) -> io::Res
         Err(_)  => 0,
     };
 
-    let rooms_map = lock_rooms(rooms)?;
+    let rooms_map = lock_rooms(rooms);
     if let Some(room_arc) = rooms_map.get(room_name) {
         if let Ok(mut room_guard) = room_arc.lock() {
             if let Some(entry) = room_guard.users.get_mut(username) {
@@ -437,3 +638,37 @@ pub fn parse_duration(spec: &str) -> io::Result<u64> {
     }
     Ok(secs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_filename_rejects_traversal() {
+        assert!(is_safe_filename("bans.csv"));
+        assert!(!is_safe_filename(""));
+        assert!(!is_safe_filename("../../users.json"));
+        assert!(!is_safe_filename(".."));
+        assert!(!is_safe_filename("sub/bans.csv"));
+        assert!(!is_safe_filename("sub\\bans.csv"));
+    }
+
+    #[test]
+    fn nickname_length_limits() {
+        assert!(validate_nickname("a").is_ok());
+        assert!(validate_nickname(&"a".repeat(MAX_NICKNAME_LEN)).is_ok());
+        assert!(validate_nickname("").is_err());
+        assert!(validate_nickname(&"a".repeat(MAX_NICKNAME_LEN + 1)).is_err());
+        // The limit counts characters, not bytes
+        assert!(validate_nickname(&"é".repeat(MAX_NICKNAME_LEN)).is_ok());
+    }
+
+    #[test]
+    fn nickname_rejects_disallowed_characters() {
+        assert!(validate_nickname("bad\nnick").is_err());
+        assert!(validate_nickname("tab\tbed").is_err());
+        assert!(validate_nickname("\u{7f}").is_err());
+        assert!(validate_nickname("not:allowed").is_err());
+        assert!(validate_nickname("Fine Name!").is_ok());
+    }
+}
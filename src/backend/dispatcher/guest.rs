@@ -1,20 +1,20 @@
 use std::io::{self, BufReader, Write};
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::fs::File;
 use serde_json::{Value, json};
 use std::sync::{Arc, Mutex};
 use colored::*;
 
 use crate::backend::parser::Command;
-use crate::backend::command_utils::{help_msg_guest, hash_password, verify_password, is_user_logged_in};
-use crate::shared::types::{Client, ClientState, Clients, Rooms};
-use crate::shared::utils::{lock_client, lock_clients, lock_users_storage, load_json, save_json, send_message, send_error, send_success, log_event, broadcast_room_list};
+use crate::backend::command_utils::{help_msg, help_entries_guest, hash_password, validate_password, verify_password, is_user_logged_in, MAX_LOGIN_FAILURES, LOGIN_FAILURE_WINDOW_SECS, LOGIN_LOCKOUT_SECS};
+use crate::shared::types::{Client, ClientState, Clients, LoginFailures, PublicKeys, ResumeTokens, Rooms, RESUME_TOKEN_TTL_SECS};
+use crate::shared::utils::{issue_resume_token, lock_client, lock_clients, lock_login_failures, lock_resume_tokens, lock_users_storage, load_json, save_json, send_message, send_message_locked, send_error, send_success, send_success_locked, log_event, broadcast_room_list};
 use super::CommandResult;
 
-pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms) -> io::Result<CommandResult> {
+pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, login_failures: &LoginFailures, resume_tokens: &ResumeTokens) -> io::Result<CommandResult> {
     match cmd {
-        Command::Help => {
-            send_message(&client, &format!("{}{}", help_msg_guest().bright_blue(), "\x1b[0m"))?;
+        Command::Help { query } => {
+            send_message(&client, &format!("{}{}", help_msg(help_entries_guest(), &query).bright_blue(), "\x1b[0m"))?;
             Ok(CommandResult::Handled)
         }
 
@@ -25,6 +25,12 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
             Ok(CommandResult::Handled)
         }
 
+        Command::Mode { json } => {
+            lock_client(&client).json_output = json;
+            send_success(&client, if json { "Output mode set to json" } else { "Output mode set to text" })?;
+            Ok(CommandResult::Handled)
+        }
+
         Command::PubKey { .. } => {
             send_message(&client, &"Public keys are handled automatically when logging in".yellow().to_string())?;
             Ok(CommandResult::Handled)
@@ -32,40 +38,44 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
 
         Command::Quit => {
             let addr = {
-                let c = lock_client(&client)?;
+                let c = lock_client(&client);
                 c.addr
             };
 
             {
-                let mut clients = lock_clients(clients)?;
+                let mut clients = lock_clients(clients);
                 clients.remove(&addr);
             }
 
-            let mut client = lock_client(&client)?;
-            writeln!(client.stream, "{}", "Exiting...".green())?;
+            let mut client = lock_client(&client);
+            send_success_locked(&mut client, "Exiting...")?;
             client.stream.shutdown(std::net::Shutdown::Both)?;     
             Ok(CommandResult::Stop)
         }
 
-        Command::Leave | Command::Status | Command::AFK | Command::Announce { .. } | Command::Seen { .. } | Command::DM { .. } | Command::Me { .. } |
-        Command::SuperUsers | Command::SuperRename { .. } | Command::SuperExport { .. } | Command::SuperWhitelist | Command::SuperWhitelistToggle | Command::SuperWhitelistAdd { .. } | Command::SuperWhitelistRemove { .. } | Command::SuperLimit | Command::SuperLimitRate { .. } | Command::SuperLimitSession { .. } | Command::SuperRoles | Command::SuperRolesAdd { .. } | Command::SuperRolesRevoke { .. } | Command::SuperRolesAssign { .. } | Command::SuperRolesRecolor { .. } |
-        Command::Users | Command::UsersRename { .. } | Command::UsersRecolor { .. } | Command::UsersHide |
-        Command::ModInfo | Command::ModKick { .. } | Command::ModMute { .. } | Command::ModUnmute { .. } | Command::ModBan { .. } | Command::ModUnban { .. } => {
+        Command::HelpRoles | Command::Leave | Command::Status | Command::Stats | Command::AFK { .. } | Command::Announce { .. } | Command::Seen { .. } | Command::DM { .. } | Command::DmRole { .. } | Command::DmHistory | Command::Reply { .. } | Command::Edit { .. } | Command::Me { .. } |
+        Command::SuperInfo | Command::SuperUsers { .. } | Command::SuperRename { .. } | Command::SuperLock | Command::SuperStaffAlerts | Command::SuperPolicyBanReason { .. } | Command::SuperMotd { .. } | Command::SuperArchive | Command::SuperFreeze | Command::SuperLog { .. } | Command::SuperSlowmode { .. } | Command::SuperPurge { .. } | Command::SuperKickAll { .. } | Command::SuperKickRole { .. } | Command::SuperRun { .. } | Command::SuperHandoff { .. } | Command::SuperImportProfile { .. } | Command::SuperExport { .. } | Command::SuperExportAll | Command::SuperWhitelist | Command::SuperWhitelistToggle | Command::SuperWhitelistEnforce | Command::SuperWhitelistAdd { .. } | Command::SuperWhitelistRemove { .. } | Command::SuperWhitelistImport { .. } | Command::SuperLimit | Command::SuperLimitRate { .. } | Command::SuperLimitSession { .. } | Command::SuperRoles | Command::SuperRolesAdd { .. } | Command::SuperRolesRevoke { .. } | Command::SuperRolesPreview { .. } | Command::SuperRolesAssign { .. } | Command::SuperRolesRecolor { .. } | Command::SuperRolesReset | Command::SuperRolesLabel { .. } | Command::SuperRolesCopy { .. } | Command::SuperRolesExport { .. } | Command::SuperRolesImport { .. } | Command::SuperTagsAdd { .. } | Command::SuperTagsRemove { .. } | Command::SuperFilterList | Command::SuperFilterToggle | Command::SuperFilterAdd { .. } | Command::SuperFilterRemove { .. } | Command::SuperPinList | Command::SuperPinAdd { .. } | Command::SuperPinRemove { .. } | Command::SuperPinClear |
+        Command::Users | Command::UsersCount | Command::UsersRename { .. } | Command::UsersRecolor { .. } | Command::UsersRecolorPreview { .. } | Command::UsersProfile { .. } | Command::UsersHide | Command::UsersWhoami |
+        Command::ModInfo { .. } | Command::ModKick { .. } | Command::ModMute { .. } | Command::ModUnmute { .. } | Command::ModBan { .. } | Command::ModUnban { .. } | Command::ModWhois { .. } | Command::ModExport { .. } | Command::ModHistory { .. } => {
             send_message(&client, &"Must be logged in to perform this command".yellow().to_string())?;
             Ok(CommandResult::Handled)
         }
-        Command::IgnoreList | Command::IgnoreAdd { .. } | Command::IgnoreRemove { .. } => {
+        Command::IgnoreList | Command::IgnoreAdd { .. } | Command::IgnoreRemove { .. } | Command::IgnoreShare | Command::IgnoreImport { .. } => {
             send_message(&client, &"Must be logged in to manage your ignore list".yellow().to_string())?;
             Ok(CommandResult::Handled)
         }
+        Command::BlockList | Command::BlockAdd { .. } | Command::BlockRemove { .. } => {
+            send_message(&client, &"Must be logged in to manage your block list".yellow().to_string())?;
+            Ok(CommandResult::Handled)
+        }
 
         Command::AccountRegister {username, password, confirm} => {
             {
-                let mut c = lock_client(&client)?;
+                let mut c = lock_client(&client);
                 let now = Instant::now();
                 c.login_attempts.retain(|t| now.duration_since(*t).as_secs() < 60);
                 if c.login_attempts.len() >= 5 {
-                    writeln!(c.stream, "{}", "Too many attempts, try again later".yellow())?;
+                    send_message_locked(&mut c, &"Too many attempts, try again later".yellow().to_string())?;
                     return Ok(CommandResult::Handled);
                 }
                 c.login_attempts.push_back(now);
@@ -76,7 +86,12 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
                 return Ok(CommandResult::Handled)
             }
 
-            let _lock = lock_users_storage()?;
+            if let Err(reason) = validate_password(&password) {
+                send_message(&client, &reason.yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+
+            let _lock = lock_users_storage();
 
             let mut users = load_json("data/users.json")?;
             
@@ -89,17 +104,23 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
 
             users[&username] = json!({
                 "password": password_hash,
-                "ignore": []
+                "ignore": [],
+                "dm_block": [],
+                "favorites": [],
+                "created_at": SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
             });
 
             save_json("data/users.json", &users)?;
 
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             let peer = c.addr;
             c.state = ClientState::LoggedIn { username: username.clone() };
+            load_user_lists(&users[&username], &mut c);
+            c.dm_log.clear();
             writeln!(c.stream, "{}", format!("/LOGIN_OK {}", username))?;
+            issue_resume_token(&mut c)?;
 
-            writeln!(c.stream, "{}", format!("User Registered: {username}").green())?;
+            send_success_locked(&mut c, &format!("User Registered: {username}"))?;
             drop(c);
             log_event(&peer, Some(&username), None, "Logged in");
             let _ = broadcast_room_list(clients, rooms, &username);
@@ -108,22 +129,37 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
 
         Command::AccountLogin {username, password} => {
             {
-                let mut c = lock_client(&client)?;
+                let mut c = lock_client(&client);
                 let now = Instant::now();
                 c.login_attempts.retain(|t| now.duration_since(*t).as_secs() < 60);
                 if c.login_attempts.len() >= 5 {
-                    writeln!(c.stream, "{}", "Too many login attempts, try again later".yellow())?;
+                    send_message_locked(&mut c, &"Too many login attempts, try again later".yellow().to_string())?;
                     return Ok(CommandResult::Handled);
                 }
                 c.login_attempts.push_back(now);
             }
 
+            {
+                let mut failures = lock_login_failures(login_failures);
+                if let Some((count, since)) = failures.get(&username) {
+                    let elapsed = since.elapsed().as_secs();
+                    if *count >= MAX_LOGIN_FAILURES && elapsed < LOGIN_LOCKOUT_SECS {
+                        drop(failures);
+                        send_message(&client, &"Account temporarily locked".yellow().to_string())?;
+                        return Ok(CommandResult::Handled);
+                    }
+                    if *count >= MAX_LOGIN_FAILURES || elapsed >= LOGIN_FAILURE_WINDOW_SECS {
+                        failures.remove(&username);
+                    }
+                }
+            }
+
             if is_user_logged_in(clients, &username) {
                 send_message(&client, &format!("Error: {username} is already logged in").yellow().to_string())?;
                 return Ok(CommandResult::Handled);
             }
 
-            let _lock = lock_users_storage()?;
+            let _lock = lock_users_storage();
 
             let users = load_json("data/users.json")?;
 
@@ -137,19 +173,26 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
                         }
                     };
                     if verify_password(&password, stored_hash) {
-                        let mut client = lock_client(&client)?;
+                        lock_login_failures(login_failures).remove(&username);
+                        let mut client = lock_client(&client);
                         let peer = client.addr;
                         client.state = ClientState::LoggedIn { username: username.clone() };
-                        client.ignore_list = user_obj.get("ignore")
-                            .and_then(|v| v.as_array())
-                            .map_or_else(Vec::new, |arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect());
+                        load_user_lists(user_obj, &mut client);
                         writeln!(client.stream, "{}", format!("/LOGIN_OK {}", username))?;
+                        issue_resume_token(&mut client)?;
 
-                        writeln!(client.stream, "{}", format!("Logged in as: {username}").green())?;
+                        send_success_locked(&mut client, &format!("Logged in as: {username}"))?;
                         drop(client);
                         log_event(&peer, Some(&username), None, "Logged in");
                         let _ = broadcast_room_list(clients, rooms, &username);
                     } else {
+                        let mut failures = lock_login_failures(login_failures);
+                        let entry = failures.entry(username.clone()).or_insert((0, Instant::now()));
+                        entry.0 += 1;
+                        if entry.0 >= MAX_LOGIN_FAILURES {
+                            entry.1 = Instant::now();
+                        }
+                        drop(failures);
                         send_message(&client, &"Error: Incorrect password".yellow().to_string())?;
                     }
                 }
@@ -161,6 +204,51 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
             Ok(CommandResult::Handled)
         }
 
+        Command::AccountResume { token } => {
+            let entry = lock_resume_tokens(resume_tokens).remove(&token);
+            let (username, room) = match entry {
+                Some((username, room, dropped)) if dropped.elapsed().as_secs() < RESUME_TOKEN_TTL_SECS => (username, room),
+                _ => {
+                    send_message(&client, &"Error: Invalid or expired resume token".yellow().to_string())?;
+                    return Ok(CommandResult::Handled);
+                }
+            };
+
+            if is_user_logged_in(clients, &username) {
+                send_message(&client, &format!("Error: {username} is already logged in").yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+
+            let _lock = lock_users_storage();
+            let users = load_json("data/users.json")?;
+            let user_obj = match users.get(&username) {
+                Some(u) => u,
+                None => {
+                    send_message(&client, &"Error: Username not found".yellow().to_string())?;
+                    return Ok(CommandResult::Handled);
+                }
+            };
+
+            if let Ok(mut map) = pubkeys.lock() {
+                map.remove(&username);
+            }
+
+            let mut c = lock_client(&client);
+            let peer = c.addr;
+            c.state = ClientState::LoggedIn { username: username.clone() };
+            load_user_lists(user_obj, &mut c);
+            c.dm_log.clear();
+            c.resume_room = room;
+            writeln!(c.stream, "/LOGIN_OK {username}")?;
+            issue_resume_token(&mut c)?;
+
+            send_success_locked(&mut c, &format!("Resumed session as: {username}"))?;
+            drop(c);
+            log_event(&peer, Some(&username), None, "Resumed session");
+            let _ = broadcast_room_list(clients, rooms, &username);
+            Ok(CommandResult::Handled)
+        }
+
         Command::AccountLogout | Command::AccountEditUsername { .. } | Command::AccountEditPassword { .. } => {
             send_message(&client, &"You are not currently logged in".yellow().to_string())?;
             Ok(CommandResult::Handled)
@@ -200,7 +288,7 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
                 }
             };
 
-            let _lock = lock_users_storage()?;
+            let _lock = lock_users_storage();
             let mut users = load_json("data/users.json")?;
 
             if users.get(&username).is_some() {
@@ -226,12 +314,17 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
             Ok(CommandResult::Handled)
         }
 
+        Command::AccountBans => {
+            send_success(&client, "Currently a guest, please register or log into an account to view your bans")?;
+            Ok(CommandResult::Handled)
+        }
+
         Command::Account => {
             send_success(&client, "Must register or log into an account to join a room")?;
             Ok(CommandResult::Handled)
         }
 
-        Command::RoomList | Command::RoomCreate { .. } | Command::RoomJoin { .. } | Command::RoomImport { .. } | Command::RoomDelete { .. } => {
+        Command::RoomList { .. } | Command::RoomSearch { .. } | Command::RoomCreate { .. } | Command::RoomJoin { .. } | Command::RoomImport { .. } | Command::RoomDelete { .. } | Command::RoomHistory { .. } | Command::RoomMembers | Command::RoomTransfer { .. } | Command::RoomFavoriteList | Command::RoomFavoriteAdd { .. } | Command::RoomFavoriteRemove { .. } => {
             send_message(&client, &"Must log in to perform this command".yellow().to_string())?;
             Ok(CommandResult::Handled)
         }
@@ -247,3 +340,15 @@ pub fn guest_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients
         }
     }
 }
+
+// Copies the ignore, DM block, and favorites lists from a user's stored record onto their connection
+fn load_user_lists(user_obj: &Value, client: &mut Client) {
+    let list = |key: &str| -> Vec<String> {
+        user_obj.get(key)
+            .and_then(|v| v.as_array())
+            .map_or_else(Vec::new, |arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+    };
+    client.ignore_list = list("ignore");
+    client.dm_block_list = list("dm_block");
+    client.favorites = list("favorites");
+}
@@ -4,34 +4,69 @@ pub mod superuser_roles;
 pub mod user;
 pub mod messaging;
 
-use std::io::{self, Write};
+use std::io;
 use std::sync::{Arc, Mutex};
 use colored::*;
 
 use crate::backend::parser::Command;
-use crate::backend::command_utils::{help_msg_inroom, has_permission, unix_timestamp, sync_room_members};
+use crate::backend::command_utils::{help_msg, help_entries_inroom, help_msg_role, has_permission, unix_timestamp, sync_room_members};
 use crate::shared::types::{Client, ClientState, Clients, PublicKeys, Rooms};
-use crate::shared::utils::{lock_client, lock_clients, lock_rooms, lock_room, log_event, broadcast_user_list};
+use crate::shared::utils::{send_failure, find_room, lock_client, lock_clients, lock_rooms, lock_room, log_event, broadcast_user_list, send_message, send_error, send_message_locked, send_success_locked};
 use super::CommandResult;
 
-pub fn inroom_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, room: &String, pubkeys: &PublicKeys) -> io::Result<CommandResult> {
+// Server state and the acting user, bundled for handlers that also take several command arguments
+#[derive(Clone, Copy)]
+pub struct RoomCtx<'a> {
+    pub clients: &'a Clients,
+    pub rooms: &'a Rooms,
+    pub pubkeys: &'a PublicKeys,
+    pub username: &'a str,
+    pub room: &'a str,
+}
+
+pub fn leave_room(client: &Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, username: &str, room: &str) -> io::Result<()> {
+    {
+        let rooms_map = lock_rooms(rooms);
+        if let Some(room_arc) = rooms_map.get(room) {
+            if let Ok(mut r) = room_arc.lock() {
+                r.online_users.retain(|u| u != username);
+            }
+        }
+    }
+    let _ = sync_room_members(rooms, clients, pubkeys, room);
+    if let Err(e) = unix_timestamp(rooms, room, username) {
+        eprintln!("Error updating last_seen for {username} in {room}: {e}");
+    }
+    let mut c = lock_client(client);
+    let peer = c.addr;
+    c.state = ClientState::LoggedIn {
+        username: username.to_string()
+    };
+    send_message_locked(&mut c, "/LOBBY_STATE")?;
+    send_success_locked(&mut c, &format!("You have left {room}"))?;
+    log_event(&peer, Some(username), Some(room), &format!("Left room {}", room));
+    let _ = broadcast_user_list(clients, rooms, room);
+    Ok(())
+}
+
+pub fn inroom_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &str, room: &str, pubkeys: &PublicKeys) -> io::Result<CommandResult> {
     if !has_permission(&cmd, client.clone(), rooms, username, room)? {
         return Ok(CommandResult::Handled);
     }
+    let ctx = RoomCtx { clients, rooms, pubkeys, username, room };
 
     match cmd {
-        Command::Help => {
+        Command::Help { query } => {
             let role_cmds: Vec<String> = {
-                let rooms_map = lock_rooms(rooms)?;
-                let room_arc = match rooms_map.get(room) {
-                    Some(arc) => arc,
-                    None => {
-                        let mut c = lock_client(&client)?;
-                        writeln!(c.stream, "{}", "Error: Room not found".red())?;
+                let rooms_map = lock_rooms(rooms);
+                let room_arc = match find_room(&rooms_map, room) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        send_failure(&client, &e)?;
                         return Ok(CommandResult::Handled);
                     }
                 };
-                let room_guard = lock_room(room_arc)?;
+                let room_guard = lock_room(&room_arc);
                 let role = match room_guard.users.get(username) {
                     Some(u) => u.role.as_str(),
                     None => "user",
@@ -47,21 +82,39 @@ pub fn inroom_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Client
                 }
             };
 
-            let mut c = lock_client(&client)?;
             let role_cmds_refs: Vec<&str> = role_cmds.iter().map(|s| s.as_str()).collect();
-            writeln!(c.stream, "{}", help_msg_inroom(role_cmds_refs).bright_blue())?;
+            send_message(&client, &help_msg(help_entries_inroom(role_cmds_refs), &query).bright_blue().to_string())?;
+            Ok(CommandResult::Handled)
+        }
+        Command::HelpRoles => {
+            let msg = {
+                let rooms_map = lock_rooms(rooms);
+                let room_arc = match find_room(&rooms_map, room) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        send_failure(&client, &e)?;
+                        return Ok(CommandResult::Handled);
+                    }
+                };
+                let room_guard = lock_room(&room_arc);
+                let role = match room_guard.users.get(username) {
+                    Some(u) => u.role.as_str(),
+                    None => "user",
+                };
+                help_msg_role(role, &room_guard.roles)
+            };
+
+            send_message(&client, &msg.bright_blue().to_string())?;
             Ok(CommandResult::Handled)
         }
         Command::Ping { start_time }=> {
-            let mut c = lock_client(&client)?;
             if let Some(start_ms) = start_time {
-                writeln!(c.stream, "/PONG {start_ms}")?;
+                send_message(&client, &format!("/PONG {start_ms}"))?;
             }
             Ok(CommandResult::Handled)
         }
         Command::PubKey { .. } => {
-            let mut c = lock_client(&client)?;
-            writeln!(c.stream, "{}", "Public keys are handled automatically when logging in".yellow())?;
+            send_message(&client, &"Public keys are handled automatically when logging in".yellow().to_string())?;
             Ok(CommandResult::Handled)
         }
         Command::Quit => {
@@ -73,11 +126,11 @@ pub fn inroom_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Client
                 pubkeys_map.remove(username);
             }
             let addr = {
-                let c = lock_client(&client)?;
+                let c = lock_client(&client);
                 c.addr
             };
             {
-                let rooms_map = lock_rooms(rooms)?;
+                let rooms_map = lock_rooms(rooms);
                 if let Some(room_arc) = rooms_map.get(room) {
                     if let Ok(mut room_guard) = room_arc.lock() {
                         room_guard.online_users.retain(|u| u != username);
@@ -89,112 +142,137 @@ pub fn inroom_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Client
                 eprintln!("Error updating last_seen for {username} in {room}: {e}");
             }
             {
-                let mut clients_guard = lock_clients(clients)?;
+                let mut clients_guard = lock_clients(clients);
                 clients_guard.remove(&addr);
             }
             
-            let c_guard = lock_client(&client)?;
+            let c_guard = lock_client(&client);
             crate::shared::utils::send_success(&client, "Exiting...")?;
             c_guard.stream.shutdown(std::net::Shutdown::Both)?;
             Ok(CommandResult::Stop)
         }
         Command::Leave => {
-            {
-                let rooms_map = lock_rooms(rooms)?;
-                if let Some(room_arc) = rooms_map.get(room) {
-                    if let Ok(mut r) = room_arc.lock() {
-                        r.online_users.retain(|u| u != username);
-                    }
-                }
-            }
-            let _ = sync_room_members(rooms, clients, pubkeys, room);
-            if let Err(e) = unix_timestamp(rooms, room, username) {
-                eprintln!("Error updating last_seen for {username} in {room}: {e}");
-            }
-            let mut c = lock_client(&client)?;
-            let peer = c.addr;
-            c.state = ClientState::LoggedIn {
-                username: username.clone()
-            };
-            writeln!(c.stream, "{}", format!("/LOBBY_STATE"))?;
-            writeln!(c.stream, "{}", format!("You have left {room}").green())?;
-            log_event(&peer, Some(username), Some(room), &format!("Left room {}", room));
-            let _ = broadcast_user_list(clients, rooms, room);
+            leave_room(&client, clients, rooms, pubkeys, username, room)?;
             Ok(CommandResult::Handled)
         }
         Command::Status => {
-            let rooms_map = lock_rooms(rooms)?;
-            let room_arc = match rooms_map.get(room) {
-                Some(r) => Arc::clone(r),
-                None => {
-                    let mut c = lock_client(&client)?;
-                    writeln!(c.stream, "{}", format!("Room {room} not found").yellow())?;
+            let rooms_map = lock_rooms(rooms);
+            let room_arc = match find_room(&rooms_map, room) {
+                Ok(r) => r,
+                Err(e) => {
+                    send_failure(&client, &e)?;
                     return Ok(CommandResult::Handled);
                 }
             };
-            let room_guard = lock_room(&room_arc)?;
-            let role = room_guard.users.get(username).map(|u| u.role.clone()).unwrap_or_else(|| "user".to_string());
+            let room_guard = lock_room(&room_arc);
+            let role = room_guard.users.get(username).map(|u| u.role.as_str()).unwrap_or("user");
+            let role = room_guard.role_label(role);
             let online: Vec<&String> = room_guard.online_users.iter().collect();
-            let mut c = lock_client(&client)?;
-            writeln!(c.stream, "{}", format!("Room: {} | Role: {} | Online: {}", room, role, online.len()).cyan())?;
+            send_message(&client, &format!("Room: {} | Role: {} | Online: {}", room, role, online.len()).cyan().to_string())?;
             Ok(CommandResult::Handled)
         }
-        Command::IgnoreList => {
+        Command::Stats | Command::Mode { .. } | Command::DmHistory | Command::BlockList | Command::BlockAdd { .. } | Command::BlockRemove { .. } | Command::RoomFavoriteList | Command::RoomFavoriteAdd { .. } | Command::RoomFavoriteRemove { .. } => {
+            crate::backend::dispatcher::loggedin::loggedin_command(cmd, client, clients, rooms, username, pubkeys)
+        }
+        Command::IgnoreList | Command::IgnoreShare => {
             crate::backend::dispatcher::loggedin::loggedin_command(cmd, client, clients, rooms, username, pubkeys)
         }
-        Command::IgnoreAdd { .. } | Command::IgnoreRemove { .. } => {
+        Command::IgnoreAdd { .. } | Command::IgnoreRemove { .. } | Command::IgnoreImport { .. } => {
             let res = crate::backend::dispatcher::loggedin::loggedin_command(cmd, client, clients, rooms, username, pubkeys)?;
             let _ = sync_room_members(rooms, clients, pubkeys, room);
             Ok(res)
         }
-        Command::AFK => messaging::handle_afk(client, clients, rooms, username, room),
-        Command::DM { recipient, message } => messaging::handle_dm(client, clients, rooms, username, room, &recipient, &message),
-        Command::Me { action } => messaging::handle_me(client, clients, rooms, username, room, &action),
-        Command::Seen { username: target } => messaging::handle_seen(client, rooms, room, &target),
-        Command::Announce { message } => messaging::handle_announce(client, clients, rooms, username, room, &message),
-        Command::AccountRegister { .. } | Command::AccountLogin { .. } | Command::Account | Command::AccountDelete { .. } | Command::AccountEditPassword { .. } | Command::AccountEditUsername { .. } | Command::AccountExport { .. } | Command::AccountImport { .. } | Command::RoomList | Command::AccountLogout => {
-            let mut c = lock_client(&client)?;
-            writeln!(c.stream, "{}", "Cannot use this command while in a room. Leave the room first.".yellow())?;
+        Command::AFK { reason } => messaging::handle_afk(client, ctx, &reason),
+        Command::DM { recipient, message } => messaging::handle_dm(client, ctx, &recipient, &message),
+        Command::DmRole { role, message } => messaging::handle_dm_role(client, ctx, &role, &message),
+        Command::Edit { recipient, ciphertext, is_first } => messaging::handle_edit(client, ctx, &recipient, &ciphertext, is_first),
+        Command::Reply { message } => messaging::handle_reply(client, ctx, &message),
+        Command::Me { action, target } => messaging::handle_me(client, ctx, &action, target.as_deref()),
+        Command::Seen { username: target } => messaging::handle_seen(client, ctx, &target),
+        Command::Announce { message, scope } => messaging::handle_announce(client, ctx, &message, scope),
+        Command::RoomHistory { count } => messaging::handle_history(client, ctx, count),
+        Command::RoomTransfer { username: target } => superuser_roles::handle_room_transfer(client, ctx, &target),
+        Command::SuperHandoff { username: target } => superuser_roles::handle_super_handoff(client, ctx, &target),
+        Command::AccountRegister { .. } | Command::AccountLogin { .. } | Command::AccountResume { .. } | Command::Account | Command::AccountDelete { .. } | Command::AccountEditPassword { .. } | Command::AccountEditUsername { .. } | Command::AccountExport { .. } | Command::AccountImport { .. } | Command::AccountBans | Command::RoomList { .. } | Command::RoomSearch { .. } | Command::AccountLogout => {
+            send_message(&client, &"Cannot use this command while in a room. Leave the room first.".yellow().to_string())?;
             Ok(CommandResult::Handled)
         }
-        Command::SuperUsers => superuser::handle_super_users(client, clients, rooms, room),
-        Command::SuperRename { name: new_name } => superuser::handle_super_rename(client, clients, rooms, room, &new_name),
-        Command::SuperExport { filename } => superuser::handle_super_export(client, rooms, room, &filename),
-        Command::SuperWhitelist => superuser::handle_super_whitelist(client, rooms, room),
-        Command::SuperWhitelistToggle => superuser::handle_super_whitelist_toggle(client, clients, rooms, room),
-        Command::SuperWhitelistAdd { users } => superuser::handle_super_whitelist_add(client, clients, rooms, room, &users),
-        Command::SuperWhitelistRemove { users } => superuser::handle_super_whitelist_remove(client, clients, rooms, room, &users),
-        Command::SuperLimit => superuser::handle_super_limit(client, rooms, room),
-        Command::SuperLimitRate { limit } => superuser::handle_super_limit_rate(client, rooms, room, limit),
-        Command::SuperLimitSession { limit } => superuser::handle_super_limit_session(client, rooms, room, limit),
-        Command::SuperRoles => superuser_roles::handle_super_roles(client, rooms, room),
-        Command::SuperRolesAdd { role, commands } => superuser_roles::handle_super_roles_add(client, clients, rooms, room, &role, &commands),
-        Command::SuperRolesRevoke { role, commands } => superuser_roles::handle_super_roles_revoke(client, clients, rooms, room, &role, &commands),
-        Command::SuperRolesAssign { role, users } => superuser_roles::handle_super_roles_assign(client, clients, rooms, pubkeys, room, &role, &users),
-        Command::SuperRolesRecolor { role, color } => superuser_roles::handle_super_roles_recolor(client, clients, rooms, pubkeys, room, &role, &color),
-        Command::Users => user::handle_users(client, rooms, room),
-        Command::UsersRename { name } => user::handle_users_rename(client, clients, rooms, pubkeys, room, username, &name),
-        Command::UsersRecolor { color } => user::handle_users_recolor(client, clients, rooms, pubkeys, room, username, &color),
-        Command::UsersHide => user::handle_users_hide(client, clients, rooms, pubkeys, username, room),
-        Command::ModInfo => moderation::handle_mod_info(client, rooms, room),
-        Command::ModKick { username: target, reason } => moderation::handle_mod_kick(client, clients, rooms, pubkeys, username, room, &target, reason),
-        Command::ModBan { username: target, duration, reason } => moderation::handle_mod_ban(client, clients, rooms, pubkeys, username, room, &target, duration, reason),
-        Command::ModUnban { username: target } => moderation::handle_mod_unban(client, rooms, username, room, &target),
-        Command::ModMute { username: target, duration, reason } => moderation::handle_mod_mute(client, clients, rooms, username, room, &target, duration, reason),
-        Command::ModUnmute { username: target } => moderation::handle_mod_unmute(client, clients, rooms, username, room, &target),
+        Command::SuperInfo => superuser::handle_super_info(client, ctx),
+        Command::SuperUsers { filter } => superuser::handle_super_users(client, ctx, filter.as_deref()),
+        Command::SuperRename { name: new_name, force } => superuser::handle_super_rename(client, ctx, &new_name, force),
+        Command::SuperLock => superuser::handle_super_lock(client, ctx),
+        Command::SuperStaffAlerts => superuser::handle_super_staffalerts(client, ctx),
+        Command::SuperMotd { text } => superuser::handle_super_motd(client, ctx, text.as_deref()),
+        Command::SuperPolicyBanReason { enabled } => superuser::handle_super_policy_banreason(client, ctx, enabled),
+        Command::SuperArchive => superuser::handle_super_archive(client, ctx),
+        Command::SuperFreeze => superuser::handle_super_freeze(client, ctx),
+        Command::SuperLog { count } => superuser::handle_super_log(client, ctx, count),
+        Command::SuperSlowmode { secs } => superuser::handle_super_slowmode(client, ctx, secs),
+        Command::SuperPurge { days, dry } => superuser::handle_super_purge(client, ctx, days, dry),
+        Command::SuperKickAll { reason, dry } => superuser::handle_super_kickall(client, ctx, &reason, dry),
+        Command::SuperKickRole { role } => superuser::handle_super_kick_role(client, ctx, &role),
+        Command::SuperRun { filename } => superuser::handle_super_run(client, ctx, &filename),
+        Command::SuperImportProfile { target, source } => superuser::handle_super_import_profile(client, ctx, &target, &source),
+        Command::SuperExport { filename, section } => superuser::handle_super_export(client, ctx, &filename, section.as_deref()),
+        Command::SuperExportAll => superuser::handle_super_export_all(client, ctx),
+        Command::SuperWhitelist => superuser::handle_super_whitelist(client, ctx),
+        Command::SuperWhitelistToggle => superuser::handle_super_whitelist_toggle(client, ctx),
+        Command::SuperWhitelistEnforce => superuser::handle_super_whitelist_enforce(client, ctx),
+        Command::SuperWhitelistAdd { users } => superuser::handle_super_whitelist_add(client, ctx, &users),
+        Command::SuperWhitelistRemove { users } => superuser::handle_super_whitelist_remove(client, ctx, &users),
+        Command::SuperWhitelistImport { filename } => superuser::handle_super_whitelist_import(client, ctx, &filename),
+        Command::SuperLimit => superuser::handle_super_limit(client, ctx),
+        Command::SuperLimitRate { limit } => superuser::handle_super_limit_rate(client, ctx, limit),
+        Command::SuperLimitSession { limit } => superuser::handle_super_limit_session(client, ctx, limit),
+        Command::SuperRoles => superuser_roles::handle_super_roles(client, ctx),
+        Command::SuperRolesAdd { role, commands } => superuser_roles::handle_super_roles_add(client, ctx, &role, &commands),
+        Command::SuperRolesRevoke { role, commands } => superuser_roles::handle_super_roles_revoke(client, ctx, &role, &commands),
+        Command::SuperRolesPreview { role, commands } => superuser_roles::handle_super_roles_preview(client, ctx, &role, &commands),
+        Command::SuperRolesAssign { role, users, duration } => superuser_roles::handle_super_roles_assign(client, ctx, &role, &users, duration),
+        Command::SuperRolesRecolor { role, color } => superuser_roles::handle_super_roles_recolor(client, ctx, &role, &color),
+        Command::SuperRolesReset => superuser_roles::handle_super_roles_reset(client, ctx),
+        Command::SuperRolesCopy { source, colors } => superuser_roles::handle_super_roles_copy(client, ctx, &source, colors),
+        Command::SuperRolesExport { filename } => superuser_roles::handle_super_roles_export(client, ctx, &filename),
+        Command::SuperRolesImport { filename } => superuser_roles::handle_super_roles_import(client, ctx, &filename),
+        Command::SuperRolesLabel { role, label } => superuser_roles::handle_super_roles_label(client, ctx, &role, &label),
+        Command::SuperTagsAdd { tags } => superuser::handle_super_tags_add(client, ctx, &tags),
+        Command::SuperTagsRemove { tags } => superuser::handle_super_tags_remove(client, ctx, &tags),
+        Command::SuperFilterList => superuser::handle_super_filter_list(client, ctx),
+        Command::SuperFilterToggle => superuser::handle_super_filter_toggle(client, ctx),
+        Command::SuperFilterAdd { words } => superuser::handle_super_filter_add(client, ctx, &words),
+        Command::SuperFilterRemove { words } => superuser::handle_super_filter_remove(client, ctx, &words),
+        Command::SuperPinList => superuser::handle_super_pin_list(client, ctx),
+        Command::SuperPinAdd { text } => superuser::handle_super_pin_add(client, ctx, &text),
+        Command::SuperPinRemove { index } => superuser::handle_super_pin_remove(client, ctx, index),
+        Command::SuperPinClear => superuser::handle_super_pin_clear(client, ctx),
+        Command::Users => user::handle_users(client, ctx),
+        Command::UsersCount => user::handle_users_count(client, ctx),
+        Command::UsersWhoami => user::handle_users_whoami(client, ctx),
+        Command::RoomMembers => user::handle_room_members(client, ctx),
+        Command::UsersRename { name } => user::handle_users_rename(client, ctx, &name),
+        Command::UsersRecolor { color } => user::handle_users_recolor(client, ctx, &color),
+        Command::UsersRecolorPreview { color } => user::handle_users_recolor_preview(client, ctx, &color),
+        Command::UsersProfile { nick, color } => user::handle_users_profile(client, ctx, &nick, &color),
+        Command::UsersHide => user::handle_users_hide(client, ctx),
+        Command::ModInfo { page } => moderation::handle_mod_info(client, ctx, page),
+        Command::ModKick { username: target, reason } => moderation::handle_mod_kick(client, ctx, &target, reason),
+        Command::ModBan { username: target, duration, reason } => moderation::handle_mod_ban(client, ctx, &target, duration, reason),
+        Command::ModUnban { users } => moderation::handle_mod_unban(client, ctx, &users),
+        Command::ModMute { username: target, duration, reason } => moderation::handle_mod_mute(client, ctx, &target, duration, reason),
+        Command::ModUnmute { users } => moderation::handle_mod_unmute(client, ctx, &users),
+        Command::ModWhois { username: target } => moderation::handle_mod_whois(client, ctx, &target),
+        Command::ModExport { filename } => moderation::handle_mod_export(client, ctx, &filename),
+        Command::ModHistory { username: target } => moderation::handle_mod_history(client, ctx, &target),
         Command::RoomJoin { .. } | Command::RoomCreate { .. } | Command::RoomDelete { .. } | Command::RoomImport { .. } => {
-            let mut c = lock_client(&client)?;
-            writeln!(c.stream, "{}", "You are already in a room. Use /leave first to switch rooms.".yellow())?;
+            send_message(&client, &"You are already in a room. Use /leave first to switch rooms.".yellow().to_string())?;
             Ok(CommandResult::Handled)
         }
         Command::InvalidSyntax { err_msg } => {
-            let mut c = lock_client(&client)?;
-            writeln!(c.stream, "{}", err_msg)?;
+            send_message(&client, &err_msg)?;
             Ok(CommandResult::Handled)
         }
         Command::Unavailable => {
-            let mut c = lock_client(&client)?;
-            writeln!(c.stream, "{}", "Command not available, use /help to see available commands".red())?;
+            send_error(&client, "Command not available, use /help to see available commands")?;
             Ok(CommandResult::Handled)
         }
     }
@@ -3,51 +3,101 @@ use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 use colored::*;
 
-use crate::shared::types::{Client, ClientState, Clients, Rooms};
-use crate::shared::utils::{lock_client, lock_clients, lock_rooms, lock_room, check_mute, send_error, send_message, send_success, broadcast_message, broadcast_user_list};
+use crate::shared::types::{Client, ClientState, MAILBOX_LIMIT};
+use crate::shared::utils::{is_archived, send_failure, find_room, format_duration, log_dm, store_mail, lock_client, lock_clients, lock_rooms, lock_room, check_mute, check_filter, check_rate_limit, format_broadcast, send_error, send_message, send_message_locked, send_success, broadcast_message, broadcast_user_list};
+use crate::backend::command_utils::{resolve_username, MAX_MESSAGE_BYTES};
+use crate::backend::dispatcher::inroom::moderation::role_rank;
 use crate::backend::dispatcher::CommandResult;
+use crate::backend::dispatcher::inroom::RoomCtx;
 
-pub fn handle_afk(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, _username: &String, room: &String) -> io::Result<CommandResult> {
-    let mut c = lock_client(&client)?;
-    if let ClientState::InRoom { is_afk, .. } = &mut c.state {
-        *is_afk = true;
-    }
+pub fn handle_afk(client: Arc<Mutex<Client>>, ctx: RoomCtx, reason: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
+    let mut c = lock_client(&client);
+    let now_afk = match &mut c.state {
+        ClientState::InRoom { is_afk, afk_reason, .. } => {
+            if reason == "back" || (reason.is_empty() && *is_afk) {
+                *is_afk = false;
+                afk_reason.clear();
+            } else {
+                *is_afk = true;
+                *afk_reason = reason.to_string();
+            }
+            *is_afk
+        }
+        _ => return Ok(CommandResult::Handled),
+    };
     use std::io::Write;
-    writeln!(c.stream, "{}", "You are now set as AFK".yellow())?;
+    if !now_afk {
+        writeln!(c.stream, "{}", "You are no longer AFK".green())?;
+    } else if reason.is_empty() {
+        writeln!(c.stream, "{}", "You are now set as AFK".yellow())?;
+    } else {
+        writeln!(c.stream, "{}", format!("You are now set as AFK: {reason}").yellow())?;
+    }
     drop(c);
     let _ = broadcast_user_list(clients, rooms, room);
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_dm(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, room: &String, recipient: &String, message: &String) -> io::Result<CommandResult> {
+#[derive(PartialEq)]
+enum DmOutcome {
+    Delivered,
+    Dropped,
+    WriteFailed,
+    Offline,
+}
+
+pub fn handle_dm(client: Arc<Mutex<Client>>, ctx: RoomCtx, recipient: &str, message: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    if is_archived(rooms, room) {
+        send_error(&client, "This room is archived")?;
+        return Ok(CommandResult::Handled);
+    }
     if let Some(msg) = check_mute(rooms, room, username)? {
         send_error(&client, &msg)?;
         return Ok(CommandResult::Handled);
     }
+    if message.len() > MAX_MESSAGE_BYTES {
+        send_error(&client, "Message too long")?;
+        return Ok(CommandResult::Handled);
+    }
+    if check_filter(rooms, room, message)? {
+        send_error(&client, "Message blocked by room filter")?;
+        return Ok(CommandResult::Handled);
+    }
 
     let room_arc = {
-        let rooms_map = lock_rooms(rooms)?;
-        match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                send_message(&client, &format!("Room {room} not found").yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         }
     };
 
-    let is_online = {
-        let room_guard = lock_room(&room_arc)?;
-        room_guard.online_users.contains(recipient)
+    let (is_online, is_member) = {
+        let room_guard = lock_room(&room_arc);
+        (room_guard.online_users.iter().any(|u| u == recipient), room_guard.users.contains_key(recipient))
     };
 
     if !is_online {
-        send_message(&client, &format!("{recipient} is not currently online").yellow().to_string())?;
+        if !is_member {
+            send_message(&client, &format!("{recipient} is not currently online").yellow().to_string())?;
+        } else if store_mail(recipient, username, room, message)? {
+            let mut c = lock_client(&client);
+            log_dm(&mut c, recipient, message, true);
+            drop(c);
+            send_success(&client, &format!("{recipient} is offline, message will be delivered when they next join {room}"))?;
+        } else {
+            send_error(&client, &format!("{recipient}'s mailbox is full (max {MAILBOX_LIMIT} messages)"))?;
+        }
         return Ok(CommandResult::Handled);
     }
 
-    let clients_map = lock_clients(clients)?;
-    let mut found = false;
+    let clients_map = lock_clients(clients);
+    let mut outcome = DmOutcome::Offline;
     for client_arc in clients_map.values() {
         let mut c = match client_arc.lock() {
             Ok(guard) => guard,
@@ -60,49 +110,271 @@ pub fn handle_dm(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, u
         match &c.state {
             ClientState::InRoom { username: uname, room: rname, .. }
                 if uname == recipient && rname == room => {
-                    if c.ignore_list.contains(username) {
-                        found = true;
+                    if c.ignore_list.iter().any(|u| u == username) || c.dm_block_list.iter().any(|u| u == username) {
+                        outcome = DmOutcome::Dropped;
                         break;
                     }
 
                     use std::io::Write;
-                    writeln!(c.stream, "{}", format!("(Private) {username}: {message}").cyan().italic())?;
-                    found = true;
+                    if writeln!(c.stream, "{}", format!("(Private) {username}: {message}").cyan().italic()).is_err() {
+                        outcome = DmOutcome::WriteFailed;
+                        break;
+                    }
+                    c.last_dm_from = Some(username.to_string());
+                    log_dm(&mut c, username, message, false);
+                    outcome = DmOutcome::Delivered;
                     break;
                 }
             _ => continue,
         }
     }
 
-    if found {
-        send_success(&client, &format!("Message sent to {recipient}"))?;
-    } else {
-        send_error(&client, &format!("Failed to deliver message to {recipient}"))?;
+    drop(clients_map);
+
+    if outcome == DmOutcome::Delivered {
+        let mut c = lock_client(&client);
+        log_dm(&mut c, recipient, message, true);
+    }
+
+    match outcome {
+        DmOutcome::Delivered | DmOutcome::Dropped => send_success(&client, &format!("Message delivered to {recipient}"))?,
+        DmOutcome::WriteFailed => send_error(&client, &format!("Failed to deliver message to {recipient}: connection lost"))?,
+        DmOutcome::Offline => send_message(&client, &format!("{recipient} is not currently online").yellow().to_string())?,
     }
 
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_me(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, room: &String, action: &String) -> io::Result<CommandResult> {
+pub fn handle_dm_role(client: Arc<Mutex<Client>>, ctx: RoomCtx, min_role: &str, message: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    if is_archived(rooms, room) {
+        send_error(&client, "This room is archived")?;
+        return Ok(CommandResult::Handled);
+    }
     if let Some(msg) = check_mute(rooms, room, username)? {
         send_error(&client, &msg)?;
         return Ok(CommandResult::Handled);
     }
-    let msg = format!("* {username} {action}").bright_green().to_string();
-    broadcast_message(clients, room, username, &msg, true, false)?;
+    if message.len() > MAX_MESSAGE_BYTES {
+        send_error(&client, "Message too long")?;
+        return Ok(CommandResult::Handled);
+    }
+    if check_filter(rooms, room, message)? {
+        send_error(&client, "Message blocked by room filter")?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+    };
+
+    let staff: Vec<String> = {
+        let room_guard = lock_room(&room_arc);
+        room_guard.users.iter()
+            .filter(|(name, rec)| *name != username && role_rank(&rec.role) >= role_rank(min_role) && room_guard.online_users.contains(*name))
+            .map(|(name, _)| name.clone())
+            .collect()
+    };
+
+    let (label, group) = if min_role == "admin" { ("admins", "@admins") } else { ("mods", "@mods") };
+    if staff.is_empty() {
+        send_message(&client, &format!("No other {label} are currently online").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let clients_map = lock_clients(clients);
+    let mut reached = 0;
+    for client_arc in clients_map.values() {
+        let mut c = match client_arc.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                eprintln!("Client lock poisoned: {poisoned}");
+                continue;
+            }
+        };
+
+        match &c.state {
+            ClientState::InRoom { username: uname, room: rname, .. }
+                if rname == room && staff.contains(uname) => {
+                    if c.ignore_list.iter().any(|u| u == username) || c.dm_block_list.iter().any(|u| u == username) {
+                        reached += 1;
+                        continue;
+                    }
+
+                    use std::io::Write;
+                    if writeln!(c.stream, "{}", format!("(Private to {label}) {username}: {message}").cyan().italic()).is_err() {
+                        continue;
+                    }
+                    c.last_dm_from = Some(username.to_string());
+                    log_dm(&mut c, username, message, false);
+                    reached += 1;
+                }
+            _ => continue,
+        }
+    }
+
+    drop(clients_map);
+
+    if reached == 0 {
+        send_error(&client, &format!("Failed to deliver message to {label}: connection lost"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    {
+        let mut c = lock_client(&client);
+        log_dm(&mut c, group, message, true);
+    }
+    send_success(&client, &format!("Message delivered to {reached} staff member(s) ({group})"))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_edit(client: Arc<Mutex<Client>>, ctx: RoomCtx, recipient: &str, ciphertext: &str, is_first: bool) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    // Same gate as the /enc relay, so editing can't be used to get around the size cap, mutes, the rate limit, or slowmode
+    if ciphertext.len() > MAX_MESSAGE_BYTES {
+        if is_first {
+            send_error(&client, "Message too long")?;
+        }
+        return Ok(CommandResult::Handled);
+    }
+    if is_archived(rooms, room) {
+        if is_first {
+            send_error(&client, "This room is archived")?;
+        }
+        return Ok(CommandResult::Handled);
+    }
+    if let Some(msg) = check_mute(rooms, room, username)? {
+        if is_first {
+            send_error(&client, &msg)?;
+        }
+        return Ok(CommandResult::Handled);
+    }
+    if !check_rate_limit(&client, rooms, is_first)? {
+        return Ok(CommandResult::Handled);
+    }
+
+    let msg_id = {
+        let c = lock_client(&client);
+        c.last_msg_id
+    };
+
+    if msg_id == 0 {
+        if is_first {
+            send_message(&client, &"No message to edit".yellow().to_string())?;
+        }
+        return Ok(CommandResult::Handled);
+    }
+
+    let (role_prefix, display_name) = format_broadcast(rooms, room, username)?;
+
+    let rec_arc = {
+        let clients_map = lock_clients(clients);
+        clients_map.values().find(|arc| {
+            match arc.lock() {
+                Ok(c) => matches!(&c.state,
+                    ClientState::InRoom { username: u, room: r, .. }
+                    if u == recipient && r == room),
+                Err(_) => false,
+            }
+        }).cloned()
+    };
+
+    if let Some(rec_arc) = rec_arc {
+        let mut rec = lock_client(&rec_arc);
+        if rec.ignore_list.iter().any(|u| u == username) || rec.dm_block_list.iter().any(|u| u == username) {
+            return Ok(CommandResult::Handled);
+        }
+        send_message_locked(&mut rec, &format!("/edit {username}:{msg_id} {role_prefix} {display_name}: {ciphertext}"))?;
+    }
+
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_seen(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String, username: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
+pub fn handle_reply(client: Arc<Mutex<Client>>, ctx: RoomCtx, message: &str) -> io::Result<CommandResult> {
+    let last_sender = {
+        let c = lock_client(&client);
+        c.last_dm_from.clone()
+    };
+
+    match last_sender {
+        Some(recipient) => handle_dm(client, ctx, &recipient, message),
         None => {
-            send_message(&client, &"Room not found".yellow().to_string())?;
+            send_message(&client, &"No one to reply to".yellow().to_string())?;
+            Ok(CommandResult::Handled)
+        }
+    }
+}
+
+pub fn handle_me(client: Arc<Mutex<Client>>, ctx: RoomCtx, action: &str, target: Option<&str>) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    if is_archived(rooms, room) {
+        send_error(&client, "This room is archived")?;
+        return Ok(CommandResult::Handled);
+    }
+    if let Some(msg) = check_mute(rooms, room, username)? {
+        send_error(&client, &msg)?;
+        return Ok(CommandResult::Handled);
+    }
+    if action.len() > MAX_MESSAGE_BYTES {
+        send_error(&client, "Message too long")?;
+        return Ok(CommandResult::Handled);
+    }
+    if check_filter(rooms, room, action)? {
+        send_error(&client, "Message blocked by room filter")?;
+        return Ok(CommandResult::Handled);
+    }
+    let target = match target {
+        Some(name) => {
+            let online = {
+                let rooms_map = lock_rooms(rooms);
+                match rooms_map.get(room) {
+                    Some(room_arc) => {
+                        let room_guard = lock_room(room_arc);
+                        resolve_username(&room_guard, name).filter(|u| room_guard.online_users.contains(u))
+                    }
+                    None => None,
+                }
+            };
+            match online {
+                Some(resolved) => Some(resolved),
+                None => {
+                    send_message(&client, &"No such user in room".yellow().to_string())?;
+                    return Ok(CommandResult::Handled);
+                }
+            }
+        }
+        None => None,
+    };
+    if !check_rate_limit(&client, rooms, true)? {
+        return Ok(CommandResult::Handled);
+    }
+    let msg = match target {
+        Some(target) => format!("{}{}", format!("* {username} {action} ").bright_green(), format!("@{target}").bright_cyan().bold()),
+        None => format!("* {username} {action}").bright_green().to_string(),
+    };
+    broadcast_message(clients, room, username, &msg, true, false, None)?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_seen(client: Arc<Mutex<Client>>, ctx: RoomCtx, username: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
-    let room_guard = lock_room(&room_arc)?;
+    let room_guard = lock_room(&room_arc);
+    let username = &resolve_username(&room_guard, username).unwrap_or_else(|| username.to_string());
 
     let is_online = room_guard.online_users.iter().any(|u| u == username);
 
@@ -115,12 +387,8 @@ pub fn handle_seen(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String, use
                     Ok(d)  => d.as_secs(),
                     Err(_) => 0,
                 };
-                let diff = now_secs.saturating_sub(info.last_seen);
-                let days = diff / 86_400;
-                let hrs  = (diff % 86_400) / 3_600;
-                let mins = (diff % 3_600) / 60;
-                let secs = diff % 60;
-                format!("{username} was last seen {days}d {hrs}h {mins}m {secs}s ago").green().to_string()
+                let diff = format_duration(now_secs.saturating_sub(info.last_seen));
+                format!("{username} was last seen {diff} ago").green().to_string()
             }
             None => format!("{username} has never joined this room").yellow().to_string(),
         }
@@ -130,12 +398,94 @@ pub fn handle_seen(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String, use
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_announce(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, room: &String, message: &String) -> io::Result<CommandResult> {
+pub fn handle_history(client: Arc<Mutex<Client>>, ctx: RoomCtx, count: usize) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+    };
+
+    let entries: Vec<String> = {
+        let room_guard = lock_room(&room_arc);
+        // Same visibility rule as the member list: only Admins and Owners see hidden users
+        let role = room_guard.users.get(username).map(|u| u.role.as_str()).unwrap_or("user");
+        let can_see_hidden = role == "owner" || role == "admin";
+        let visible: Vec<_> = room_guard.history.iter().filter(|entry| {
+            can_see_hidden || entry.sender == username || !room_guard.users.get(&entry.sender).is_some_and(|u| u.hidden)
+        }).collect();
+        let skip = visible.len().saturating_sub(count);
+        visible.into_iter().skip(skip).map(|entry| {
+            let when = match chrono::DateTime::from_timestamp(entry.timestamp as i64, 0) {
+                Some(dt) => dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+                None => entry.timestamp.to_string(),
+            };
+            format!("> [{when}] {}", entry.sender)
+        }).collect()
+    };
+
+    if entries.is_empty() {
+        send_message(&client, &"No message history for this room".yellow().to_string())?;
+    } else {
+        send_success(&client, &format!("Recent activity in {room} ({} of {}):\n{}", entries.len(), count, entries.join("\n")))?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_announce(client: Arc<Mutex<Client>>, ctx: RoomCtx, message: &str, scope: Option<String>) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    if is_archived(rooms, room) {
+        send_error(&client, "This room is archived")?;
+        return Ok(CommandResult::Handled);
+    }
     if let Some(msg) = check_mute(rooms, room, username)? {
         send_error(&client, &msg)?;
         return Ok(CommandResult::Handled);
     }
-    let msg = format!("Announcement: {message}").bright_yellow().to_string();
-    broadcast_message(clients, room, username, &msg, true, true)?;
+    if message.len() > MAX_MESSAGE_BYTES {
+        send_error(&client, "Message too long")?;
+        return Ok(CommandResult::Handled);
+    }
+    if check_filter(rooms, room, message)? {
+        send_error(&client, "Message blocked by room filter")?;
+        return Ok(CommandResult::Handled);
+    }
+    if !check_rate_limit(&client, rooms, true)? {
+        return Ok(CommandResult::Handled);
+    }
+
+    let recipients: Option<Vec<String>> = match &scope {
+        Some(min_role) => {
+            let room_arc = {
+                let rooms_map = lock_rooms(rooms);
+                match find_room(&rooms_map, room) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        send_failure(&client, &e)?;
+                        return Ok(CommandResult::Handled);
+                    }
+                }
+            };
+            let room_guard = lock_room(&room_arc);
+            Some(room_guard.users.iter()
+                .filter(|(_, rec)| role_rank(&rec.role) >= role_rank(min_role))
+                .map(|(name, _)| name.clone())
+                .collect())
+        }
+        None => None,
+    };
+
+    let msg = match scope.as_deref() {
+        Some("admin") => format!("Announcement (admins): {message}"),
+        Some(_) => format!("Announcement (mods): {message}"),
+        None => format!("Announcement: {message}"),
+    }.bright_yellow().to_string();
+    broadcast_message(clients, room, username, &msg, true, true, recipients.as_deref())?;
     Ok(CommandResult::Handled)
 }
@@ -3,10 +3,11 @@ use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 use colored::*;
 
-use crate::backend::command_utils::{parse_duration, sync_room_members};
-use crate::shared::types::{Client, ClientState, Clients, RoomUser, Rooms, PublicKeys};
-use crate::shared::utils::{lock_client, lock_clients, lock_room, lock_rooms, save_rooms_to_disk, send_message_locked, send_error_locked, send_success_locked, log_event, broadcast_user_list};
+use crate::backend::command_utils::{is_safe_filename, parse_duration, resolve_room_username, resolve_username, sync_room_members, MOD_INFO_PAGE_SIZE};
+use crate::shared::types::{Client, ClientState, RoomUser};
+use crate::shared::utils::{find_room, send_failure, format_duration, lock_client, lock_clients, lock_room, lock_rooms, save_rooms_to_disk, send_message_locked, send_error_locked, send_success_locked, log_event, log_audit, broadcast_user_list};
 use crate::backend::dispatcher::CommandResult;
+use crate::backend::dispatcher::inroom::RoomCtx;
 
 pub fn role_rank(role: &str) -> u8 {
     match role {
@@ -17,24 +18,24 @@ pub fn role_rank(role: &str) -> u8 {
     }
 }
 
-pub fn handle_mod_info(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) -> io::Result<CommandResult> {
+pub fn handle_mod_info(client: Arc<Mutex<Client>>, ctx: RoomCtx, page: usize) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
     let mut changed = false;
     let mut banned = Vec::<String>::new();
     let mut muted = Vec::<String>::new();
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         {
-            let mut room_guard = lock_room(&room_arc)?;
+            let mut room_guard = lock_room(&room_arc);
             let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
                 Ok(d) => d.as_secs(),
                 Err(_) => 0,
@@ -62,12 +63,7 @@ pub fn handle_mod_info(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String)
                         let remaining = if rec.ban_length == 0 {
                             "Permanent".to_string()
                         } else {
-                            let rem = rec.ban_stamp.saturating_add(rec.ban_length) - now;
-                            let d = rem / 86_400;
-                            let h = (rem % 86_400) / 3_600;
-                            let m = (rem % 3_600) / 60;
-                            let s = rem % 60;
-                            format!("{d}d {h}h {m}m {s}s left")
+                            format!("{} left", format_duration(rec.ban_stamp.saturating_add(rec.ban_length) - now))
                         };
                         let reason = if rec.ban_reason.is_empty() { "" } else { " - " };
                         banned.push(format!("{uname} ({remaining}){reason}{}", rec.ban_reason));
@@ -95,12 +91,7 @@ pub fn handle_mod_info(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String)
                         let remaining = if rec.mute_length == 0 {
                             "Permanent".to_string()
                         } else {
-                            let rem = rec.mute_stamp.saturating_add(rec.mute_length) - now;
-                            let d = rem / 86_400;
-                            let h = (rem % 86_400) / 3_600;
-                            let m = (rem % 3_600) / 60;
-                            let s = rem % 60;
-                            format!("{d}d {h}h {m}m {s}s left")
+                            format!("{} left", format_duration(rec.mute_stamp.saturating_add(rec.mute_length) - now))
                         };
                         let reason = if rec.mute_reason.is_empty() { "" } else { " - " };
                         muted.push(format!("{uname} ({remaining}){reason}{}", rec.mute_reason));
@@ -111,64 +102,81 @@ pub fn handle_mod_info(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String)
 
         if changed {
             if let Err(e) = save_rooms_to_disk(&rooms_map) {
-                let mut c = lock_client(&client)?;
+                let mut c = lock_client(&client);
                 send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
                 return Ok(CommandResult::Handled);
             }
         }
     }
 
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     if banned.is_empty() && muted.is_empty() {
         send_success_locked(&mut c, "No users are currently banned or muted")?;
-    } else {
-        if !banned.is_empty() {
-            send_success_locked(&mut c, "- Banned users -")?;
-            for line in &banned {
-                send_message_locked(&mut c, &format!("  > {line}"))?;
-            }
-        }
-        if !muted.is_empty() {
-            send_success_locked(&mut c, "- Muted users -")?;
-            for line in &muted {
-                send_message_locked(&mut c, &format!("  > {line}"))?;
-            }
+        return Ok(CommandResult::Handled);
+    }
+
+    // Sorted so each page shows the same entries between calls
+    banned.sort();
+    muted.sort();
+    let entries: Vec<(&str, &String)> = banned.iter().map(|l| ("- Banned users -", l))
+        .chain(muted.iter().map(|l| ("- Muted users -", l)))
+        .collect();
+    let pages = entries.len().div_ceil(MOD_INFO_PAGE_SIZE);
+    if page > pages {
+        send_message_locked(&mut c, &format!("Error: Page {page} doesn't exist, there are {pages} page(s)").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let mut header = "";
+    for (section, line) in entries.iter().skip((page - 1) * MOD_INFO_PAGE_SIZE).take(MOD_INFO_PAGE_SIZE) {
+        if *section != header {
+            send_success_locked(&mut c, section)?;
+            header = section;
         }
+        send_message_locked(&mut c, &format!("  > {line}"))?;
     }
+    send_message_locked(&mut c, &format!("Page {page} of {pages}"))?;
 
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_mod_kick(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, username: &String, room: &String, target: &String, reason: String) -> io::Result<CommandResult> {
+pub fn send_to_lobby(target_c: &mut Client, target: &str, msg: &str) {
+    let _ = writeln!(target_c.stream, "/LOBBY_STATE");
+    let _ = writeln!(target_c.stream, "{}", msg.red());
+    target_c.state = ClientState::LoggedIn { username: target.to_string() };
+}
+
+pub fn handle_mod_kick(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str, reason: String) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    let target = &resolve_room_username(rooms, room, target)?;
     let mut kicked = false;
     let mut target_peer: Option<std::net::SocketAddr> = None;
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         {
-            let rg = lock_room(&room_arc)?;
+            let rg = lock_room(&room_arc);
             let caller_role = rg.users.get(username).map(|u| u.role.as_str()).unwrap_or("user");
             let target_role = rg.users.get(target).map(|u| u.role.as_str()).unwrap_or("user");
             if role_rank(caller_role) <= role_rank(target_role) {
-                let mut c = lock_client(&client)?;
+                let mut c = lock_client(&client);
                 send_message_locked(&mut c, &"Error: Cannot kick a user with equal or higher privilege".yellow().to_string())?;
                 return Ok(CommandResult::Handled);
             }
         }
 
         {
-            let mut rg = lock_room(&room_arc)?;
+            let mut rg = lock_room(&room_arc);
             if !rg.online_users.contains(target) {
-                let mut c = lock_client(&client)?;
+                let mut c = lock_client(&client);
                 send_message_locked(&mut c, &format!("{target} is not currently online").yellow().to_string())?;
                 return Ok(CommandResult::Handled);
             }
@@ -182,7 +190,7 @@ pub fn handle_mod_kick(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
         }
 
         {
-            let clients_map = lock_clients(clients)?;
+            let clients_map = lock_clients(clients);
             for c_arc in clients_map.values() {
                 if let Ok(mut target_c) = c_arc.try_lock() {
                     if let ClientState::InRoom { username: u, room: rnm, .. } = &target_c.state {
@@ -193,9 +201,7 @@ pub fn handle_mod_kick(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
                             } else {
                                 format!("You have been kicked from {room}: {reason}")
                             };
-                            let _ = writeln!(target_c.stream, "/LOBBY_STATE");
-                            let _ = writeln!(target_c.stream, "{}", msg.red());
-                            target_c.state = ClientState::LoggedIn { username: target.clone() };
+                            send_to_lobby(&mut target_c, target, &msg);
                             kicked = true;
                             break;
                         }
@@ -205,13 +211,13 @@ pub fn handle_mod_kick(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
         }
 
         if let Err(e) = save_rooms_to_disk(&rooms_map) {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
             return Ok(CommandResult::Handled);
         }
     }
 
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     if kicked {
         if reason.trim().is_empty() {
             send_success_locked(&mut c, &format!("Kicked {target}"))?;
@@ -221,6 +227,7 @@ pub fn handle_mod_kick(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
         if let Some(peer) = target_peer {
             log_event(&peer, Some(target), Some(room), &format!("Kicked from room {}", room));
         }
+        log_audit(username, target, room, format!("kick {reason}").trim_end());
     } else {
         send_message_locked(&mut c, &format!("Failed to kick {target}").yellow().to_string())?;
     }
@@ -231,11 +238,13 @@ pub fn handle_mod_kick(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_mod_ban(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, username: &String, room: &String, target: &String, duration: String, reason: String) -> io::Result<CommandResult> {
+pub fn handle_mod_ban(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str, duration: String, reason: String) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    let target = &resolve_room_username(rooms, room, target)?;
     let ban_secs = match parse_duration(&duration) {
         Ok(v) => v,
         Err(e) => {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_message_locked(&mut c, &format!("Bad duration: {e}").yellow().to_string())?;
             return Ok(CommandResult::Handled);
         }
@@ -245,54 +254,54 @@ pub fn handle_mod_ban(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Roo
         Ok(d) => d.as_secs(),
         Err(_) => 0,
     };
-    let human_len = if ban_secs == 0 { "PERMANENT".to_string() } else {
-        let mut rem = ban_secs;
-        let d = rem / 86_400; rem %= 86_400;
-        let h = rem / 3_600; rem %= 3_600;
-        let m = rem / 60; let s = rem % 60;
-        format!("{d}d {h}h {m}m {s}s")
-    };
+    let human_len = if ban_secs == 0 { "PERMANENT".to_string() } else { format_duration(ban_secs) };
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         {
-            let rg = lock_room(&room_arc)?;
+            let rg = lock_room(&room_arc);
             let caller_role = rg.users.get(username).map(|u| u.role.as_str()).unwrap_or("user");
             let target_role = rg.users.get(target).map(|u| u.role.as_str()).unwrap_or("user");
             if role_rank(caller_role) <= role_rank(target_role) {
-                let mut c = lock_client(&client)?;
+                let mut c = lock_client(&client);
                 send_message_locked(&mut c, &"Error: Cannot ban a user with equal or higher privilege".yellow().to_string())?;
                 return Ok(CommandResult::Handled);
             }
+            if rg.require_ban_reason && reason.trim().is_empty() {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &"A reason is required in this room".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
         }
 
         {
-            let mut rg = lock_room(&room_arc)?;
+            let mut rg = lock_room(&room_arc);
             let user_rec = rg.users.entry(target.clone()).or_insert(RoomUser {
                 nick: "".to_string(), color: "".to_string(), role: "user".to_string(),
                 hidden: false, last_seen: now, banned: false, ban_stamp: 0, ban_length: 0, ban_reason: "".to_string(),
                 muted: false, mute_stamp: 0, mute_length: 0, mute_reason: "".to_string(),
+                role_expiry: 0, prior_role: "".to_string(), ban_history: Vec::new(),
             });
 
             user_rec.banned = true;
             user_rec.ban_stamp = now;
             user_rec.ban_length = ban_secs;
             user_rec.ban_reason = reason.clone();
+            user_rec.ban_history.push((now, reason.clone()));
             user_rec.last_seen = now;
             rg.online_users.retain(|u| u != target);
         }
 
         {
-            let clients_map = lock_clients(clients)?;
+            let clients_map = lock_clients(clients);
             for c_arc in clients_map.values() {
                 if let Ok(mut target_c) = c_arc.try_lock() {
                     if let ClientState::InRoom { username: u, room: rnm, .. } = &target_c.state {
@@ -313,75 +322,100 @@ pub fn handle_mod_ban(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Roo
         }
 
         if let Err(e) = save_rooms_to_disk(&rooms_map) {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
             return Ok(CommandResult::Handled);
         }
     }
 
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     if reason.trim().is_empty() {
         send_success_locked(&mut c, &format!("Banned {target} ({human_len})"))?;
     } else {
         send_success_locked(&mut c, &format!("Banned {target} ({human_len}): {reason}"))?;
     }
     drop(c);
+    log_audit(username, target, room, format!("ban ({human_len}) {reason}").trim_end());
 
     let _ = sync_room_members(rooms, clients, pubkeys, room);
     let _ = broadcast_user_list(clients, rooms, room);
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_mod_unban(client: Arc<Mutex<Client>>, rooms: &Rooms, _username: &String, room: &String, target: &String) -> io::Result<CommandResult> {
-    let mut actually_unbanned = false;
+pub fn handle_mod_unban(client: Arc<Mutex<Client>>, ctx: RoomCtx, targets: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    let unban_all = targets == "all";
+    let mut unbanned = Vec::new();
+    let mut skipped = Vec::new();
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         {
-            let mut rg = lock_room(&room_arc)?;
-            if let Some(rec) = rg.users.get_mut(target) {
-                if rec.banned {
-                    rec.banned = false;
-                    rec.ban_stamp = 0;
-                    rec.ban_length = 0;
-                    rec.ban_reason.clear();
-                    actually_unbanned = true;
+            let mut rg = lock_room(&room_arc);
+            let names: Vec<String> = if unban_all {
+                rg.users.iter().filter(|(_, u)| u.banned).map(|(name, _)| name.clone()).collect()
+            } else {
+                targets.split_whitespace().map(|t| resolve_username(&rg, t).unwrap_or_else(|| t.to_string())).collect()
+            };
+            for target in names {
+                match rg.users.get_mut(&target) {
+                    Some(rec) if rec.banned => {
+                        rec.banned = false;
+                        rec.ban_stamp = 0;
+                        rec.ban_length = 0;
+                        rec.ban_reason.clear();
+                        unbanned.push(target);
+                    }
+                    _ => skipped.push(target),
                 }
             }
         }
 
-        if !actually_unbanned {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("{target} is not currently banned").yellow().to_string())?;
-            return Ok(CommandResult::Handled);
+        if !unbanned.is_empty() {
+            if let Err(e) = save_rooms_to_disk(&rooms_map) {
+                let mut c = lock_client(&client);
+                send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+                return Ok(CommandResult::Handled);
+            }
         }
+    }
 
-        if let Err(e) = save_rooms_to_disk(&rooms_map) {
-            let mut c = lock_client(&client)?;
-            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
-            return Ok(CommandResult::Handled);
+    let mut c = lock_client(&client);
+    for target in &skipped {
+        send_message_locked(&mut c, &format!("{target} is not currently banned").yellow().to_string())?;
+    }
+    if unban_all && unbanned.is_empty() {
+        send_message_locked(&mut c, &"No users were banned".yellow().to_string())?;
+    } else if unban_all {
+        send_success_locked(&mut c, &format!("Unbanned {} user(s)", unbanned.len()))?;
+    } else {
+        for target in &unbanned {
+            send_success_locked(&mut c, &format!("Unbanned {target}"))?;
         }
     }
+    drop(c);
 
-    let mut c = lock_client(&client)?;
-    send_success_locked(&mut c, &format!("Unbanned {target}"))?;
+    for target in &unbanned {
+        log_audit(username, target, room, "unban");
+    }
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_mod_mute(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, room: &String, target: &String, duration: String, reason: String) -> io::Result<CommandResult> {
+pub fn handle_mod_mute(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str, duration: String, reason: String) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    let target = &resolve_room_username(rooms, room, target)?;
     let mute_secs = match parse_duration(&duration) {
         Ok(v) => v,
         Err(e) => {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_message_locked(&mut c, &format!("Bad duration: {e}").yellow().to_string())?;
             return Ok(CommandResult::Handled);
         }
@@ -392,42 +426,41 @@ pub fn handle_mod_mute(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
         Err(_) => 0,
     };
 
-    let human_len = if mute_secs == 0 { "PERMANENT".to_string() } else {
-        let mut rem = mute_secs;
-        let d = rem / 86_400; rem %= 86_400;
-        let h = rem / 3_600; rem %= 3_600;
-        let m = rem / 60; let s = rem % 60;
-        format!("{d}d {h}h {m}m {s}s")
-    };
+    let human_len = if mute_secs == 0 { "PERMANENT".to_string() } else { format_duration(mute_secs) };
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         {
-            let rg = lock_room(&room_arc)?;
+            let rg = lock_room(&room_arc);
             let caller_role = rg.users.get(username).map(|u| u.role.as_str()).unwrap_or("user");
             let target_role = rg.users.get(target).map(|u| u.role.as_str()).unwrap_or("user");
             if role_rank(caller_role) <= role_rank(target_role) {
-                let mut c = lock_client(&client)?;
+                let mut c = lock_client(&client);
                 send_message_locked(&mut c, &"Error: Cannot mute a user with equal or higher privilege".yellow().to_string())?;
                 return Ok(CommandResult::Handled);
             }
+            if rg.require_ban_reason && reason.trim().is_empty() {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &"A reason is required in this room".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
         }
 
         {
-            let mut rg = lock_room(&room_arc)?;
+            let mut rg = lock_room(&room_arc);
             let rec = rg.users.entry(target.clone()).or_insert(RoomUser {
                 nick: "".into(), color: "".into(), role: "user".into(),
                 hidden: false, last_seen: now, banned: false, ban_stamp: 0, ban_length: 0, ban_reason: "".into(),
                 muted: false, mute_stamp: 0, mute_length: 0, mute_reason: "".into(),
+                role_expiry: 0, prior_role: "".into(), ban_history: Vec::new(),
             });
 
             rec.muted = true;
@@ -438,7 +471,7 @@ pub fn handle_mod_mute(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
         }
 
         {
-            let clients_map = lock_clients(clients)?;
+            let clients_map = lock_clients(clients);
             for c_arc in clients_map.values() {
                 if let Ok(mut target_c) = c_arc.try_lock() {
                     if let ClientState::InRoom { username: u, room: rnm, .. } = &target_c.state {
@@ -457,70 +490,75 @@ pub fn handle_mod_mute(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Ro
         }
 
         if let Err(e) = save_rooms_to_disk(&rooms_map) {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
             return Ok(CommandResult::Handled);
         }
     }
 
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     if reason.trim().is_empty() {
         send_success_locked(&mut c, &format!("Muted {target} ({human_len})"))?;
     } else {
         send_success_locked(&mut c, &format!("Muted {target} ({human_len}): {reason}"))?;
     }
+    drop(c);
+    log_audit(username, target, room, format!("mute ({human_len}) {reason}").trim_end());
 
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_mod_unmute(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, _username: &String, room: &String, target: &String) -> io::Result<CommandResult> {
-    let mut unmuted_success = false;
+pub fn handle_mod_unmute(client: Arc<Mutex<Client>>, ctx: RoomCtx, targets: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    let unmute_all = targets == "all";
+    let mut unmuted = Vec::new();
+    let mut skipped = Vec::new();
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         {
-            let mut rg = lock_room(&room_arc)?;
-            if let Some(rec) = rg.users.get_mut(target) {
-                if rec.muted {
-                    rec.muted = false;
-                    rec.mute_stamp = 0;
-                    rec.mute_length = 0;
-                    rec.mute_reason.clear();
-                    unmuted_success = true;
+            let mut rg = lock_room(&room_arc);
+            let names: Vec<String> = if unmute_all {
+                rg.users.iter().filter(|(_, u)| u.muted).map(|(name, _)| name.clone()).collect()
+            } else {
+                targets.split_whitespace().map(|t| resolve_username(&rg, t).unwrap_or_else(|| t.to_string())).collect()
+            };
+            for target in names {
+                match rg.users.get_mut(&target) {
+                    Some(rec) if rec.muted => {
+                        rec.muted = false;
+                        rec.mute_stamp = 0;
+                        rec.mute_length = 0;
+                        rec.mute_reason.clear();
+                        unmuted.push(target);
+                    }
+                    _ => skipped.push(target),
                 }
             }
         }
 
-        if !unmuted_success {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("{target} is not currently muted").yellow().to_string())?;
-            return Ok(CommandResult::Handled);
-        }
-
-        if let Err(e) = save_rooms_to_disk(&rooms_map) {
-            let mut c = lock_client(&client)?;
-            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
-            return Ok(CommandResult::Handled);
-        }
+        if !unmuted.is_empty() {
+            if let Err(e) = save_rooms_to_disk(&rooms_map) {
+                let mut c = lock_client(&client);
+                send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+                return Ok(CommandResult::Handled);
+            }
 
-        {
-            let clients_map = lock_clients(clients)?;
+            let clients_map = lock_clients(clients);
             for c_arc in clients_map.values() {
                 if let Ok(mut target_c) = c_arc.try_lock() {
                     if let ClientState::InRoom { username: u, room: rnm, .. } = &target_c.state {
-                        if u == target && rnm == room {
+                        if rnm == room && unmuted.contains(u) {
                             let _ = writeln!(target_c.stream, "{}", "You have been unmuted".green());
                             let _ = target_c.stream.flush();
-                            break;
                         }
                     }
                 }
@@ -528,7 +566,215 @@ pub fn handle_mod_unmute(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &
         }
     }
 
-    let mut c = lock_client(&client)?;
-    send_success_locked(&mut c, &format!("Unmuted {target}"))?;
+    let mut c = lock_client(&client);
+    for target in &skipped {
+        send_message_locked(&mut c, &format!("{target} is not currently muted").yellow().to_string())?;
+    }
+    if unmute_all && unmuted.is_empty() {
+        send_message_locked(&mut c, &"No users were muted".yellow().to_string())?;
+    } else if unmute_all {
+        send_success_locked(&mut c, &format!("Unmuted {} user(s)", unmuted.len()))?;
+    } else {
+        for target in &unmuted {
+            send_success_locked(&mut c, &format!("Unmuted {target}"))?;
+        }
+    }
+    drop(c);
+
+    for target in &unmuted {
+        log_audit(username, target, room, "unmute");
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_mod_whois(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, .. } = ctx;
+    let session: Option<(String, bool)> = {
+        let clients_map = lock_clients(clients);
+        clients_map.values().find_map(|arc| {
+            let c = arc.try_lock().ok()?;
+            match &c.state {
+                ClientState::InRoom { username, room, is_afk, .. } if username == target => Some((room.clone(), *is_afk)),
+                _ => None,
+            }
+        })
+    };
+
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+
+    let mut sections = Vec::<String>::new();
+    {
+        let rooms_map = lock_rooms(rooms);
+        let mut names: Vec<&String> = rooms_map.keys().collect();
+        names.sort();
+
+        for name in names {
+            let room_guard = lock_room(&rooms_map[name]);
+            let rec = match room_guard.users.get(target) {
+                Some(r) => r,
+                None => continue,
+            };
+
+            let is_online = room_guard.online_users.iter().any(|u| u == target);
+            let is_afk = matches!(&session, Some((r, afk)) if r == name && *afk);
+
+            let nick = if rec.nick.is_empty() { "(none)" } else { rec.nick.as_str() };
+            let color = if rec.color.is_empty() { "(default)" } else { rec.color.as_str() };
+            let mute = if !rec.muted {
+                "No".to_string()
+            } else if rec.mute_length == 0 {
+                "Permanent".to_string()
+            } else {
+                format!("{} left", format_duration(rec.mute_stamp.saturating_add(rec.mute_length).saturating_sub(now)))
+            };
+            let ban = if !rec.banned {
+                "No".to_string()
+            } else if rec.ban_length == 0 {
+                "Permanent".to_string()
+            } else {
+                format!("{} left", format_duration(rec.ban_stamp.saturating_add(rec.ban_length).saturating_sub(now)))
+            };
+            let last_seen = if is_online {
+                "Online now".to_string()
+            } else if rec.last_seen == 0 {
+                "Never".to_string()
+            } else {
+                format!("{} ago", format_duration(now.saturating_sub(rec.last_seen)))
+            };
+
+            sections.push(format!(
+                "Room: {name}\n> Role: {}\n> Nick: {nick}\n> Color: {color}\n> Hidden: {}\n> AFK: {}\n> Muted: {mute}\n> Banned: {ban}\n> Last seen: {last_seen}",
+                room_guard.role_label(&rec.role), rec.hidden, is_afk
+            ));
+        }
+    }
+
+    let mut c = lock_client(&client);
+    if sections.is_empty() {
+        send_message_locked(&mut c, &"User has no records".yellow().to_string())?;
+    } else {
+        send_success_locked(&mut c, &format!("Records for {target}:\n{}", sections.join("\n")))?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_mod_history(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let target = &resolve_room_username(rooms, room, target)?;
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+    };
+
+    let entries: Option<Vec<String>> = {
+        let room_guard = lock_room(&room_arc);
+        room_guard.users.get(target).map(|rec| {
+            rec.ban_history.iter().map(|(stamp, reason)| {
+                let when = match chrono::DateTime::from_timestamp(*stamp as i64, 0) {
+                    Some(dt) => dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+                    None => stamp.to_string(),
+                };
+                if reason.trim().is_empty() {
+                    format!("> [{when}] (no reason)")
+                } else {
+                    format!("> [{when}] {reason}")
+                }
+            }).collect()
+        })
+    };
+
+    let mut c = lock_client(&client);
+    match entries {
+        None => send_message_locked(&mut c, &format!("{target} has never joined this room").yellow().to_string())?,
+        Some(e) if e.is_empty() => send_message_locked(&mut c, &format!("{target} has no ban history in this room").yellow().to_string())?,
+        Some(e) => send_success_locked(&mut c, &format!("Ban history for {target} ({}):\n{}", e.len(), e.join("\n")))?,
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn handle_mod_export(client: Arc<Mutex<Client>>, ctx: RoomCtx, filename: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    if !filename.is_empty() && !is_safe_filename(filename) {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &"Error: Invalid file name".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let rows: Vec<String> = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        let room_guard = lock_room(&room_arc);
+
+        let caller_rank = room_guard.users.get(username).map(|u| role_rank(&u.role)).unwrap_or(1);
+        if caller_rank < role_rank("admin") {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &"Only admins and owners can export moderation records".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let mut names: Vec<&String> = room_guard.users.keys().collect();
+        names.sort();
+
+        let mut rows = Vec::new();
+        for name in names {
+            let rec = &room_guard.users[name];
+            if rec.banned {
+                rows.push(format!("{},ban,{},{},{}", csv_field(name), csv_field(&rec.ban_reason), rec.ban_stamp, rec.ban_length));
+            }
+            if rec.muted {
+                rows.push(format!("{},mute,{},{},{}", csv_field(name), csv_field(&rec.mute_reason), rec.mute_stamp, rec.mute_length));
+            }
+        }
+        rows
+    };
+
+    let final_filename = if filename.is_empty() {
+        let stamp = chrono::Local::now().format("%y%m%d%H%M%S").to_string();
+        format!("{room}_bans_{stamp}.csv")
+    } else if filename.ends_with(".csv") {
+        filename.to_string()
+    } else {
+        format!("{filename}.csv")
+    };
+
+    let export_path = format!("data/vault/rooms/{final_filename}");
+    let mut contents = String::from("username,type,reason,start_epoch,length_secs\n");
+    for row in &rows {
+        contents.push_str(row);
+        contents.push('\n');
+    }
+
+    let mut c = lock_client(&client);
+    if let Err(e) = std::fs::write(&export_path, contents) {
+        send_error_locked(&mut c, &format!("Error creating {export_path}: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    send_success_locked(&mut c, &format!("Exported {} moderation record(s) to: {export_path}", rows.len()))?;
     Ok(CommandResult::Handled)
 }
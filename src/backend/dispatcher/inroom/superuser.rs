@@ -4,54 +4,142 @@ use serde_json::{json, Serializer};
 use serde::Serialize;
 use serde_json::ser::PrettyFormatter;
 use std::fs::OpenOptions;
+use std::time::{SystemTime, UNIX_EPOCH};
 use colored::*;
 
-use crate::shared::types::{Client, ClientState, Clients, Rooms};
-use crate::shared::utils::{lock_client, lock_clients, lock_rooms, lock_room, send_success, send_error, send_message, save_rooms_to_disk, ColorizeExt, send_message_locked, send_error_locked, send_success_locked, broadcast_room_list_to_all};
+use crate::shared::types::{Client, ClientState, Clients, Room, RoomUser};
+use crate::shared::utils::{send_failure_locked, find_room, send_failure, lock_client, lock_clients, lock_rooms, lock_room, send_success, send_error, send_message, save_rooms_to_disk, ColorizeExt, send_message_locked, send_error_locked, send_success_locked, log_audit, log_event, read_audit_log, broadcast_message, broadcast_room_list_to_all, broadcast_user_list, read_prompt_line};
+use crate::backend::command_utils::{is_safe_filename, resolve_username, validate_nickname, sync_room_members, validate_room_name, MAX_MESSAGE_BYTES, MAX_PINNED, MAX_SCRIPT_LINES, MAX_MOTD_LEN, EXPORT_ROLES_KEYS, EXPORT_CONFIG_KEYS};
+use crate::backend::parser::{parse_command, Command};
+use crate::backend::dispatcher::inroom::moderation::{role_rank, send_to_lobby};
+use crate::backend::dispatcher::inroom::RoomCtx;
 use crate::backend::dispatcher::CommandResult;
 
-pub fn handle_super_users(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, room: &String) -> io::Result<CommandResult> {
+pub fn handle_super_info(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    let room_guard = lock_room(&room_arc);
+
+    let owner = room_guard.users.iter()
+        .find(|(_, u)| u.role == "owner")
+        .map(|(name, _)| name.clone())
+        .unwrap_or_else(|| "(none)".to_string());
+
+    let whitelist_display = if room_guard.whitelist_enabled {
+        format!("ON ({} users)", room_guard.whitelist.len())
+    } else {
+        "OFF".to_string()
+    };
+
+    let rate_display = if room_guard.msg_rate == 0 {
+        "UNLIMITED".to_string()
+    } else {
+        format!("{} messages per 5 sec", room_guard.msg_rate)
+    };
+
+    let timeout_display = if room_guard.session_timeout == 0 {
+        "UNLIMITED".to_string()
+    } else {
+        format!("{} sec of inactivity", room_guard.session_timeout)
+    };
+
+    let mut colors = Vec::new();
+    for role in ["owner", "admin", "moderator", "user"] {
+        if let Some(hex) = room_guard.roles.colors.get(role) {
+            colors.push(format!("{}", role.truecolor_from_hex(hex)));
+        }
+    }
+
+    let tags_display = if room_guard.tags.is_empty() {
+        "(none)".to_string()
+    } else {
+        room_guard.tags.join(", ")
+    };
+
+    let mut c = lock_client(&client);
+    writeln!(
+        c.stream,
+        "{}\n  > Owner: {}\n  > Registered users: {}\n  > Online: {}\n  > Whitelist: {}\n  > Message rate: {}\n  > Session timeout: {}\n  > Tags: {}\n  > Rename-protected: {}\n  > Staff alerts: {}\n  > Ban reasons required: {}\n  > Archived: {}\n  > Role colors: {}",
+        format!("Room {room}:").green(),
+        owner.green(),
+        room_guard.users.len().to_string().green(),
+        room_guard.online_users.len().to_string().green(),
+        whitelist_display.green(),
+        rate_display.green(),
+        timeout_display.green(),
+        tags_display.green(),
+        (if room_guard.locked { "YES" } else { "NO" }).green(),
+        (if room_guard.announce_staff { "ON" } else { "OFF" }).green(),
+        (if room_guard.require_ban_reason { "ON" } else { "OFF" }).green(),
+        (if room_guard.archived { "YES" } else { "NO" }).green(),
+        colors.join(" ")
+    )?;
+    c.stream.flush()?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_users(client: Arc<Mutex<Client>>, ctx: RoomCtx, filter: Option<&str>) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
     let mut status_map = std::collections::HashMap::new();
     {
-        let clients_map = lock_clients(clients)?;
+        let clients_map = lock_clients(clients);
         for c_arc in clients_map.values() {
             if let Ok(target_c) = c_arc.try_lock() {
-                if let ClientState::InRoom { username, room: rnm, is_afk, room_time, .. } = &target_c.state {
+                if let ClientState::InRoom { username, room: rnm, is_afk, afk_reason, room_time, .. } = &target_c.state {
                     if rnm == room {
                         let secs = room_time.and_then(|t| t.elapsed().ok()).map(|d| d.as_secs()).unwrap_or(0);
-                        status_map.insert(username.clone(), (*is_afk, secs));
+                        status_map.insert(username.clone(), (*is_afk, afk_reason.clone(), secs));
                     }
                 }
             }
         }
     }
 
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
-    let room_guard = lock_room(&room_arc)?;
-    let mut c = lock_client(&client)?;
+    let room_guard = lock_room(&room_arc);
+    let mut c = lock_client(&client);
 
-    writeln!(c.stream, "{}", format!("User data for {room}:").green())?;
+    let heading = match filter {
+        Some(f) => format!("User data for {room} ({f}):"),
+        None => format!("User data for {room}:"),
+    };
+    writeln!(c.stream, "{}", heading.green())?;
     c.stream.flush()?;
 
+    let mut shown = 0;
     for (uname, udata) in &room_guard.users {
-        if !room_guard.online_users.contains(uname) {
+        let online = room_guard.online_users.contains(uname);
+        // Banned users are usually offline, so that filter looks at every record
+        let matches = match filter {
+            Some("afk") => online && status_map.get(uname).is_some_and(|(afk, _, _)| *afk),
+            Some("hidden") => online && udata.hidden,
+            Some("muted") => online && udata.muted,
+            Some("banned") => udata.banned,
+            _ => online,
+        };
+        if !matches {
             continue;
         }
+        shown += 1;
 
-        let role = {
-            let mut ch = udata.role.chars();
-            match ch.next() {
-                Some(f) => f.to_uppercase().collect::<String>() + ch.as_str(),
-                None => String::new(),
-            }
+        let role = room_guard.role_label(&udata.role);
+        let role = match room_guard.roles.colors.get(&udata.role) {
+            Some(hex) => role.truecolor_from_hex(hex).to_string(),
+            None => role,
         };
 
         let color_display = if udata.color.is_empty() {
@@ -72,15 +160,19 @@ pub fn handle_super_users(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
             "False".green().to_string()
         };
 
-        let (afk, secs) = status_map.get(uname).cloned().unwrap_or((false, 0));
+        let (afk, afk_reason, secs) = status_map.get(uname).cloned().unwrap_or((false, String::new(), 0));
 
-        let afk_status = if afk {
+        let afk_status = if afk && !afk_reason.is_empty() {
+            format!("True ({afk_reason})").yellow().to_string()
+        } else if afk {
             "True".yellow().to_string()
         } else {
             "False".green().to_string()
         };
 
-        let session_time = {
+        let session_time = if !online {
+            "Offline".to_string()
+        } else {
             let h = secs / 3600;
             let m = (secs % 3600) / 60;
             let s = secs % 60;
@@ -99,226 +191,1376 @@ pub fn handle_super_users(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
         c.stream.flush()?;
     }
 
+    if shown == 0 {
+        send_message_locked(&mut c, &"No matching users".yellow().to_string())?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_rename(client: Arc<Mutex<Client>>, ctx: RoomCtx, new_name: &str, force: bool) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
+    let old_name = room.to_string();
+
+    if let Err(reason) = validate_room_name(new_name) {
+        send_message(&client, &reason.yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    {
+        let clients_map = lock_clients(clients);
+        let mut rooms_map = lock_rooms(rooms);
+
+        if rooms_map.contains_key(new_name) {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &format!("Room name '{new_name}' is already taken").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let locked = match rooms_map.get(&old_name) {
+            Some(r) => lock_room(r).locked,
+            None => false,
+        };
+        if locked && !force {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &"Room is rename-protected; use force".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let room_arc = match rooms_map.remove(&old_name) {
+            Some(r) => r,
+            None => {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &format!("Room '{old_name}' not found").yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        rooms_map.insert(new_name.to_string(), Arc::clone(&room_arc));
+
+        for c_arc in clients_map.values() {
+            if let Ok(mut target_c) = c_arc.try_lock() {
+                if let ClientState::InRoom { room: r, .. } = &mut target_c.state {
+                    if r == &old_name {
+                        *r = new_name.to_string();
+                        let _ = writeln!(target_c.stream, "/ROOM_NAME {new_name}");
+                        let _ = target_c.stream.flush();
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            let mut c = lock_client(&client);
+            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
+    let mut c = lock_client(&client);
+    send_success_locked(&mut c, &format!("Room renamed from '{old_name}' to '{new_name}'"))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_lock(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let locked_now = {
+        let mut room_guard = lock_room(&room_arc);
+        room_guard.locked = !room_guard.locked;
+        room_guard.locked
+    };
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    if locked_now {
+        send_success_locked(&mut c, "Room is now rename-protected")?;
+    } else {
+        send_success_locked(&mut c, "Room is no longer rename-protected")?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_staffalerts(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let enabled = {
+        let mut room_guard = lock_room(&room_arc);
+        room_guard.announce_staff = !room_guard.announce_staff;
+        room_guard.announce_staff
+    };
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    if enabled {
+        send_success_locked(&mut c, "Staff alerts are now ON")?;
+    } else {
+        send_success_locked(&mut c, "Staff alerts are now OFF")?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_motd(client: Arc<Mutex<Client>>, ctx: RoomCtx, text: Option<&str>) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let text = match text {
+        Some(t) => t.trim(),
+        None => {
+            let motd = lock_room(&room_arc).motd.clone();
+            if motd.is_empty() {
+                send_message_locked(&mut c, &"No MOTD is set for this room".yellow().to_string())?;
+            } else {
+                send_success_locked(&mut c, &format!("Current MOTD: {motd}"))?;
+            }
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    if text.chars().count() > MAX_MOTD_LEN {
+        send_message_locked(&mut c, &format!("Error: The MOTD can be at most {MAX_MOTD_LEN} characters").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let clear = text == "*";
+    lock_room(&room_arc).motd = if clear { String::new() } else { text.to_string() };
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    if clear {
+        send_success_locked(&mut c, "MOTD cleared")?;
+    } else {
+        send_success_locked(&mut c, "MOTD updated")?;
+    }
+    drop(c);
+    log_audit(username, "-", room, if clear { "motd clear" } else { "motd set" });
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_policy_banreason(client: Arc<Mutex<Client>>, ctx: RoomCtx, enabled: bool) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    lock_room(&room_arc).require_ban_reason = enabled;
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let state = if enabled { "on" } else { "off" };
+    send_success_locked(&mut c, &format!("Ban and mute reasons are now {}", if enabled { "REQUIRED" } else { "OPTIONAL" }))?;
+    drop(c);
+    log_audit(username, "-", room, &format!("policy banreason {state}"));
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_archive(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    let archived = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        let archived = {
+            let mut room_guard = lock_room(&room_arc);
+            if room_guard.users.get(username).is_none_or(|u| u.role != "owner") {
+                send_message(&client, &"Error: Only the room owner can archive the room".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+            room_guard.archived = !room_guard.archived;
+            room_guard.archived
+        };
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            send_error(&client, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+        archived
+    };
+
+    let msg = if archived {
+        "This room has been archived and is now read-only"
+    } else {
+        "This room is no longer archived"
+    };
+    log_audit(username, "-", room, if archived { "archive" } else { "unarchive" });
+    broadcast_message(clients, room, username, &msg.bright_yellow().to_string(), true, true, None)?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_freeze(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    let frozen = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        let mut room_guard = lock_room(&room_arc);
+        if room_guard.users.get(username).is_none_or(|u| u.role != "owner" && u.role != "admin") {
+            send_message(&client, &"Error: Only Admins and Owners can freeze the room".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+        room_guard.frozen = !room_guard.frozen;
+        room_guard.frozen
+    };
+
+    let msg = if frozen {
+        format!("Chat has been frozen by {username}")
+    } else {
+        format!("Chat has been unfrozen by {username}")
+    };
+    log_audit(username, "-", room, if frozen { "freeze" } else { "unfreeze" });
+    broadcast_message(clients, room, username, &msg.bright_yellow().to_string(), true, true, None)?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_kickall(client: Arc<Mutex<Client>>, ctx: RoomCtx, reason: &str, dry: bool) -> io::Result<CommandResult> {
+    kick_online_users(client, ctx, reason, None, dry)
+}
+
+pub fn handle_super_kick_role(client: Arc<Mutex<Client>>, ctx: RoomCtx, role: &str) -> io::Result<CommandResult> {
+    let target_role = match role.to_lowercase().as_str() {
+        "usr" | "user" => "user",
+        "mod" | "moderator" => "moderator",
+        "admin" | "administrator" => "admin",
+        _ => {
+            send_message(&client, &"Error: Role must be user|mod|admin".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    kick_online_users(client, ctx, "", Some(target_role), false)
+}
+
+// Runs each command in the script through the in-room dispatcher as if the owner had typed it.
+// Blank lines and lines starting with '#' are skipped. The script stops at the first line
+// that doesn't parse, tries to run another script, or leaves the owner outside the room.
+pub fn handle_super_run(client: Arc<Mutex<Client>>, ctx: RoomCtx, filename: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    {
+        let rooms_map = lock_rooms(rooms);
+        let is_owner = match find_room(&rooms_map, room) {
+            Ok(r) => lock_room(&r).users.get(username).is_some_and(|u| u.role == "owner"),
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        if !is_owner {
+            send_message(&client, &"Error: Only the room owner can run scripts".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
+    if !is_safe_filename(filename) {
+        send_message(&client, &"Error: Invalid file name".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let script_path = format!("data/vault/scripts/{filename}");
+    let contents = match std::fs::read_to_string(&script_path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            send_message(&client, &format!("Error: {script_path} is not a valid text file").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+        Err(_) => {
+            send_message(&client, &format!("Error: Could not open {script_path}").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let lines: Vec<(usize, &str)> = contents.lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    if lines.is_empty() {
+        send_message(&client, &format!("No commands found in {script_path}").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+    if lines.len() > MAX_SCRIPT_LINES {
+        send_message(&client, &format!("Error: Scripts can run at most {MAX_SCRIPT_LINES} commands").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    log_audit(username, "-", room, &format!("run {filename}"));
+
+    for (n, line) in &lines {
+        send_message(&client, &format!("[run] Line {n}: {line}").bright_blue().to_string())?;
+
+        if !line.starts_with('/') {
+            send_error(&client, &format!("Script aborted at line {n}: scripts can only contain commands"))?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let cmd = parse_command(line);
+        match &cmd {
+            Command::SuperRun { .. } => {
+                send_error(&client, &format!("Script aborted at line {n}: scripts cannot run other scripts"))?;
+                return Ok(CommandResult::Handled);
+            }
+            Command::InvalidSyntax { err_msg } => {
+                send_message(&client, err_msg)?;
+                send_error(&client, &format!("Script aborted at line {n}: invalid command"))?;
+                return Ok(CommandResult::Handled);
+            }
+            Command::Unavailable => {
+                send_error(&client, &format!("Script aborted at line {n}: unknown command"))?;
+                return Ok(CommandResult::Handled);
+            }
+            _ => {}
+        }
+
+        if let CommandResult::Stop = crate::backend::dispatcher::inroom::inroom_command(cmd, Arc::clone(&client), clients, rooms, username, room, pubkeys)? {
+            return Ok(CommandResult::Stop);
+        }
+
+        let still_here = matches!(&lock_client(&client).state, ClientState::InRoom { room: r, .. } if r == room);
+        if !still_here {
+            send_error(&client, &format!("Script stopped after line {n}: you are no longer in {room}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
+    send_success(&client, &format!("Ran {} command(s) from {filename}", lines.len()))?;
+    Ok(CommandResult::Handled)
+}
+
+// Sends online users below the caller's rank back to the lobby. With `role` set, only users
+// holding that role are kicked (Owner only); otherwise everyone is (Admins and Owners).
+// With `dry` set, only reports who would be kicked.
+fn kick_online_users(client: Arc<Mutex<Client>>, ctx: RoomCtx, reason: &str, role: Option<&str>, dry: bool) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+    let mut skipped = 0;
+
+    let (targets, peers) = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        let targets: Vec<String> = {
+            let mut rg = lock_room(&room_arc);
+            let caller_rank = rg.users.get(username).map(|u| role_rank(&u.role)).unwrap_or(1);
+            if role.is_some() && caller_rank < role_rank("owner") {
+                send_message(&client, &"Error: Only the room owner can kick users by role".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+            if caller_rank < role_rank("admin") {
+                send_message(&client, &"Error: Only admins and owners can kick everyone from the room".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+
+            let mut targets = Vec::new();
+            for u in rg.online_users.clone() {
+                if u == username { continue; }
+                let target_role = rg.users.get(&u).map(|r| r.role.as_str()).unwrap_or("user");
+                if role.is_some_and(|r| r != target_role) { continue; }
+                if role_rank(target_role) >= caller_rank {
+                    skipped += 1;
+                    continue;
+                }
+                targets.push(u);
+            }
+
+            if dry {
+                let mut names = targets.clone();
+                names.sort();
+                let msg = if names.is_empty() {
+                    format!("[dry run] No one would be kicked from {room} ({skipped} with equal or higher privilege skipped)")
+                } else {
+                    format!("[dry run] Would kick {} user(s) from {room}: {} ({skipped} with equal or higher privilege skipped)", names.len(), names.join(", "))
+                };
+                send_message(&client, &msg.cyan().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+
+            for u in &targets {
+                if let Some(rec) = rg.users.get_mut(u) {
+                    rec.last_seen = now;
+                }
+            }
+            rg.online_users.retain(|u| !targets.contains(u));
+            targets
+        };
+
+        if targets.is_empty() {
+            send_message(&client, &"No one to kick".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let msg = if reason.trim().is_empty() {
+            format!("You have been kicked from {room}")
+        } else {
+            format!("You have been kicked from {room}: {reason}")
+        };
+
+        let peers = lobby_targets(clients, room, &targets, &msg)?;
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            send_error(&client, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+        (targets, peers)
+    };
+
+    let noun = if targets.len() == 1 { "user" } else { "users" };
+    if skipped > 0 {
+        send_success(&client, &format!("Kicked {} {noun} from {room} ({skipped} with equal or higher privilege skipped)", targets.len()))?;
+    } else if let Some(r) = role {
+        send_success(&client, &format!("Kicked {} {noun} with role {r} from {room}", targets.len()))?;
+    } else {
+        send_success(&client, &format!("Kicked {} {noun} from {room}", targets.len()))?;
+    }
+
+    for (target, peer) in &peers {
+        log_event(peer, Some(target), Some(room), &format!("Kicked from room {}", room));
+    }
+    for target in &targets {
+        match role {
+            Some(r) => log_audit(username, target, room, &format!("kick-role {r}")),
+            None => log_audit(username, target, room, format!("kickall {reason}").trim_end()),
+        }
+    }
+
+    let _ = sync_room_members(rooms, clients, pubkeys, room);
+    let _ = broadcast_user_list(clients, rooms, room);
+    Ok(CommandResult::Handled)
+}
+
+// Sends each target still connected to `room` back to the lobby, returning who was reached
+fn lobby_targets(clients: &Clients, room: &str, targets: &[String], msg: &str) -> io::Result<Vec<(String, std::net::SocketAddr)>> {
+    let mut peers = Vec::new();
+    let clients_map = lock_clients(clients);
+    for c_arc in clients_map.values() {
+        if let Ok(mut target_c) = c_arc.try_lock() {
+            let target = match &target_c.state {
+                ClientState::InRoom { username: u, room: rnm, .. } if rnm == room && targets.contains(u) => u.clone(),
+                _ => continue,
+            };
+            send_to_lobby(&mut target_c, &target, msg);
+            peers.push((target, target_c.addr));
+        }
+    }
+    Ok(peers)
+}
+
+fn purgeable(room: &Room, name: &str, rec: &RoomUser, cutoff: u64) -> bool {
+    rec.role == "user" && !rec.banned && rec.last_seen < cutoff && !room.online_users.iter().any(|u| u == name)
+}
+
+pub fn handle_super_import_profile(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str, source: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+
+    if source == room {
+        send_message(&client, &"Error: The source room must be a different room".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let (room_arc, source_arc) = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        let source_arc = match find_room(&rooms_map, source) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        (room_arc, source_arc)
+    };
+
+    let caller_rank = {
+        let room_guard = lock_room(&room_arc);
+        room_guard.users.get(username).map(|u| role_rank(&u.role)).unwrap_or(1)
+    };
+    if caller_rank < role_rank("admin") {
+        send_message(&client, &"Error: Only admins and owners can import user profiles".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let (target, nick, color) = {
+        let source_guard = lock_room(&source_arc);
+        let target = match resolve_username(&source_guard, target) {
+            Some(t) => t,
+            None => {
+                send_message(&client, &format!("Error: {target} has no record in {source}").yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        let rec = &source_guard.users[&target];
+        (target.clone(), rec.nick.clone(), rec.color.clone())
+    };
+
+    // The copied nick has to pass the same checks as /user nick
+    if !nick.is_empty() && let Err(e) = validate_nickname(&nick) {
+        send_message(&client, &e.yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    {
+        let mut room_guard = lock_room(&room_arc);
+        if target != username && room_guard.users.get(&target).is_some_and(|u| role_rank(&u.role) >= caller_rank) {
+            send_message(&client, &"Error: Cannot import a profile for a user with equal or higher privilege".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+        if !nick.is_empty() && room_guard.users.iter().any(|(name, u)| name != &target && u.nick.eq_ignore_ascii_case(&nick)) {
+            send_message(&client, &"Nickname already taken".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let entry = room_guard.users.entry(target.clone()).or_insert(RoomUser {
+            nick: "".to_string(), color: "".to_string(), role: "user".to_string(),
+            hidden: false, last_seen: 0, banned: false, ban_stamp: 0, ban_length: 0, ban_reason: "".to_string(),
+            muted: false, mute_stamp: 0, mute_length: 0, mute_reason: "".to_string(),
+            role_expiry: 0, prior_role: "".to_string(), ban_history: Vec::new()
+        });
+        entry.nick = nick.clone();
+        entry.color = color.clone();
+    }
+
+    {
+        let rooms_map = lock_rooms(rooms);
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            send_error(&client, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+    log_audit(username, &target, room, &format!("import-profile {source}"));
+
+    let nick_display = if nick.is_empty() { "none".to_string() } else { nick };
+    let color_display = if color.is_empty() { "none".to_string() } else { color.as_str().truecolor_from_hex(&color).to_string() };
+    send_success(&client, &format!("Imported {target}'s profile from {source} (nick: {nick_display}, color: {color_display})"))?;
+
+    let _ = sync_room_members(rooms, clients, pubkeys, room);
+    let _ = broadcast_user_list(clients, rooms, room);
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_purge(client: Arc<Mutex<Client>>, ctx: RoomCtx, days: u64, dry: bool) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+    let cutoff = now.saturating_sub(days.saturating_mul(86400));
+
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+    };
+
+    let count = {
+        let room_guard = lock_room(&room_arc);
+        match room_guard.users.get(username) {
+            Some(u) if u.role == "owner" => {},
+            _ => {
+                send_message(&client, &"Error: Only the room owner can purge user records".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+        let mut candidates: Vec<&String> = room_guard.users.iter()
+            .filter(|(name, rec)| purgeable(&room_guard, name, rec, cutoff))
+            .map(|(name, _)| name)
+            .collect();
+
+        if dry {
+            candidates.sort();
+            let msg = if candidates.is_empty() {
+                format!("[dry run] No user records in {room} have been inactive for over {days} days")
+            } else {
+                format!("[dry run] Would remove {} user record(s) inactive for over {days} days from {room}: {}", candidates.len(), candidates.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", "))
+            };
+            send_message(&client, &msg.cyan().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+        candidates.len()
+    };
+
+    if count == 0 {
+        send_message(&client, &format!("No user records have been inactive for over {days} days").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    {
+        let mut c = lock_client(&client);
+        writeln!(c.stream, "{}", format!("Are you sure you want to remove {count} user record(s) inactive for over {days} days from {room}? (y/n): ").red())?;
+
+        let mut reader = BufReader::new(c.stream.try_clone()?);
+        drop(c);
+        loop {
+            let mut line = String::new();
+            let bytes_read = read_prompt_line(&mut reader, &mut line, &client)?;
+            if bytes_read == 0 {
+                return Ok(CommandResult::Stop);
+            }
+
+            match line.trim().to_lowercase().as_str() {
+                "y" => break,
+                "n" => {
+                    send_message(&client, &"Purge cancelled".yellow().to_string())?;
+                    return Ok(CommandResult::Handled);
+                },
+                _ => {
+                    let mut c = lock_client(&client);
+                    writeln!(c.stream, "{}", "(y/n): ".red())?;
+                }
+            }
+        }
+    }
+
+    let rooms_map = lock_rooms(rooms);
+    let purged: Vec<String> = {
+        let mut room_guard = lock_room(&room_arc);
+        let purged: Vec<String> = room_guard.users.iter()
+            .filter(|(name, rec)| purgeable(&room_guard, name, rec, cutoff))
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &purged {
+            room_guard.users.remove(name);
+        }
+        purged
+    };
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error(&client, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+    drop(rooms_map);
+
+    for name in &purged {
+        log_audit(username, name, room, &format!("purge (inactive {days}d)"));
+    }
+
+    send_success(&client, &format!("Purged {} inactive user record(s)", purged.len()))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_export(client: Arc<Mutex<Client>>, ctx: RoomCtx, filename: &str, section: Option<&str>) -> io::Result<CommandResult> {
+    let RoomCtx { room, .. } = ctx;
+    if !filename.is_empty() && !is_safe_filename(filename) {
+        send_message(&client, &"Error: Invalid file name".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let file = match std::fs::File::open("data/rooms.json") {
+        Ok(f)  => f,
+        Err(e) => {
+            send_error(&client, &format!("Error opening rooms.json: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    
+    let reader = BufReader::new(file);
+    let rooms_json: serde_json::Value = match serde_json::from_reader(reader) {
+        Ok(v)  => v,
+        Err(e) => {
+            send_error(&client, &format!("Malformed rooms.json: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let room_data = match rooms_json.get(room) {
+        Some(v) => v.clone(),
+        None => {
+            send_message(&client, &"Error: room data not found".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    // A section keeps only the room-level settings, leaving out per-user records and history
+    let room_data = match section {
+        Some(s) => {
+            let keys: &[&str] = if s == "roles" { &EXPORT_ROLES_KEYS } else { &EXPORT_CONFIG_KEYS };
+            let filtered: serde_json::Map<String, serde_json::Value> = keys.iter()
+                .filter_map(|k| room_data.get(*k).map(|v| (k.to_string(), v.clone())))
+                .collect();
+            serde_json::Value::Object(filtered)
+        }
+        None => room_data,
+    };
+
+    let final_filename = if filename.is_empty() {
+        let stamp = chrono::Local::now().format("%y%m%d%H%M%S").to_string();
+        match section {
+            Some(s) => format!("{room}_{s}_{stamp}.json"),
+            None => format!("{room}_{stamp}.json"),
+        }
+    } else if filename.ends_with(".json") {
+        filename.to_string()
+    } else {
+        format!("{filename}.json")
+    };
+
+    let export_path = format!("data/vault/rooms/{final_filename}");
+    let export_file = match OpenOptions::new().create(true).write(true).truncate(true).open(&export_path) {
+        Ok(f)  => f,
+        Err(e) => {
+            send_error(&client, &format!("Error creating {export_path}: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let mut writer = BufWriter::new(export_file);
+    let formatter = PrettyFormatter::with_indent(b"    ");
+    let mut ser = Serializer::with_formatter(&mut writer, formatter);
+    json!({ room: room_data }).serialize(&mut ser)?;
+
+    match section {
+        Some(s) => send_success(&client, &format!("Exported room {s} to: {final_filename}"))?,
+        None => send_success(&client, &format!("Exported room data to: {final_filename}"))?,
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_export_all(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { username, .. } = ctx;
+    let file = match std::fs::File::open("data/rooms.json") {
+        Ok(f)  => f,
+        Err(e) => {
+            send_error(&client, &format!("Error opening rooms.json: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let reader = BufReader::new(file);
+    let rooms_json: serde_json::Value = match serde_json::from_reader(reader) {
+        Ok(v)  => v,
+        Err(e) => {
+            send_error(&client, &format!("Malformed rooms.json: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let owned: serde_json::Map<String, serde_json::Value> = match rooms_json.as_object() {
+        Some(obj) => obj.iter()
+            .filter(|(_, data)| data["users"][username]["role"] == "owner")
+            .map(|(name, data)| (name.clone(), data.clone()))
+            .collect(),
+        None => serde_json::Map::new(),
+    };
+
+    if owned.is_empty() {
+        send_message(&client, &"You don't own any rooms to export".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let stamp = chrono::Local::now().format("%y%m%d%H%M%S").to_string();
+    let final_filename = format!("{username}_rooms_{stamp}.json");
+    let export_path = format!("data/vault/rooms/{final_filename}");
+    let export_file = match OpenOptions::new().create(true).write(true).truncate(true).open(&export_path) {
+        Ok(f)  => f,
+        Err(e) => {
+            send_error(&client, &format!("Error creating {export_path}: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let mut writer = BufWriter::new(export_file);
+    let formatter = PrettyFormatter::with_indent(b"    ");
+    let mut ser = Serializer::with_formatter(&mut writer, formatter);
+    let count = owned.len();
+    serde_json::Value::Object(owned).serialize(&mut ser)?;
+
+    send_success(&client, &format!("Exported {count} room(s) to: {final_filename}"))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_whitelist(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    let room_guard = lock_room(&room_arc);
+    let mut c = lock_client(&client);
+
+    if room_guard.whitelist_enabled {
+        send_success_locked(&mut c, "- Whitelist is currently ENABLED -")?;
+        if room_guard.whitelist.is_empty() {
+            send_success_locked(&mut c, "  > No users are currently whitelisted")?;
+        } else {
+            send_success_locked(&mut c, "Whitelisted users:")?;
+            for user in &room_guard.whitelist {
+                send_message_locked(&mut c, &format!("  > {}", user.cyan()))?;
+            }
+        }
+    } else {
+        send_success_locked(&mut c, "- Whitelist is currently DISABLED -")?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_whitelist_toggle(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let (enabled_now, whitelist) = {
+        let mut room_guard = lock_room(&room_arc);
+        room_guard.whitelist_enabled = !room_guard.whitelist_enabled;
+        let enabled = room_guard.whitelist_enabled;
+        let wl = room_guard.whitelist.clone();
+        drop(room_guard);
+        (enabled, wl)
+    };
+
+    drop(rooms_map);
+
+    {
+        let fresh_map = lock_rooms(rooms);
+        if let Err(e) = save_rooms_to_disk(&fresh_map) {
+            let mut c = lock_client(&client);
+            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    if enabled_now {
+        let clients_map = lock_clients(clients);
+        for c_arc in clients_map.values() {
+            if let Ok(mut target_c) = c_arc.try_lock() {
+                let (should_kick, u_clone) = if let ClientState::InRoom { username: u, room: r, .. } = &target_c.state {
+                    if r == room && !whitelist.contains(u) {
+                        let room_guard = lock_room(&room_arc);
+                        let is_owner = room_guard.users.get(u).map(|ud| ud.role == "owner").unwrap_or(false);
+                        drop(room_guard);
+                        (!is_owner, Some(u.clone()))
+                    } else {
+                        (false, None)
+                    }
+                } else {
+                    (false, None)
+                };
+
+                if should_kick {
+                    if let Some(uname) = u_clone {
+                        let _ = writeln!(target_c.stream, "/LOBBY_STATE");
+                        let _ = writeln!(target_c.stream, "{}", format!("The whitelist for '{room}' has been enabled, and you are not whitelisted.").red());
+                        target_c.state = ClientState::LoggedIn { username: uname };
+                    }
+                }
+            }
+        }
+    }
+
+    let mut c = lock_client(&client);
+    if enabled_now {
+        send_success_locked(&mut c, "Whitelist is now ENABLED")?;
+    } else {
+        send_success_locked(&mut c, "Whitelist is now DISABLED")?;
+    }
+    drop(c);
+
+    let _ = broadcast_room_list_to_all(clients, rooms);
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_whitelist_enforce(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+
+    let peers = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        let targets: Vec<String> = {
+            let mut rg = lock_room(&room_arc);
+            if !rg.whitelist_enabled {
+                send_message(&client, &"Error: The whitelist is disabled, enable it first with /super whitelist toggle".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+
+            let targets: Vec<String> = rg.online_users.iter()
+                .filter(|u| *u != username && !rg.whitelist.contains(u))
+                .filter(|u| role_rank(rg.users.get(*u).map(|r| r.role.as_str()).unwrap_or("user")) < role_rank("admin"))
+                .cloned()
+                .collect();
+            for u in &targets {
+                if let Some(rec) = rg.users.get_mut(u) {
+                    rec.last_seen = now;
+                }
+            }
+            rg.online_users.retain(|u| !targets.contains(u));
+            targets
+        };
+
+        if targets.is_empty() {
+            send_message(&client, &"Everyone online is already whitelisted".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let peers = lobby_targets(clients, room, &targets, &format!("The whitelist for '{room}' is being enforced, and you are not whitelisted."))?;
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            send_error(&client, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+        peers
+    };
+
+    let noun = if peers.len() == 1 { "user" } else { "users" };
+    send_success(&client, &format!("Removed {} {noun} not on the whitelist from {room}", peers.len()))?;
+
+    for (target, peer) in &peers {
+        log_event(peer, Some(target), Some(room), &format!("Removed from room {room} by whitelist enforcement"));
+        log_audit(username, target, room, "whitelist.enforce");
+    }
+
+    let _ = sync_room_members(rooms, clients, pubkeys, room);
+    let _ = broadcast_user_list(clients, rooms, room);
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_tags_add(client: Arc<Mutex<Client>>, ctx: RoomCtx, tags: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let mut added_any = false;
+    {
+        let mut room_guard = lock_room(&room_arc);
+        for tag in tags.split_whitespace().map(|t| t.to_lowercase()) {
+            if room_guard.tags.contains(&tag) {
+                send_message_locked(&mut c, &format!("Room is already tagged '{tag}'").cyan().to_string())?;
+            } else {
+                send_success_locked(&mut c, &format!("Added tag '{tag}'"))?;
+                room_guard.tags.push(tag);
+                added_any = true;
+            }
+        }
+    }
+
+    if !added_any {
+        return Ok(CommandResult::Handled);
+    }
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_tags_remove(client: Arc<Mutex<Client>>, ctx: RoomCtx, tags: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let mut removed_any = false;
+    {
+        let mut room_guard = lock_room(&room_arc);
+        for tag in tags.split_whitespace().map(|t| t.to_lowercase()) {
+            if let Some(pos) = room_guard.tags.iter().position(|t| *t == tag) {
+                room_guard.tags.remove(pos);
+                send_success_locked(&mut c, &format!("Removed tag '{tag}'"))?;
+                removed_any = true;
+            } else {
+                send_message_locked(&mut c, &format!("Room is not tagged '{tag}'").cyan().to_string())?;
+            }
+        }
+    }
+
+    if !removed_any {
+        return Ok(CommandResult::Handled);
+    }
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_filter_list(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    let room_guard = lock_room(&room_arc);
+
+    if room_guard.filter_enabled {
+        send_success_locked(&mut c, "- Word filter is currently ENABLED -")?;
+    } else {
+        send_success_locked(&mut c, "- Word filter is currently DISABLED -")?;
+    }
+    if room_guard.blocked_words.is_empty() {
+        send_success_locked(&mut c, "  > No words are currently blocked")?;
+    } else {
+        send_success_locked(&mut c, "Blocked words:")?;
+        for word in &room_guard.blocked_words {
+            send_message_locked(&mut c, &format!("  > {}", word.cyan()))?;
+        }
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_filter_toggle(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let enabled_now = {
+        let mut room_guard = lock_room(&room_arc);
+        room_guard.filter_enabled = !room_guard.filter_enabled;
+        room_guard.filter_enabled
+    };
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    if enabled_now {
+        send_success_locked(&mut c, "Word filter is now enabled")?;
+    } else {
+        send_success_locked(&mut c, "Word filter is now disabled")?;
+    }
+
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_rename(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, room: &String, new_name: &String) -> io::Result<CommandResult> {
-    let old_name = room.clone();
-
-    {
-        let clients_map = lock_clients(clients)?;
-        let mut rooms_map = lock_rooms(rooms)?;
-
-        if rooms_map.contains_key(new_name) {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room name '{new_name}' is already taken").yellow().to_string())?;
+pub fn handle_super_filter_add(client: Arc<Mutex<Client>>, ctx: RoomCtx, words: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
+    };
 
-        let room_arc = match rooms_map.remove(&old_name) {
-            Some(r) => r,
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &format!("Room '{old_name}' not found").yellow().to_string())?;
-                return Ok(CommandResult::Handled);
-            }
-        };
-
-        rooms_map.insert(new_name.clone(), Arc::clone(&room_arc));
-
-        for c_arc in clients_map.values() {
-            if let Ok(mut target_c) = c_arc.try_lock() {
-                if let ClientState::InRoom { room: r, .. } = &mut target_c.state {
-                    if r == &old_name {
-                        *r = new_name.clone();
-                        let _ = writeln!(target_c.stream, "/ROOM_NAME {new_name}");
-                        let _ = target_c.stream.flush();
-                    }
-                }
+    let mut added_any = false;
+    {
+        let mut room_guard = lock_room(&room_arc);
+        for word in words.split_whitespace().map(|w| w.to_lowercase()) {
+            if room_guard.blocked_words.contains(&word) {
+                send_message_locked(&mut c, &format!("'{word}' is already blocked").cyan().to_string())?;
+            } else {
+                send_success_locked(&mut c, &format!("Blocked word '{word}'"))?;
+                room_guard.blocked_words.push(word);
+                added_any = true;
             }
         }
+    }
 
-        if let Err(e) = save_rooms_to_disk(&rooms_map) {
-            let mut c = lock_client(&client)?;
-            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
-            return Ok(CommandResult::Handled);
-        }
+    if !added_any {
+        return Ok(CommandResult::Handled);
+    }
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
     }
 
-    let mut c = lock_client(&client)?;
-    send_success_locked(&mut c, &format!("Room renamed from '{old_name}' to '{new_name}'"))?;
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_export(client: Arc<Mutex<Client>>, _rooms: &Rooms, room: &String, filename: &String) -> io::Result<CommandResult> {
-    let file = match std::fs::File::open("data/rooms.json") {
-        Ok(f)  => f,
-        Err(e) => {
-            send_error(&client, &format!("Error opening rooms.json: {e}"))?;
-            return Ok(CommandResult::Handled);
-        }
-    };
-    
-    let reader = BufReader::new(file);
-    let rooms_json: serde_json::Value = match serde_json::from_reader(reader) {
-        Ok(v)  => v,
+pub fn handle_super_filter_remove(client: Arc<Mutex<Client>>, ctx: RoomCtx, words: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
         Err(e) => {
-            send_error(&client, &format!("Malformed rooms.json: {e}"))?;
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
 
-    let room_data = match rooms_json.get(room) {
-        Some(v) => v.clone(),
-        None => {
-            send_message(&client, &"Error: room data not found".yellow().to_string())?;
-            return Ok(CommandResult::Handled);
+    let mut removed_any = false;
+    {
+        let mut room_guard = lock_room(&room_arc);
+        for word in words.split_whitespace().map(|w| w.to_lowercase()) {
+            if room_guard.blocked_words.contains(&word) {
+                room_guard.blocked_words.retain(|w| w != &word);
+                send_success_locked(&mut c, &format!("Unblocked word '{word}'"))?;
+                removed_any = true;
+            } else {
+                send_message_locked(&mut c, &format!("'{word}' is not blocked").cyan().to_string())?;
+            }
         }
-    };
+    }
 
-    let final_filename = if filename.is_empty() {
-        let stamp = chrono::Local::now().format("%y%m%d%H%M%S").to_string();
-        format!("{room}_{stamp}.json")
-    } else if filename.ends_with(".json") {
-        filename.clone()
-    } else {
-        format!("{filename}.json")
-    };
+    if !removed_any {
+        return Ok(CommandResult::Handled);
+    }
 
-    let export_path = format!("data/vault/rooms/{final_filename}");
-    let export_file = match OpenOptions::new().create(true).write(true).truncate(true).open(&export_path) {
-        Ok(f)  => f,
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_pin_list(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
         Err(e) => {
-            send_error(&client, &format!("Error creating {export_path}: {e}"))?;
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
+    let room_guard = lock_room(&room_arc);
 
-    let mut writer = BufWriter::new(export_file);
-    let formatter = PrettyFormatter::with_indent(b"    ");
-    let mut ser = Serializer::with_formatter(&mut writer, formatter);
-    json!({ room: room_data }).serialize(&mut ser)?;
+    if room_guard.pinned.is_empty() {
+        send_message_locked(&mut c, &"No messages are pinned in this room".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+    send_success_locked(&mut c, &format!("Pinned ({}/{MAX_PINNED}):", room_guard.pinned.len()))?;
+    for (i, pin) in room_guard.pinned.iter().enumerate() {
+        send_message_locked(&mut c, &format!("  {}. {}", i + 1, pin.bright_magenta()))?;
+    }
 
-    send_success(&client, &format!("Exported room data to: {final_filename}"))?;
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_whitelist(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_super_pin_add(client: Arc<Mutex<Client>>, ctx: RoomCtx, text: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    if text.len() > MAX_MESSAGE_BYTES {
+        send_error(&client, "Pin too long")?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
-    let room_guard = lock_room(&room_arc)?;
-    let mut c = lock_client(&client)?;
 
-    if room_guard.whitelist_enabled {
-        send_success_locked(&mut c, "- Whitelist is currently ENABLED -")?;
-        if room_guard.whitelist.is_empty() {
-            send_success_locked(&mut c, "  > No users are currently whitelisted")?;
-        } else {
-            send_success_locked(&mut c, "Whitelisted users:")?;
-            for user in &room_guard.whitelist {
-                send_message_locked(&mut c, &format!("  > {}", user.cyan()))?;
-            }
+    let count = {
+        let mut room_guard = lock_room(&room_arc);
+        if room_guard.pinned.len() >= MAX_PINNED {
+            send_message_locked(&mut c, &format!("Error: A room can have at most {MAX_PINNED} pins, remove one first").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
         }
-    } else {
-        send_success_locked(&mut c, "- Whitelist is currently DISABLED -")?;
+        room_guard.pinned.push(text.to_string());
+        room_guard.pinned.len()
+    };
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
     }
 
+    send_success_locked(&mut c, &format!("Pinned as #{count}"))?;
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_whitelist_toggle(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, room: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_super_pin_remove(client: Arc<Mutex<Client>>, ctx: RoomCtx, index: usize) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
 
-    let (enabled_now, whitelist) = {
-        let mut room_guard = lock_room(&room_arc)?;
-        room_guard.whitelist_enabled = !room_guard.whitelist_enabled;
-        let enabled = room_guard.whitelist_enabled;
-        let wl = room_guard.whitelist.clone();
-        drop(room_guard);
-        (enabled, wl)
+    let removed = {
+        let mut room_guard = lock_room(&room_arc);
+        if index > room_guard.pinned.len() {
+            send_message_locked(&mut c, &format!("Error: There is no pin #{index}").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+        room_guard.pinned.remove(index - 1)
     };
 
-    drop(rooms_map);
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
 
-    {
-        let fresh_map = lock_rooms(rooms)?;
-        if let Err(e) = save_rooms_to_disk(&fresh_map) {
-            let mut c = lock_client(&client)?;
-            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+    send_success_locked(&mut c, &format!("Unpinned #{index}: {removed}"))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_pin_clear(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
 
-    if enabled_now {
-        let clients_map = lock_clients(clients)?;
-        for c_arc in clients_map.values() {
-            if let Ok(mut target_c) = c_arc.try_lock() {
-                let (should_kick, u_clone) = if let ClientState::InRoom { username: u, room: r, .. } = &target_c.state {
-                    if r == room && !whitelist.contains(u) {
-                        let room_guard = lock_room(&room_arc)?;
-                        let is_owner = room_guard.users.get(u).map(|ud| ud.role == "owner").unwrap_or(false);
-                        drop(room_guard);
-                        (!is_owner, Some(u.clone()))
-                    } else {
-                        (false, None)
-                    }
-                } else {
-                    (false, None)
-                };
+    let cleared = {
+        let mut room_guard = lock_room(&room_arc);
+        let n = room_guard.pinned.len();
+        room_guard.pinned.clear();
+        n
+    };
 
-                if should_kick {
-                    if let Some(uname) = u_clone {
-                        let _ = writeln!(target_c.stream, "/LOBBY_STATE");
-                        let _ = writeln!(target_c.stream, "{}", format!("The whitelist for '{room}' has been enabled, and you are not whitelisted.").red());
-                        target_c.state = ClientState::LoggedIn { username: uname };
-                    }
-                }
-            }
-        }
+    if cleared == 0 {
+        send_message_locked(&mut c, &"No messages are pinned in this room".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
     }
 
-    let mut c = lock_client(&client)?;
-    if enabled_now {
-        send_success_locked(&mut c, "Whitelist is now ENABLED")?;
-    } else {
-        send_success_locked(&mut c, "Whitelist is now DISABLED")?;
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
     }
-    drop(c);
-
-    let _ = broadcast_room_list_to_all(clients, rooms);
 
+    send_success_locked(&mut c, &format!("Cleared {cleared} pin(s)"))?;
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_whitelist_add(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, room: &String, users: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let mut c = lock_client(&client)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_super_whitelist_add(client: Arc<Mutex<Client>>, ctx: RoomCtx, users: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
 
     let mut added_any = false;
     {
-        let mut room_guard = lock_room(&room_arc)?;
+        let mut room_guard = lock_room(&room_arc);
         for user in users.split_whitespace() {
             if room_guard.whitelist.contains(&user.to_string()) {
                 send_message_locked(&mut c, &format!("'{user}' is already whitelisted").cyan().to_string())?;
@@ -336,8 +1578,8 @@ pub fn handle_super_whitelist_add(client: Arc<Mutex<Client>>, clients: &Clients,
         drop(rooms_map);
 
         {
-            let fresh_map = lock_rooms(rooms)?;
-            let mut c = lock_client(&client)?;
+            let fresh_map = lock_rooms(rooms);
+            let mut c = lock_client(&client);
             if let Err(e) = save_rooms_to_disk(&fresh_map) {
                 send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
             }
@@ -349,13 +1591,86 @@ pub fn handle_super_whitelist_add(client: Arc<Mutex<Client>>, clients: &Clients,
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_whitelist_remove(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, room: &String, users: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let mut c = lock_client(&client)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_super_whitelist_import(client: Arc<Mutex<Client>>, ctx: RoomCtx, filename: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
+    if !is_safe_filename(filename) {
+        send_message(&client, &"Error: Invalid file name".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let import_path = format!("data/vault/whitelists/{filename}");
+    let contents = match std::fs::read_to_string(&import_path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            send_message(&client, &format!("Error: {import_path} is not a valid text file").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+        Err(_) => {
+            send_message(&client, &format!("Error: Could not open {import_path}").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let mut listed: Vec<String> = Vec::new();
+    for user in contents.split_whitespace() {
+        if !listed.iter().any(|u| u == user) {
+            listed.push(user.to_string());
+        }
+    }
+
+    if listed.is_empty() {
+        send_message(&client, &format!("No usernames found in {import_path}").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let (added, already) = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        let (added, already) = {
+            let mut room_guard = lock_room(&room_arc);
+            let mut added = 0;
+            for user in &listed {
+                if !room_guard.whitelist.contains(user) {
+                    room_guard.whitelist.push(user.clone());
+                    added += 1;
+                }
+            }
+            (added, listed.len() - added)
+        };
+
+        if added > 0 {
+            if let Err(e) = save_rooms_to_disk(&rooms_map) {
+                send_error(&client, &format!("Failed to save rooms: {e}"))?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+        (added, already)
+    };
+
+    send_success(&client, &format!("Imported whitelist from {filename}: {added} added, {already} already present"))?;
+
+    if added > 0 {
+        let _ = broadcast_room_list_to_all(clients, rooms);
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_whitelist_remove(client: Arc<Mutex<Client>>, ctx: RoomCtx, users: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let mut c = lock_client(&client);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure_locked(&mut c, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
@@ -363,7 +1678,7 @@ pub fn handle_super_whitelist_remove(client: Arc<Mutex<Client>>, clients: &Clien
     let mut removed_any = false;
     let mut removed_users = Vec::new();
     let whitelist_enabled = {
-        let mut room_guard = lock_room(&room_arc)?;
+        let mut room_guard = lock_room(&room_arc);
         for user in users.split_whitespace() {
             if let Some(pos) = room_guard.whitelist.iter().position(|u| u == user) {
                 room_guard.whitelist.remove(pos);
@@ -384,8 +1699,8 @@ pub fn handle_super_whitelist_remove(client: Arc<Mutex<Client>>, clients: &Clien
         drop(rooms_map);
 
         {
-            let fresh_map = lock_rooms(rooms)?;
-            let mut c = lock_client(&client)?;
+            let fresh_map = lock_rooms(rooms);
+            let mut c = lock_client(&client);
             if let Err(e) = save_rooms_to_disk(&fresh_map) {
                 send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
             }
@@ -393,19 +1708,19 @@ pub fn handle_super_whitelist_remove(client: Arc<Mutex<Client>>, clients: &Clien
 
         if whitelist_enabled {
             let room_arc = {
-                let fresh_map = lock_rooms(rooms)?;
+                let fresh_map = lock_rooms(rooms);
                 match fresh_map.get(room) {
                     Some(r) => Arc::clone(r),
                     None => return Ok(CommandResult::Handled),
                 }
             };
 
-            let clients_map = lock_clients(clients)?;
+            let clients_map = lock_clients(clients);
             for c_arc in clients_map.values() {
                 if let Ok(mut target_c) = c_arc.try_lock() {
                     let (should_kick, u_clone) = if let ClientState::InRoom { username: u, room: r, .. } = &target_c.state {
                         if r == room && removed_users.contains(u) {
-                            let room_guard = lock_room(&room_arc)?;
+                            let room_guard = lock_room(&room_arc);
                             let is_owner = room_guard.users.get(u).map(|ud| ud.role == "owner").unwrap_or(false);
                             drop(room_guard);
                             (!is_owner, Some(u.clone()))
@@ -433,18 +1748,18 @@ pub fn handle_super_whitelist_remove(client: Arc<Mutex<Client>>, clients: &Clien
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_limit(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_super_limit(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
-    let room_guard = lock_room(&room_arc)?;
-    let mut c = lock_client(&client)?;
+    let room_guard = lock_room(&room_arc);
+    let mut c = lock_client(&client);
 
     let rate_display = if room_guard.msg_rate == 0 {
         "UNLIMITED".to_string()
@@ -458,33 +1773,39 @@ pub fn handle_super_limit(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &Stri
         format!("{}", room_guard.session_timeout)
     };
 
-    writeln!(c.stream, "{}\n  > Message rate: {} messages per 5 sec\n  > Session timeout: {} sec of inactivity", "Current limits:".green(), rate_display.to_string().green(), timeout_display.to_string().green())?;
+    let slowmode_display = if room_guard.slowmode_secs == 0 {
+        "OFF".to_string()
+    } else {
+        format!("{} sec between messages", room_guard.slowmode_secs)
+    };
+
+    writeln!(c.stream, "{}\n  > Message rate: {} messages per 5 sec\n  > Session timeout: {} sec of inactivity\n  > Slowmode: {}", "Current limits:".green(), rate_display.to_string().green(), timeout_display.to_string().green(), slowmode_display.green())?;
     c.stream.flush()?;
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_limit_rate(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String, limit: u8) -> io::Result<CommandResult> {            
-    let rooms_map   = lock_rooms(rooms)?;
-    let room_arc    = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+pub fn handle_super_limit_rate(client: Arc<Mutex<Client>>, ctx: RoomCtx, limit: u8) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map   = lock_rooms(rooms);
+    let room_arc    = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
     {
-        let mut room_guard = lock_room(&room_arc)?;
+        let mut room_guard = lock_room(&room_arc);
         room_guard.msg_rate = limit;
     }
 
     if let Err(e) = save_rooms_to_disk(&rooms_map) {
-        let mut c = lock_client(&client)?;
+        let mut c = lock_client(&client);
         send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
         return Ok(CommandResult::Handled);
     }
 
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     if limit == 0 {
         send_success_locked(&mut c, "Message rate limit set to UNLIMITED")?;
     } else {
@@ -494,28 +1815,79 @@ pub fn handle_super_limit_rate(client: Arc<Mutex<Client>>, rooms: &Rooms, room:
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_limit_session(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String, limit: u32) -> io::Result<CommandResult> {
-    let rooms_map   = lock_rooms(rooms)?;
-    let room_arc    = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &"Room not found".yellow().to_string())?;
+pub fn handle_super_log(client: Arc<Mutex<Client>>, ctx: RoomCtx, count: usize) -> io::Result<CommandResult> {
+    let RoomCtx { room, .. } = ctx;
+    let entries = match read_audit_log(room, count) {
+        Ok(e) => e,
+        Err(e) => {
+            send_error(&client, &format!("Failed to read audit log: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    if entries.is_empty() {
+        send_message(&client, &"No moderation actions have been logged for this room".yellow().to_string())?;
+    } else {
+        let lines: Vec<String> = entries.iter().map(|e| format!("> {e}")).collect();
+        send_success(&client, &format!("Last {} moderation action(s):\n{}", entries.len(), lines.join("\n")))?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_slowmode(client: Arc<Mutex<Client>>, ctx: RoomCtx, secs: u32) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map   = lock_rooms(rooms);
+    let room_arc    = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    {
+        let mut room_guard = lock_room(&room_arc);
+        room_guard.slowmode_secs = secs;
+    }
+
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        let mut c = lock_client(&client);
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let mut c = lock_client(&client);
+    if secs == 0 {
+        send_success_locked(&mut c, "Slowmode turned OFF")?;
+    } else {
+        send_success_locked(&mut c, &format!("Slowmode set to one message every {secs} sec"))?;
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_limit_session(client: Arc<Mutex<Client>>, ctx: RoomCtx, limit: u32) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map   = lock_rooms(rooms);
+    let room_arc    = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
     {
-        let mut room_guard = lock_room(&room_arc)?;
+        let mut room_guard = lock_room(&room_arc);
         room_guard.session_timeout = limit;
     }
 
     if let Err(e) = save_rooms_to_disk(&rooms_map) {
-        let mut c = lock_client(&client)?;
+        let mut c = lock_client(&client);
         send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
         return Ok(CommandResult::Handled);
     }
 
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     if limit == 0 {
         send_success_locked(&mut c, "Session timeout set to UNLIMITED")?;
     } else {
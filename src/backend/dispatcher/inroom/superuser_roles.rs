@@ -1,24 +1,26 @@
-use std::io::{self, BufRead};
+use std::io::{self};
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use colored::*;
 
-use crate::shared::types::{Client, ClientState, Clients, Rooms, RoomUser, PublicKeys};
-use crate::shared::utils::{lock_client, lock_rooms, lock_room, save_rooms_to_disk, ColorizeExt, send_message_locked, send_error_locked, send_success_locked, broadcast_user_list};
+use crate::shared::types::{Client, ClientState, Roles, RoomUser};
+use crate::shared::utils::{find_room, send_failure, format_duration, lock_client, lock_rooms, lock_room, save_rooms_to_disk, ColorizeExt, send_message_locked, send_error_locked, send_success_locked, log_audit, broadcast_user_list, read_prompt_line};
 use crate::backend::dispatcher::CommandResult;
-use crate::backend::command_utils::{RESTRICTED_COMMANDS, command_order, sync_room_commands};
-
-pub fn handle_super_roles(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+use crate::backend::dispatcher::inroom::RoomCtx;
+use crate::backend::command_utils::{is_safe_filename, RESTRICTED_COMMANDS, DEFAULT_MODERATOR_COMMANDS, DEFAULT_USER_COMMANDS, MAX_ROLE_LABEL_LEN, command_order, sync_room_commands};
+
+pub fn handle_super_roles(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
-    let room_guard = lock_room(&room_arc)?;
-    let mut c = lock_client(&client)?;
+    let room_guard = lock_room(&room_arc);
+    let mut c = lock_client(&client);
 
     let mod_cmds = &room_guard.roles.moderator;
     let user_cmds = &room_guard.roles.user;
@@ -53,33 +55,112 @@ pub fn handle_super_roles(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &Stri
     Ok(CommandResult::Handled)
 }
         
-pub fn handle_super_roles_add(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, room: &String, role: &String, commands: &String) -> io::Result<CommandResult> {
+fn editable_role(role: &str) -> Option<&'static str> {
+    match role.to_lowercase().as_str() {
+        "user" => Some("user"),
+        "mod" | "moderator" => Some("moderator"),
+        _ => None,
+    }
+}
+
+fn unknown_commands(cmd_tokens: &[&str]) -> Vec<String> {
+    cmd_tokens.iter().filter(|c_token| !RESTRICTED_COMMANDS.contains(**c_token)).map(|c_token| (*c_token).to_string()).collect()
+}
+
+pub fn handle_super_roles_preview(client: Arc<Mutex<Client>>, ctx: RoomCtx, role: &str, commands: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let target_role = match editable_role(role) {
+        Some(r) => r,
+        None => {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &"Error: Role must be user|mod".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let current = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        let room_guard = lock_room(&room_arc);
+        if target_role == "moderator" {
+            room_guard.roles.moderator.clone()
+        } else {
+            room_guard.roles.user.clone()
+        }
+    };
+
+    let cmd_tokens: Vec<&str> = commands.split_whitespace().collect();
+    let invalid = unknown_commands(&cmd_tokens);
+    let mut added: Vec<String> = Vec::new();
+    for &c_token in &cmd_tokens {
+        if RESTRICTED_COMMANDS.contains(c_token) && !current.iter().any(|e| e == c_token) && !added.iter().any(|e| e == c_token) {
+            added.push(c_token.to_string());
+        }
+    }
+
+    let mut lines = vec![format!("[preview] {target_role} with: {}", cmd_tokens.join(" "))];
+    if invalid.is_empty() {
+        lines.push("All commands are valid".to_string());
+    } else {
+        lines.push(format!("Unknown commands: {}", invalid.join(", ")));
+    }
+    if added.is_empty() {
+        lines.push("No new permissions would be granted".to_string());
+    } else {
+        lines.push(format!("Would add: {}", added.join(", ")));
+    }
+
+    let allowed: Vec<&str> = command_order().into_iter()
+        .filter(|cmd| current.iter().any(|e| e == cmd) || added.iter().any(|e| e == cmd))
+        .collect();
+    if allowed.is_empty() {
+        lines.push(format!("The {target_role} role would have no addable commands"));
+    } else {
+        lines.push(format!("The {target_role} role would be able to use:"));
+        for cmd in allowed {
+            let mark = if added.iter().any(|e| e == cmd) { "+" } else { " " };
+            let indent = if cmd.contains('.') { "   " } else { "" };
+            lines.push(format!("  > {mark} {indent}{cmd}"));
+        }
+    }
+
+    let mut c = lock_client(&client);
+    send_message_locked(&mut c, &lines.join("\n").cyan().to_string())?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_roles_add(client: Arc<Mutex<Client>>, ctx: RoomCtx, role: &str, commands: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
     let mut added = Vec::<String>::new();
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
-        let mut room_guard = lock_room(&room_arc)?;
-        let mut c = lock_client(&client)?;
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
 
-        let target_role = match role.to_lowercase().as_str() {
-            "user" => "user",
-            "mod" | "moderator" => "moderator",
-            _ => {
+        let target_role = match editable_role(role) {
+            Some(r) => r,
+            None => {
                 send_message_locked(&mut c, &"Error: Role must be user|mod".yellow().to_string())?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         let cmd_tokens: Vec<&str> = commands.split_whitespace().collect();
-        let invalid: Vec<String> = cmd_tokens.iter().filter(|c_token| !RESTRICTED_COMMANDS.contains(**c_token)).map(|c_token| (*c_token).to_string()).collect();
+        let invalid = unknown_commands(&cmd_tokens);
         if !invalid.is_empty() {
             send_message_locked(&mut c, &format!("Error: Unknown commands: {}", invalid.join(", ")).yellow().to_string())?;
             return Ok(CommandResult::Handled);
@@ -119,33 +200,32 @@ pub fn handle_super_roles_add(client: Arc<Mutex<Client>>, clients: &Clients, roo
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_roles_revoke(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, room: &String, role: &String, commands: &String) -> io::Result<CommandResult> {
+pub fn handle_super_roles_revoke(client: Arc<Mutex<Client>>, ctx: RoomCtx, role: &str, commands: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
     let mut removed = Vec::<String>::new();
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
-        let mut room_guard = lock_room(&room_arc)?;
-        let mut c = lock_client(&client)?;
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
 
-        let target_role = match role.to_lowercase().as_str() {
-            "user" => "user",
-            "mod" | "moderator" => "moderator",
-            _ => {
+        let target_role = match editable_role(role) {
+            Some(r) => r,
+            None => {
                 send_message_locked(&mut c, &"Error: Role must be user|mod".yellow().to_string())?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         let cmd_tokens: Vec<&str> = commands.split_whitespace().collect();
-        let invalid: Vec<String> = cmd_tokens.iter().filter(|c_token| !RESTRICTED_COMMANDS.contains(**c_token)).map(|c_token| (*c_token).to_string()).collect();
+        let invalid = unknown_commands(&cmd_tokens);
         if !invalid.is_empty() {
             send_message_locked(&mut c, &format!("Error: Unknown commands: {}", invalid.join(", ")).yellow().to_string())?;
             return Ok(CommandResult::Handled);
@@ -185,14 +265,15 @@ pub fn handle_super_roles_revoke(client: Arc<Mutex<Client>>, clients: &Clients,
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, room: &String, role: &String, users: &String) -> io::Result<CommandResult> {
+pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, ctx: RoomCtx, role: &str, users: &str, duration: u64) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, room, .. } = ctx;
     let target_role = match role.to_lowercase().as_str() {
         "usr" | "user" => "user",
         "mod" | "moderator" => "moderator",
         "admin" | "administrator" => "admin",
         "owner" | "creator" | "founder" => "owner",
         _ => {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_message_locked(&mut c, &"Error: Role must be user|mod|admin|owner".yellow().to_string())?;
             return Ok(CommandResult::Handled);
         }
@@ -200,20 +281,26 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
 
     let users_vec: Vec<&str> = users.split_whitespace().collect();
     if users_vec.is_empty() {
-        let mut c = lock_client(&client)?;
+        let mut c = lock_client(&client);
         send_message_locked(&mut c, &"Error: No users specified".yellow().to_string())?;
         return Ok(CommandResult::Handled);
     }
 
+    if target_role == "owner" && duration > 0 {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &"Error: Ownership cannot be assigned temporarily".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
     if target_role == "owner" && users_vec.len() != 1 {
-        let mut c = lock_client(&client)?;
+        let mut c = lock_client(&client);
         send_message_locked(&mut c, &"Error: Only 1 user may be assigned to owner".yellow().to_string())?;
         return Ok(CommandResult::Handled);
     }
 
     let username;
     {
-        let c = lock_client(&client)?;
+        let c = lock_client(&client);
         username = match &c.state {
             ClientState::InRoom { username, .. } => username.clone(),
             _ => return Ok(CommandResult::Handled),
@@ -223,20 +310,19 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
     let mut owner_transfer_approved = false;
     if target_role == "owner" {
         {
-            let rooms_map = lock_rooms(rooms)?;
-            let room_arc = match rooms_map.get(room) {
-                Some(r) => Arc::clone(r),
-                None => {
-                    let mut c = lock_client(&client)?;
-                    send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+            let rooms_map = lock_rooms(rooms);
+            let room_arc = match find_room(&rooms_map, room) {
+                Ok(r) => r,
+                Err(e) => {
+                    send_failure(&client, &e)?;
                     return Ok(CommandResult::Handled);
                 }
             };
-            let room_guard = lock_room(&room_arc)?;
+            let room_guard = lock_room(&room_arc);
             match room_guard.users.get(&username) {
                 Some(u) if u.role == "owner" => {},
                 _ => {
-                    let mut c = lock_client(&client)?;
+                    let mut c = lock_client(&client);
                     send_message_locked(&mut c, &"Error: Only the room owner can transfer ownership".yellow().to_string())?;
                     return Ok(CommandResult::Handled);
                 }
@@ -244,7 +330,7 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
         }
 
         let new_owner = users_vec[0];
-        let mut c = lock_client(&client)?;
+        let mut c = lock_client(&client);
         use std::io::Write;
         writeln!(c.stream, "{}", format!("Assigning {new_owner} as owner will transfer room ownership to them. Are you sure you want to do this? (y/n): ").red())?;
         c.stream.flush()?;
@@ -253,16 +339,16 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
         drop(c);
         loop {
             let mut line = String::new();
-            if reader.read_line(&mut line)? == 0 { return Ok(CommandResult::Stop); }
+            if read_prompt_line(&mut reader, &mut line, &client)? == 0 { return Ok(CommandResult::Stop); }
             match line.trim().to_lowercase().as_str() {
                 "y" => { owner_transfer_approved = true; break; },
                 "n" => {
-                    let mut c = lock_client(&client)?;
+                    let mut c = lock_client(&client);
                     send_message_locked(&mut c, &"Owner transfer cancelled".yellow().to_string())?;
                     return Ok(CommandResult::Handled);
                 }
                 _ => {
-                    let mut c = lock_client(&client)?;
+                    let mut c = lock_client(&client);
                     writeln!(c.stream, "{}", "(y/n): ".red())?;
                     c.stream.flush()?;
                     drop(c);
@@ -271,19 +357,23 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
         }
     }
 
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+
     let mut assigned = Vec::<String>::new();
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
-        let mut room_guard = lock_room(&room_arc)?;
-        let mut c = lock_client(&client)?;
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
 
         if target_role == "owner" && owner_transfer_approved {
             let new_owner = users_vec[0];
@@ -298,10 +388,20 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
             let entry = room_guard.users.entry(u.to_string()).or_insert(RoomUser {
                 nick: "".to_string(), color: "".to_string(), role: "user".to_string(),
                 hidden: false, last_seen: 0, banned: false, ban_stamp: 0, ban_length: 0, ban_reason: "".to_string(),
-                muted: false, mute_stamp: 0, mute_length: 0, mute_reason: "".to_string()
+                muted: false, mute_stamp: 0, mute_length: 0, mute_reason: "".to_string(),
+                role_expiry: 0, prior_role: "".to_string(), ban_history: Vec::new()
             });
             if entry.role == "owner" && target_role != "owner" { continue; }
             if entry.role != target_role {
+                if duration > 0 {
+                    if entry.role_expiry == 0 {
+                        entry.prior_role = entry.role.clone();
+                    }
+                    entry.role_expiry = now.saturating_add(duration);
+                } else {
+                    entry.role_expiry = 0;
+                    entry.prior_role.clear();
+                }
                 entry.role = target_role.to_string();
                 assigned.push(u.to_string());
             }
@@ -318,7 +418,15 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
         if assigned.is_empty() {
             send_message_locked(&mut c, &"No role changes made".yellow().to_string())?;
         } else {
-            send_success_locked(&mut c, &format!("Assigned role '{target_role}' to: {}", assigned.join(", ")))?;
+            let expiry = if duration > 0 {
+                format!(" for {}", format_duration(duration))
+            } else {
+                "".to_string()
+            };
+            send_success_locked(&mut c, &format!("Assigned role '{target_role}'{expiry} to: {}", assigned.join(", ")))?;
+            for u in &assigned {
+                log_audit(&username, u, room, &format!("role.assign {target_role}{expiry}"));
+            }
         }
     }
 
@@ -331,27 +439,27 @@ pub fn handle_super_roles_assign(client: Arc<Mutex<Client>>, clients: &Clients,
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_super_roles_recolor(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, room: &String, role: &String, color: &String) -> io::Result<CommandResult> {
+pub fn handle_super_roles_recolor(client: Arc<Mutex<Client>>, ctx: RoomCtx, role: &str, color: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, room, .. } = ctx;
     let hex = color.trim().trim_start_matches('#');
     if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
-        let mut c = lock_client(&client)?;
+        let mut c = lock_client(&client);
         send_message_locked(&mut c, &"Error: Color must be a 6‑digit hex value".yellow().to_string())?;
         return Ok(CommandResult::Handled);
     }
     let hex_with_hash = format!("#{hex}");
 
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
-            None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
-        let mut room_guard = lock_room(&room_arc)?;
-        let mut c = lock_client(&client)?;
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
 
         let role_key = match role.to_lowercase().as_str() {
             "user" => "user",
@@ -381,3 +489,465 @@ pub fn handle_super_roles_recolor(client: Arc<Mutex<Client>>, clients: &Clients,
     let _ = broadcast_user_list(clients, rooms, room);
     Ok(CommandResult::Handled)
 }
+
+pub fn handle_super_roles_label(client: Arc<Mutex<Client>>, ctx: RoomCtx, role: &str, label: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let role_key = match role.to_lowercase().as_str() {
+        "user" => "user",
+        "mod" | "moderator" => "moderator",
+        "admin" => "admin",
+        "owner" => "owner",
+        _ => {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &"Error: Role must be user|mod|admin|owner".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let label = label.trim();
+    let reset = label == "*";
+    if !reset && (label.chars().count() > MAX_ROLE_LABEL_LEN || label.chars().any(|ch| ch.is_control() || ch == '[' || ch == ']')) {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &format!("Error: Role labels must be at most {MAX_ROLE_LABEL_LEN} characters and cannot contain brackets").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    let display = {
+        let mut room_guard = lock_room(&room_arc);
+        if reset {
+            room_guard.role_labels.remove(role_key);
+        } else {
+            room_guard.role_labels.insert(role_key.to_string(), label.to_string());
+        }
+        room_guard.role_label(role_key)
+    };
+
+    let mut c = lock_client(&client);
+    if let Err(e) = save_rooms_to_disk(&rooms_map) {
+        send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    if reset {
+        send_success_locked(&mut c, &format!("Label for {role_key} role reset to {display}"))?;
+    } else {
+        send_success_locked(&mut c, &format!("Label for {role_key} role changed to {display}"))?;
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_room_transfer(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, room, .. } = ctx;
+    let username;
+    {
+        let c = lock_client(&client);
+        username = match &c.state {
+            ClientState::InRoom { username, .. } => username.clone(),
+            _ => return Ok(CommandResult::Handled),
+        };
+    }
+
+    if target == username {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &"Error: You already own this room".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        let room_guard = lock_room(&room_arc);
+        match room_guard.users.get(&username) {
+            Some(u) if u.role == "owner" => {},
+            _ => {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &"Error: Only the room owner can transfer ownership".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+        if !room_guard.users.contains_key(target) {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &format!("Error: {target} is not a member of this room").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
+    {
+        let mut c = lock_client(&client);
+        use std::io::Write;
+        writeln!(c.stream, "{}", format!("Are you sure you want to transfer ownership of {room} to {target}? You will become an admin. (y/n): ").red())?;
+
+        let mut reader = std::io::BufReader::new(c.stream.try_clone()?);
+        drop(c);
+        loop {
+            let mut line = String::new();
+            let bytes_read = read_prompt_line(&mut reader, &mut line, &client)?;
+            if bytes_read == 0 {
+                return Ok(CommandResult::Stop);
+            }
+
+            match line.trim().to_lowercase().as_str() {
+                "y" => break,
+                "n" => {
+                    let mut c = lock_client(&client);
+                    send_message_locked(&mut c, &"Owner transfer cancelled".yellow().to_string())?;
+                    return Ok(CommandResult::Handled);
+                },
+                _ => {
+                    let mut c = lock_client(&client);
+                    writeln!(c.stream, "{}", "(y/n): ".red())?;
+                }
+            }
+        }
+    }
+
+    {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
+
+        match room_guard.users.get_mut(target) {
+            Some(new_owner) => {
+                new_owner.role = "owner".to_string();
+                new_owner.role_expiry = 0;
+                new_owner.prior_role.clear();
+            }
+            None => {
+                send_message_locked(&mut c, &format!("Error: {target} is not a member of this room").yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+        if let Some(cur_owner) = room_guard.users.get_mut(&username) {
+            cur_owner.role = "admin".to_string();
+        }
+        drop(room_guard);
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+        send_success_locked(&mut c, &format!("Transferred ownership of {room} to {target}"))?;
+        log_audit(&username, target, room, "room.transfer");
+    }
+
+    let _ = sync_room_commands(rooms, clients, room);
+    let _ = crate::backend::command_utils::sync_room_members(rooms, clients, pubkeys, room);
+    let _ = broadcast_user_list(clients, rooms, room);
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_handoff(client: Arc<Mutex<Client>>, ctx: RoomCtx, target: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    if let CommandResult::Stop = handle_room_transfer(client.clone(), ctx, target)? {
+        return Ok(CommandResult::Stop);
+    }
+
+    // Only leave if the transfer actually went through (not cancelled or rejected)
+    let transferred = {
+        let rooms_map = lock_rooms(rooms);
+        match rooms_map.get(room) {
+            Some(room_arc) => {
+                let room_guard = lock_room(room_arc);
+                room_guard.users.get(target).is_some_and(|u| u.role == "owner")
+                    && room_guard.users.get(username).is_some_and(|u| u.role != "owner")
+            }
+            None => false,
+        }
+    };
+    if !transferred {
+        return Ok(CommandResult::Handled);
+    }
+
+    crate::backend::dispatcher::inroom::leave_room(&client, clients, rooms, pubkeys, username, room)?;
+    log_audit(username, target, room, "room.handoff");
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_roles_reset(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, username, room, .. } = ctx;
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+    };
+
+    {
+        let room_guard = lock_room(&room_arc);
+        if room_guard.users.get(username).is_none_or(|u| u.role != "owner") {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &"Error: Only the room owner can reset role permissions".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
+    {
+        let mut c = lock_client(&client);
+        use std::io::Write;
+        writeln!(c.stream, "{}", format!("Are you sure you want to reset the User and Moderator permissions in {room} to the defaults? (y/n): ").red())?;
+        c.stream.flush()?;
+
+        let mut reader = std::io::BufReader::new(c.stream.try_clone()?);
+        drop(c);
+        loop {
+            let mut line = String::new();
+            if read_prompt_line(&mut reader, &mut line, &client)? == 0 { return Ok(CommandResult::Stop); }
+            match line.trim().to_lowercase().as_str() {
+                "y" => break,
+                "n" => {
+                    let mut c = lock_client(&client);
+                    send_message_locked(&mut c, &"Role reset cancelled".yellow().to_string())?;
+                    return Ok(CommandResult::Handled);
+                }
+                _ => {
+                    let mut c = lock_client(&client);
+                    writeln!(c.stream, "{}", "(y/n): ".red())?;
+                    c.stream.flush()?;
+                    drop(c);
+                }
+            }
+        }
+    }
+
+    {
+        let rooms_map = lock_rooms(rooms);
+        {
+            let mut room_guard = lock_room(&room_arc);
+            room_guard.roles.moderator = DEFAULT_MODERATOR_COMMANDS.iter().map(|s| s.to_string()).collect();
+            room_guard.roles.user = DEFAULT_USER_COMMANDS.iter().map(|s| s.to_string()).collect();
+        }
+
+        let mut c = lock_client(&client);
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+        send_success_locked(&mut c, "Default User and Moderator permissions restored")?;
+    }
+
+    let _ = sync_room_commands(rooms, clients, room);
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_roles_copy(client: Arc<Mutex<Client>>, ctx: RoomCtx, source: &str, colors: bool) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    if source == room {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &"Error: Choose a different room to copy permissions from".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let (mod_count, user_count) = {
+        let rooms_map = lock_rooms(rooms);
+        let (source_arc, room_arc) = match find_room(&rooms_map, source).and_then(|s| Ok((s, find_room(&rooms_map, room)?))) {
+            Ok(pair) => pair,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        let roles = {
+            let source_guard = lock_room(&source_arc);
+            if source_guard.users.get(username).is_none_or(|u| u.role != "owner") {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &format!("Error: You must own {source} to copy its permissions").yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+            source_guard.roles.clone()
+        };
+
+        {
+            let mut room_guard = lock_room(&room_arc);
+            if room_guard.users.get(username).is_none_or(|u| u.role != "owner") {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &"Error: Only the room owner can copy role permissions".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+            room_guard.roles.moderator = roles.moderator.clone();
+            room_guard.roles.user = roles.user.clone();
+            if colors {
+                room_guard.roles.colors = roles.colors.clone();
+            }
+        }
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            let mut c = lock_client(&client);
+            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+        (roles.moderator.len(), roles.user.len())
+    };
+
+    let extra = if colors { ", along with the role colors" } else { "" };
+    {
+        let mut c = lock_client(&client);
+        send_success_locked(&mut c, &format!("Copied permissions from {source}: Moderator ({mod_count} commands), User ({user_count} commands){extra}"))?;
+    }
+    log_audit(username, "-", room, &format!("roles.copy from {source}"));
+
+    let _ = sync_room_commands(rooms, clients, room);
+    if colors {
+        let _ = crate::backend::command_utils::sync_room_members(rooms, clients, pubkeys, room);
+        let _ = broadcast_user_list(clients, rooms, room);
+    }
+    Ok(CommandResult::Handled)
+}
+
+fn roles_template_path(filename: &str) -> Option<String> {
+    if !is_safe_filename(filename) {
+        return None;
+    }
+    let filename = filename.strip_suffix(".json").unwrap_or(filename);
+    Some(format!("data/vault/roles/{filename}.json"))
+}
+
+pub fn handle_super_roles_export(client: Arc<Mutex<Client>>, ctx: RoomCtx, filename: &str) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let Some(export_path) = roles_template_path(filename) else {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &"Error: Invalid file name".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    };
+
+    let roles = {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+        let room_guard = lock_room(&room_arc);
+        room_guard.roles.clone()
+    };
+
+    let written = std::fs::create_dir_all("data/vault/roles")
+        .and_then(|_| serde_json::to_string_pretty(&roles).map_err(io::Error::other))
+        .and_then(|text| std::fs::write(&export_path, text));
+
+    let mut c = lock_client(&client);
+    match written {
+        Ok(()) => send_success_locked(&mut c, &format!("Exported role permissions and colors to: {export_path}"))?,
+        Err(e) => send_error_locked(&mut c, &format!("Error writing {export_path}: {e}"))?,
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_super_roles_import(client: Arc<Mutex<Client>>, ctx: RoomCtx, filename: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    let Some(import_path) = roles_template_path(filename) else {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &"Error: Invalid file name".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    };
+
+    let template: Roles = match std::fs::read_to_string(&import_path) {
+        Ok(text) => match serde_json::from_str(&text) {
+            Ok(t) => t,
+            Err(e) => {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &format!("Error: {import_path} is not a valid roles template: {e}").yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        },
+        Err(_) => {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &format!("Error: Could not open {import_path}").yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+
+    // Validate the whole template up front so a bad file never half-applies
+    let cmd_tokens: Vec<&str> = template.moderator.iter().chain(template.user.iter()).map(String::as_str).collect();
+    let mut invalid = unknown_commands(&cmd_tokens);
+    invalid.sort();
+    invalid.dedup();
+    if !invalid.is_empty() {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &format!("Error: Unknown commands in template: {}", invalid.join(", ")).yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+    let bad_colors: Vec<String> = template.colors.iter()
+        .filter(|(role, hex)| {
+            let hex = hex.trim_start_matches('#');
+            !matches!(role.as_str(), "user" | "moderator" | "admin" | "owner") || hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .map(|(role, hex)| format!("{role}={hex}"))
+        .collect();
+    if !bad_colors.is_empty() {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &format!("Error: Invalid role colors in template: {}", bad_colors.join(", ")).yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        {
+            let mut room_guard = lock_room(&room_arc);
+            if room_guard.users.get(username).is_none_or(|u| u.role != "owner") {
+                let mut c = lock_client(&client);
+                send_message_locked(&mut c, &"Error: Only the room owner can import role permissions".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+            room_guard.roles.moderator = template.moderator.clone();
+            room_guard.roles.user = template.user.clone();
+            room_guard.roles.colors = template.colors.iter()
+                .map(|(role, hex)| (role.clone(), format!("#{}", hex.trim_start_matches('#'))))
+                .collect();
+        }
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            let mut c = lock_client(&client);
+            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
+    {
+        let mut c = lock_client(&client);
+        send_success_locked(&mut c, &format!("Imported roles from {import_path}: Moderator ({} commands), User ({} commands), {} role colors", template.moderator.len(), template.user.len(), template.colors.len()))?;
+    }
+    log_audit(username, "-", room, &format!("roles.import from {filename}"));
+
+    let _ = sync_room_commands(rooms, clients, room);
+    let _ = crate::backend::command_utils::sync_room_members(rooms, clients, pubkeys, room);
+    let _ = broadcast_user_list(clients, rooms, room);
+    Ok(CommandResult::Handled)
+}
@@ -2,23 +2,127 @@ use std::io::{self, Write};
 use std::sync::{Arc, Mutex};
 use colored::*;
 
-use crate::shared::types::{Client, ClientState, Rooms, Clients, PublicKeys};
-use crate::shared::utils::{lock_client, lock_rooms, lock_room, save_rooms_to_disk, send_message_locked, send_error_locked, send_success_locked, ColorizeExt, broadcast_user_list};
-use crate::backend::command_utils::sync_room_members;
+use crate::shared::types::{Client, ClientState};
+use crate::shared::utils::{find_room, send_failure, lock_client, lock_clients, lock_rooms, lock_room, save_rooms_to_disk, send_message_locked, send_error_locked, send_success_locked, ColorizeExt, broadcast_user_list};
+use crate::backend::command_utils::{check_role_permissions, sync_room_members, validate_nickname};
 use crate::backend::dispatcher::CommandResult;
+use crate::backend::dispatcher::inroom::RoomCtx;
 
-pub fn handle_users(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_users_count(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
-    let room_guard = lock_room(&room_arc)?;
-    let mut c = lock_client(&client)?;
+    let room_guard = lock_room(&room_arc);
+
+    let (visible, hidden) = room_guard.online_users.iter().fold((0, 0), |(v, h), u| {
+        match room_guard.users.get(u) {
+            Some(rec) if rec.hidden => (v, h + 1),
+            _ => (v + 1, h),
+        }
+    });
+
+    let mut c = lock_client(&client);
+    let noun = if visible == 1 { "user" } else { "users" };
+    send_success_locked(&mut c, &format!("{visible} {noun} online ({hidden} hidden)"))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_users_whoami(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    let room_guard = lock_room(&room_arc);
+
+    let (role, nick, color) = match room_guard.users.get(username) {
+        Some(rec) => (rec.role.clone(), rec.nick.clone(), rec.color.clone()),
+        None => ("user".to_string(), String::new(), String::new()),
+    };
+    let role_color = room_guard.roles.colors.get(&role).cloned().unwrap_or_default();
+    let role = room_guard.role_label(&role);
+    drop(room_guard);
+    drop(rooms_map);
+
+    let (name, source) = if !color.is_empty() {
+        (username.truecolor_from_hex(&color).to_string(), format!("{color} (your personal color, which takes precedence over your role color)"))
+    } else if !role_color.is_empty() {
+        (username.truecolor_from_hex(&role_color).to_string(), format!("{role_color} ({role} role color, since you have no personal color)"))
+    } else {
+        (username.to_string(), "default (no personal or role color is set)".to_string())
+    };
+    let nick = if nick.is_empty() { "none".to_string() } else { nick };
+
+    let mut c = lock_client(&client);
+    send_message_locked(&mut c, &format!("You are {name}\n> Role: {role}\n> Nickname: {nick}\n> Name color: {source}"))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_room_members(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { rooms, username, room, .. } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    let room_guard = lock_room(&room_arc);
+
+    let caller_role = room_guard.users.get(username).map(|u| u.role.as_str()).unwrap_or("user");
+    let sees_hidden = check_role_permissions(caller_role, "super.users", &room_guard.roles);
+
+    let mut members: Vec<String> = room_guard.online_users.iter().filter_map(|u| {
+        match room_guard.users.get(u) {
+            Some(rec) if rec.hidden && !sees_hidden => None,
+            Some(rec) if rec.hidden => Some(format!("{u} (hidden)")),
+            _ => Some(u.clone()),
+        }
+    }).collect();
+    members.sort();
+
+    let mut c = lock_client(&client);
+    send_success_locked(&mut c, &format!("Members ({}): {}", members.len(), members.join(", ")))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_users(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, room, .. } = ctx;
+    let mut afk_map = std::collections::HashMap::new();
+    {
+        let clients_map = lock_clients(clients);
+        for c_arc in clients_map.values() {
+            if let Ok(target_c) = c_arc.try_lock() {
+                if let ClientState::InRoom { username, room: rnm, is_afk: true, afk_reason, .. } = &target_c.state {
+                    if rnm == room {
+                        afk_map.insert(username.clone(), afk_reason.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
+            return Ok(CommandResult::Handled);
+        }
+    };
+    let room_guard = lock_room(&room_arc);
+    let mut c = lock_client(&client);
 
     writeln!(c.stream, "{}", format!("Users in {room}:").green())?;
     c.stream.flush()?;
@@ -28,12 +132,10 @@ pub fn handle_users(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) ->
             continue;
         }
 
-        let role = {
-            let mut ch = udata.role.chars();
-            match ch.next() {
-                Some(f) => f.to_uppercase().collect::<String>() + ch.as_str(),
-                None => String::new(),
-            }
+        let role = room_guard.role_label(&udata.role);
+        let role = match room_guard.roles.colors.get(&udata.role) {
+            Some(hex) => role.truecolor_from_hex(hex).to_string(),
+            None => role,
         };
 
         let nickname = if udata.nick.is_empty() {
@@ -48,11 +150,18 @@ pub fn handle_users(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) ->
             udata.color.to_string().truecolor_from_hex(&udata.color).to_string()
         };
 
-        writeln!(c.stream, "> {} - Role: {}, Nickname: {}, Color: {}",
+        let afk_status = match afk_map.get(uname) {
+            Some(reason) if !reason.is_empty() => format!("True ({reason})").yellow().to_string(),
+            Some(_) => "True".yellow().to_string(),
+            None => "False".green().to_string(),
+        };
+
+        writeln!(c.stream, "> {} - Role: {}, Nickname: {}, Color: {}, AFK: {}",
             uname.green(),
             role,
             nickname,
-            color_display
+            color_display,
+            afk_status
         )?;
         c.stream.flush()?;
     }
@@ -60,28 +169,36 @@ pub fn handle_users(client: Arc<Mutex<Client>>, rooms: &Rooms, room: &String) ->
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_users_rename(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, room: &String, old_name: &String, new_name: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_users_rename(client: Arc<Mutex<Client>>, ctx: RoomCtx, new_name: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username: old_name, room } = ctx;
+    if new_name != "reset" && new_name != "*" {
+        if let Err(e) = validate_nickname(new_name) {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &e.yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
 
     let username;
     {
-        let c = lock_client(&client)?;
+        let c = lock_client(&client);
         username = match &c.state {
             ClientState::InRoom { username, .. } => username.clone(),
             _ => return Ok(CommandResult::Handled),
         };
     }
 
-    if old_name != &username {
-        let room_guard = lock_room(&room_arc)?;
+    if old_name != username {
+        let room_guard = lock_room(&room_arc);
         let caller_role = room_guard.users.get(&username).map(|u| u.role.as_str()).unwrap_or("user");
         let rank = match caller_role {
             "owner" => 4,
@@ -90,21 +207,29 @@ pub fn handle_users_rename(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
             _ => 1,
         };
         if rank < 3 {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_message_locked(&mut c, &"Error: Only admins and owners can rename other users".yellow().to_string())?;
             return Ok(CommandResult::Handled);
         }
     }
 
     {
-        let mut room_guard = lock_room(&room_arc)?;
-        let mut c = lock_client(&client)?;
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
+        if new_name != "reset" && new_name != "*" {
+            let taken = room_guard.users.iter()
+                .any(|(name, u)| name != old_name && u.nick.eq_ignore_ascii_case(new_name));
+            if taken {
+                send_message_locked(&mut c, &"Nickname already taken".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        }
         match room_guard.users.get_mut(old_name) {
             Some(u) => {
                 if new_name == "reset" || new_name == "*" {
                     u.nick.clear();
                 } else {
-                    u.nick = new_name.clone();
+                    u.nick = new_name.to_string();
                 }
             }
             None => {
@@ -119,8 +244,8 @@ pub fn handle_users_rename(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
     drop(rooms_map);
 
     {
-        let fresh_map = lock_rooms(rooms)?;
-        let mut c = lock_client(&client)?;
+        let fresh_map = lock_rooms(rooms);
+        let mut c = lock_client(&client);
         if let Err(e) = save_rooms_to_disk(&fresh_map) {
             send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
             return Ok(CommandResult::Handled);
@@ -137,40 +262,40 @@ pub fn handle_users_rename(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_users_recolor(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, room: &String, target_user: &String, color: &String) -> io::Result<CommandResult> {
-    let rooms_map = lock_rooms(rooms)?;
-    let room_arc = match rooms_map.get(room) {
-        Some(r) => Arc::clone(r),
-        None => {
-            let mut c = lock_client(&client)?;
-            send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+pub fn handle_users_recolor(client: Arc<Mutex<Client>>, ctx: RoomCtx, color: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username: target_user, room } = ctx;
+    let rooms_map = lock_rooms(rooms);
+    let room_arc = match find_room(&rooms_map, room) {
+        Ok(r) => r,
+        Err(e) => {
+            send_failure(&client, &e)?;
             return Ok(CommandResult::Handled);
         }
     };
 
     let username;
     {
-        let c = lock_client(&client)?;
+        let c = lock_client(&client);
         username = match &c.state {
             ClientState::InRoom { username, .. } => username.clone(),
             _ => return Ok(CommandResult::Handled),
         };
     }
 
-    if target_user != &username {
-        let room_guard = lock_room(&room_arc)?;
+    if target_user != username {
+        let room_guard = lock_room(&room_arc);
         let caller_role = room_guard.users.get(&username).map(|u| u.role.as_str()).unwrap_or("user");
         let target_role = room_guard.users.get(target_user).map(|u| u.role.as_str()).unwrap_or("user");
         let c_rank = match caller_role { "owner" => 4, "admin" => 3, "moderator" => 2, _ => 1 };
         let t_rank = match target_role { "owner" => 4, "admin" => 3, "moderator" => 2, _ => 1 };
         
         if c_rank < 3 {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_message_locked(&mut c, &"Error: Only admins and owners can recolor other users".yellow().to_string())?;
             return Ok(CommandResult::Handled);
         }
         if c_rank <= t_rank {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_message_locked(&mut c, &"Error: Cannot recolor user with equal or higher privilege".yellow().to_string())?;
             return Ok(CommandResult::Handled);
         }
@@ -181,7 +306,7 @@ pub fn handle_users_recolor(client: Arc<Mutex<Client>>, clients: &Clients, rooms
         String::new()
     } else {
         if c_str.len() != 6 || !c_str.chars().all(|c| c.is_ascii_hexdigit()) {
-            let mut c = lock_client(&client)?;
+            let mut c = lock_client(&client);
             send_message_locked(&mut c, &"Error: Bad color hex, must be exactly 6 characters (e.g. #FF0000)".yellow().to_string())?;
             return Ok(CommandResult::Handled);
         }
@@ -189,8 +314,8 @@ pub fn handle_users_recolor(client: Arc<Mutex<Client>>, clients: &Clients, rooms
     };
 
     {
-        let mut room_guard = lock_room(&room_arc)?;
-        let mut c = lock_client(&client)?;
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
         match room_guard.users.get_mut(target_user) {
             Some(u) => {
                 u.color = formatted_color.clone();
@@ -207,8 +332,8 @@ pub fn handle_users_recolor(client: Arc<Mutex<Client>>, clients: &Clients, rooms
     drop(rooms_map);
 
     {
-        let fresh_map = lock_rooms(rooms)?;
-        let mut c = lock_client(&client)?;
+        let fresh_map = lock_rooms(rooms);
+        let mut c = lock_client(&client);
         if let Err(e) = save_rooms_to_disk(&fresh_map) {
             send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
             return Ok(CommandResult::Handled);
@@ -227,22 +352,111 @@ pub fn handle_users_recolor(client: Arc<Mutex<Client>>, clients: &Clients, rooms
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_users_hide(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, username: &String, room: &String) -> io::Result<CommandResult> {
+pub fn handle_users_recolor_preview(client: Arc<Mutex<Client>>, ctx: RoomCtx, color: &str) -> io::Result<CommandResult> {
+    let RoomCtx { username, .. } = ctx;
+    let c_str = color.trim().trim_start_matches('#');
+    if c_str.len() != 6 || !c_str.chars().all(|c| c.is_ascii_hexdigit()) {
+        let mut c = lock_client(&client);
+        send_message_locked(&mut c, &"Error: Bad color hex, must be exactly 6 characters (e.g. #FF0000)".yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let hex = format!("#{c_str}");
+    let mut c = lock_client(&client);
+    writeln!(c.stream, "{} {} {}", "Preview:".green(), username.truecolor_from_hex(&hex), format!("({hex}, not saved)").green())?;
+    c.stream.flush()?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_users_profile(client: Arc<Mutex<Client>>, ctx: RoomCtx, nick: &str, color: &str) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    let c_str = color.trim().trim_start_matches('#');
+    let formatted_color = if c_str == "reset" || c_str == "*" {
+        String::new()
+    } else {
+        if c_str.len() != 6 || !c_str.chars().all(|c| c.is_ascii_hexdigit()) {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &"Error: Bad color hex, must be exactly 6 characters (e.g. #FF0000)".yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+        format!("#{c_str}")
+    };
+    let clear_nick = nick == "reset" || nick == "*";
+    if !clear_nick {
+        if let Err(e) = validate_nickname(nick) {
+            let mut c = lock_client(&client);
+            send_message_locked(&mut c, &e.yellow().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
+    }
+
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(room) {
-            Some(r) => Arc::clone(r),
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
+                return Ok(CommandResult::Handled);
+            }
+        };
+
+        let mut room_guard = lock_room(&room_arc);
+        let mut c = lock_client(&client);
+        if !clear_nick {
+            let taken = room_guard.users.iter()
+                .any(|(name, u)| name != username && u.nick.eq_ignore_ascii_case(nick));
+            if taken {
+                send_message_locked(&mut c, &"Nickname already taken".yellow().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+        match room_guard.users.get_mut(username) {
+            Some(u) => {
+                u.nick = if clear_nick { String::new() } else { nick.to_string() };
+                u.color = formatted_color.clone();
+            }
             None => {
-                let mut c = lock_client(&client)?;
-                send_message_locked(&mut c, &format!("Room {room} not found").yellow().to_string())?;
+                send_message_locked(&mut c, &"Error: user record missing".red().to_string())?;
+                return Ok(CommandResult::Handled);
+            }
+        }
+        drop(room_guard);
+
+        if let Err(e) = save_rooms_to_disk(&rooms_map) {
+            send_error_locked(&mut c, &format!("Failed to save rooms: {e}"))?;
+            return Ok(CommandResult::Handled);
+        }
+
+        let nick_display = if clear_nick { "None".to_string() } else { nick.italic().to_string() };
+        let color_display = if formatted_color.is_empty() {
+            "Default".to_string()
+        } else {
+            formatted_color.clone().truecolor_from_hex(&formatted_color).to_string()
+        };
+        send_message_locked(&mut c, &format!("{} Nickname: {}, Color: {}", "Profile updated -".green(), nick_display, color_display))?;
+    }
+
+    let _ = sync_room_members(rooms, clients, pubkeys, room);
+    let _ = broadcast_user_list(clients, rooms, room);
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_users_hide(client: Arc<Mutex<Client>>, ctx: RoomCtx) -> io::Result<CommandResult> {
+    let RoomCtx { clients, rooms, pubkeys, username, room } = ctx;
+    {
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
         let now_hidden;
         {
-            let mut room_guard = lock_room(&room_arc)?;
-            let mut c = lock_client(&client)?;
+            let mut room_guard = lock_room(&room_arc);
+            let mut c = lock_client(&client);
             match room_guard.users.get_mut(username) {
                 Some(u) => {
                     u.hidden = !u.hidden;
@@ -1,21 +1,23 @@
 pub mod account;
 pub mod rooms;
 pub mod ignore;
+pub mod block;
+pub mod favorites;
 
 use std::io;
 use std::sync::{Arc, Mutex};
 use colored::*;
 
 use crate::backend::parser::Command;
-use crate::backend::command_utils::help_msg_loggedin;
-use crate::shared::types::{Client, Clients, PublicKeys, Rooms};
-use crate::shared::utils::{lock_client, lock_clients, send_message, send_error, send_success};
+use crate::backend::command_utils::{help_msg, help_entries_loggedin};
+use crate::shared::types::{Client, Clients, PublicKeys, Rooms, SERVER_START};
+use crate::shared::utils::{lock_client, lock_clients, lock_room, lock_rooms, send_message, send_error, send_success};
 use crate::backend::dispatcher::CommandResult;
 
-pub fn loggedin_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, pubkeys: &PublicKeys) -> io::Result<CommandResult> {
+pub fn loggedin_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &str, pubkeys: &PublicKeys) -> io::Result<CommandResult> {
     match cmd {
-        Command::Help => {
-            send_message(&client, &format!("{}{}", help_msg_loggedin().bright_blue(), "\x1b[0m"))?;
+        Command::Help { query } => {
+            send_message(&client, &format!("{}{}", help_msg(help_entries_loggedin(), &query).bright_blue(), "\x1b[0m"))?;
             Ok(CommandResult::Handled)
         }
 
@@ -26,6 +28,12 @@ pub fn loggedin_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clie
             Ok(CommandResult::Handled)
         }
 
+        Command::Mode { json } => {
+            lock_client(&client).json_output = json;
+            send_success(&client, if json { "Output mode set to json" } else { "Output mode set to text" })?;
+            Ok(CommandResult::Handled)
+        }
+
         Command::PubKey { pubkey } => {
             let mut map = match pubkeys.lock() {
                 Ok(m) => m,
@@ -38,8 +46,14 @@ pub fn loggedin_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clie
                 send_error(&client, "Public key already registered for this user")?;
                 return Ok(CommandResult::Handled);
             }
-            map.insert(username.clone(), pubkey.clone());
-            Ok(CommandResult::Handled)
+            map.insert(username.to_string(), pubkey.clone());
+            drop(map);
+
+            let resume_room = lock_client(&client).resume_room.take();
+            match resume_room {
+                Some(room) => rooms::handle_room_join(client, clients, rooms, pubkeys, username, &room),
+                None => Ok(CommandResult::Handled),
+            }
         }
 
         Command::Quit => {
@@ -51,33 +65,72 @@ pub fn loggedin_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clie
                 pubkeys_map.remove(username);
             }
             let addr = {
-                let c = lock_client(&client)?;
+                let c = lock_client(&client);
                 c.addr
             };
             {
-                let mut clients = lock_clients(clients)?;
+                let mut clients = lock_clients(clients);
                 clients.remove(&addr);
             }
-            let c_guard = lock_client(&client)?;
+            let c_guard = lock_client(&client);
             send_success(&client, "Exiting...")?;
             use std::net::Shutdown;
             c_guard.stream.shutdown(Shutdown::Both)?;
             Ok(CommandResult::Stop)
         }
 
+        Command::Stats => {
+            let connected = lock_clients(clients).len();
+            let (room_count, online) = {
+                let rooms_map = lock_rooms(rooms);
+                let mut online = 0;
+                for room_arc in rooms_map.values() {
+                    online += lock_room(room_arc).online_users.len();
+                }
+                (rooms_map.len(), online)
+            };
+            let uptime = SERVER_START.get().map(|t| t.elapsed().as_secs()).unwrap_or(0);
+            send_message(&client, &format!("Stat clients={connected}\nStat rooms={room_count}\nStat online={online}\nStat uptime={uptime}"))?;
+            Ok(CommandResult::Handled)
+        }
+
+        Command::DmHistory => {
+            let c = lock_client(&client);
+            let lines: Vec<String> = c.dm_log.iter()
+                .map(|(peer, text, outgoing)| if *outgoing {
+                    format!("> [to {peer}] {text}")
+                } else {
+                    format!("> [from {peer}] {text}")
+                })
+                .collect();
+            drop(c);
+            if lines.is_empty() {
+                send_message(&client, &"No private messages this session".yellow().to_string())?;
+            } else {
+                send_message(&client, &format!("Private message history:\n{}", lines.join("\n")).cyan().to_string())?;
+            }
+            Ok(CommandResult::Handled)
+        }
+
         Command::IgnoreList => ignore::handle_ignore_list(client),
         Command::IgnoreAdd { users } => ignore::handle_ignore_add(client, username, &users),
         Command::IgnoreRemove { users } => ignore::handle_ignore_remove(client, username, &users),
+        Command::IgnoreShare => ignore::handle_ignore_share(client, username),
+        Command::IgnoreImport { user } => ignore::handle_ignore_import(client, username, &user),
+
+        Command::BlockList => block::handle_block_list(client),
+        Command::BlockAdd { users } => block::handle_block_add(client, username, &users),
+        Command::BlockRemove { users } => block::handle_block_remove(client, username, &users),
 
-        Command::Leave | Command::Status | Command::AFK | Command::Announce { .. } | Command::Seen { .. } | Command::DM { .. } | Command::Me { .. } |
-        Command::SuperUsers | Command::SuperRename { .. } | Command::SuperExport { .. } | Command::SuperWhitelist | Command::SuperWhitelistToggle | Command::SuperWhitelistAdd { .. } | Command::SuperWhitelistRemove { .. } | Command::SuperLimit | Command::SuperLimitRate { .. } | Command::SuperLimitSession { .. } | Command::SuperRoles | Command::SuperRolesAdd { .. } | Command::SuperRolesRevoke { .. } | Command::SuperRolesAssign { .. } | Command::SuperRolesRecolor { .. } |
-        Command::Users | Command::UsersRename { .. } | Command::UsersRecolor { .. } | Command::UsersHide |
-        Command::ModInfo | Command::ModKick { .. } | Command::ModMute { .. } | Command::ModUnmute { .. } | Command::ModBan { .. } | Command::ModUnban { .. } => {
+        Command::HelpRoles | Command::Leave | Command::Status | Command::RoomHistory { .. } | Command::RoomMembers | Command::RoomTransfer { .. } | Command::AFK { .. } | Command::Announce { .. } | Command::Seen { .. } | Command::DM { .. } | Command::DmRole { .. } | Command::Reply { .. } | Command::Edit { .. } | Command::Me { .. } |
+        Command::SuperInfo | Command::SuperUsers { .. } | Command::SuperRename { .. } | Command::SuperLock | Command::SuperStaffAlerts | Command::SuperPolicyBanReason { .. } | Command::SuperMotd { .. } | Command::SuperArchive | Command::SuperFreeze | Command::SuperLog { .. } | Command::SuperSlowmode { .. } | Command::SuperPurge { .. } | Command::SuperKickAll { .. } | Command::SuperKickRole { .. } | Command::SuperRun { .. } | Command::SuperHandoff { .. } | Command::SuperImportProfile { .. } | Command::SuperExport { .. } | Command::SuperExportAll | Command::SuperWhitelist | Command::SuperWhitelistToggle | Command::SuperWhitelistEnforce | Command::SuperWhitelistAdd { .. } | Command::SuperWhitelistRemove { .. } | Command::SuperWhitelistImport { .. } | Command::SuperLimit | Command::SuperLimitRate { .. } | Command::SuperLimitSession { .. } | Command::SuperRoles | Command::SuperRolesAdd { .. } | Command::SuperRolesRevoke { .. } | Command::SuperRolesPreview { .. } | Command::SuperRolesAssign { .. } | Command::SuperRolesRecolor { .. } | Command::SuperRolesReset | Command::SuperRolesLabel { .. } | Command::SuperRolesCopy { .. } | Command::SuperRolesExport { .. } | Command::SuperRolesImport { .. } | Command::SuperTagsAdd { .. } | Command::SuperTagsRemove { .. } | Command::SuperFilterList | Command::SuperFilterToggle | Command::SuperFilterAdd { .. } | Command::SuperFilterRemove { .. } | Command::SuperPinList | Command::SuperPinAdd { .. } | Command::SuperPinRemove { .. } | Command::SuperPinClear |
+        Command::Users | Command::UsersCount | Command::UsersRename { .. } | Command::UsersRecolor { .. } | Command::UsersRecolorPreview { .. } | Command::UsersProfile { .. } | Command::UsersHide | Command::UsersWhoami |
+        Command::ModInfo { .. } | Command::ModKick { .. } | Command::ModMute { .. } | Command::ModUnmute { .. } | Command::ModBan { .. } | Command::ModUnban { .. } | Command::ModWhois { .. } | Command::ModExport { .. } | Command::ModHistory { .. } => {
             send_message(&client, &"This command requires you to be in a room. Join a room first.".yellow().to_string())?;
             Ok(CommandResult::Handled)
         }
 
-        Command::AccountRegister { .. } | Command::AccountLogin { .. } => {
+        Command::AccountRegister { .. } | Command::AccountLogin { .. } | Command::AccountResume { .. } => {
             send_error(&client, "You are already logged in")?;
             Ok(CommandResult::Handled)
         }
@@ -88,13 +141,18 @@ pub fn loggedin_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clie
         Command::AccountImport { filename } => account::handle_account_import(client, &filename),
         Command::AccountExport { filename } => account::handle_account_export(client, username, &filename),
         Command::AccountDelete { force } => account::handle_account_delete(client, username, pubkeys, force),
-        Command::Account => account::handle_account(client, username),
+        Command::Account => account::handle_account(client, rooms, pubkeys, username),
+        Command::AccountBans => account::handle_account_bans(client, rooms, username),
 
-        Command::RoomList => rooms::handle_room_list(client, rooms, username),
+        Command::RoomList { tag, busy } => rooms::handle_room_list(client, rooms, username, tag.as_deref(), busy),
+        Command::RoomSearch { query } => rooms::handle_room_search(client, rooms, username, &query),
         Command::RoomCreate { name, whitelist } => rooms::handle_room_create(client, clients, rooms, username, &name, whitelist),
         Command::RoomJoin { name } => rooms::handle_room_join(client, clients, rooms, pubkeys, username, &name),
         Command::RoomImport { filename } => rooms::handle_room_import(client, rooms, &filename),
-        Command::RoomDelete { name, force } => rooms::handle_room_delete(client, clients, rooms, username, &name, force),
+        Command::RoomDelete { name, force, dry } => rooms::handle_room_delete(client, clients, rooms, username, &name, force, dry),
+        Command::RoomFavoriteList => favorites::handle_favorite_list(client, rooms),
+        Command::RoomFavoriteAdd { rooms: names } => favorites::handle_favorite_add(client, rooms, username, &names),
+        Command::RoomFavoriteRemove { rooms: names } => favorites::handle_favorite_remove(client, username, &names),
 
         Command::InvalidSyntax {err_msg } => {
             send_message(&client, &err_msg)?;
@@ -1,17 +1,18 @@
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufReader};
 use std::fs::{File, OpenOptions};
 use serde::Serialize;
 use serde_json::{json, Serializer, Value};
 use serde_json::ser::PrettyFormatter;
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use colored::*;
 
-use crate::shared::types::{Client, ClientState, PublicKeys};
-use crate::shared::utils::{lock_client, lock_users_storage, load_json, save_json, send_error, send_success, send_error_locked, send_message_locked, send_success_locked, log_event};
+use crate::shared::types::{Client, ClientState, PublicKeys, Rooms};
+use crate::shared::utils::{format_duration, lock_client, lock_rooms, lock_room, save_rooms_to_disk, send_message, lock_users_storage, load_json, save_json, send_error, send_success, send_error_locked, send_message_locked, send_success_locked, log_event, read_prompt_line};
 use crate::backend::dispatcher::CommandResult;
 use crate::backend::command_utils::{hash_password, verify_password};
 
-pub fn handle_account_logout(client: Arc<Mutex<Client>>, username: &String, pubkeys: &PublicKeys) -> io::Result<CommandResult> {
+pub fn handle_account_logout(client: Arc<Mutex<Client>>, username: &str, pubkeys: &PublicKeys) -> io::Result<CommandResult> {
     {
         let mut pubkeys_map = match pubkeys.lock() {
             Ok(g) => g,
@@ -21,9 +22,16 @@ pub fn handle_account_logout(client: Arc<Mutex<Client>>, username: &String, pubk
         pubkeys_map.remove(username);
     }
     
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     let peer = c.addr;
     c.state = ClientState::Guest;
+    c.ignore_list.clear();
+    c.dm_block_list.clear();
+    c.favorites.clear();
+    c.last_room_list.clear();
+    c.dm_log.clear();
+    c.resume_token = None;
+    c.resume_room = None;
     let _ = crate::shared::utils::send_message_locked(&mut c, "/GUEST_STATE");
     let _ = crate::shared::utils::send_success_locked(&mut c, &format!("Logged out: {username}"));
     log_event(&peer, Some(username), None, "Logged out");
@@ -31,16 +39,16 @@ pub fn handle_account_logout(client: Arc<Mutex<Client>>, username: &String, pubk
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_account_edit_username(client: Arc<Mutex<Client>>, username: &String, new_username: &String) -> io::Result<CommandResult> {
+pub fn handle_account_edit_username(client: Arc<Mutex<Client>>, username: &str, new_username: &str) -> io::Result<CommandResult> {
     {
-        let mut c = lock_client(&client)?;
+        let mut c = lock_client(&client);
         if new_username.is_empty() {
              send_error_locked(&mut c, "Username cannot be empty")?;
              return Ok(CommandResult::Handled);
         }
     }
     
-    let _lock = lock_users_storage()?;
+    let _lock = lock_users_storage();
 
     let mut users = load_json("data/users.json")?;
 
@@ -61,17 +69,17 @@ pub fn handle_account_edit_username(client: Arc<Mutex<Client>>, username: &Strin
 
     save_json("data/users.json", &users)?;
 
-    let old_username = username.clone();
-    let mut c = lock_client(&client)?;
-    c.state = ClientState::LoggedIn { username: new_username.clone() };
+    let old_username = username.to_string();
+    let mut c = lock_client(&client);
+    c.state = ClientState::LoggedIn { username: new_username.to_string() };
 
     send_message_locked(&mut c, &format!("/LOGIN_OK {new_username}"))?;
     send_success_locked(&mut c, &format!("Username changed from {old_username} to: {new_username}"))?;
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_account_edit_password(client: Arc<Mutex<Client>>, username: &String, current_password: &String, new_password: &String) -> io::Result<CommandResult> {
-    let _lock = lock_users_storage()?;
+pub fn handle_account_edit_password(client: Arc<Mutex<Client>>, username: &str, current_password: &str, new_password: &str) -> io::Result<CommandResult> {
+    let _lock = lock_users_storage();
 
     let mut users = load_json("data/users.json")?;
 
@@ -105,12 +113,12 @@ pub fn handle_account_edit_password(client: Arc<Mutex<Client>>, username: &Strin
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_account_import(client: Arc<Mutex<Client>>, filename: &String) -> io::Result<CommandResult> {
+pub fn handle_account_import(client: Arc<Mutex<Client>>, filename: &str) -> io::Result<CommandResult> {
     let safe_filename = if !filename.ends_with(".json") {
         format!("{filename}.json")
     }
     else {
-        filename.clone()
+        filename.to_string()
     };
 
     let import_path = format!("data/vault/users/{safe_filename}");
@@ -139,7 +147,7 @@ pub fn handle_account_import(client: Arc<Mutex<Client>>, filename: &String) -> i
         }
     };
 
-    let _lock = lock_users_storage()?;
+    let _lock = lock_users_storage();
 
     let mut users = load_json("data/users.json")?;
 
@@ -156,8 +164,8 @@ pub fn handle_account_import(client: Arc<Mutex<Client>>, filename: &String) -> i
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_account_export(client: Arc<Mutex<Client>>, username: &String, filename: &String) -> io::Result<CommandResult> {
-    let _lock = lock_users_storage()?;
+pub fn handle_account_export(client: Arc<Mutex<Client>>, username: &str, filename: &str) -> io::Result<CommandResult> {
+    let _lock = lock_users_storage();
 
     let users = load_json("data/users.json")?;
 
@@ -177,7 +185,7 @@ pub fn handle_account_export(client: Arc<Mutex<Client>>, username: &String, file
             format!("{filename}.json")
         }
         else {
-            filename.clone()
+            filename.to_string()
         }
     };
 
@@ -194,17 +202,16 @@ pub fn handle_account_export(client: Arc<Mutex<Client>>, username: &String, file
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_account_delete(client: Arc<Mutex<Client>>, username: &String, pubkeys: &PublicKeys, force: bool) -> io::Result<CommandResult> {
+pub fn handle_account_delete(client: Arc<Mutex<Client>>, username: &str, pubkeys: &PublicKeys, force: bool) -> io::Result<CommandResult> {
     if !force {
-        let mut c = lock_client(&client)?;
-        use std::io::Write;
-        writeln!(c.stream, "{}", "Are you sure you want to delete your account? (y/n): ".red())?;
+        let mut c = lock_client(&client);
+        send_error_locked(&mut c, "Are you sure you want to delete your account? (y/n): ")?;
 
         let mut reader: BufReader<std::net::TcpStream> = BufReader::new(c.stream.try_clone()?);
         drop(c);
         loop {
             let mut line = String::new();
-            let bytes_read = reader.read_line(&mut line)?;
+            let bytes_read = read_prompt_line(&mut reader, &mut line, &client)?;
             if bytes_read == 0 {
                 send_error(&client, "Connection closed")?;
                 return Ok(CommandResult::Stop);
@@ -224,7 +231,7 @@ pub fn handle_account_delete(client: Arc<Mutex<Client>>, username: &String, pubk
         }
     }
 
-    let _lock = lock_users_storage()?;
+    let _lock = lock_users_storage();
     let mut users = load_json("data/users.json")?;
 
     if users.get(username).is_none() {
@@ -250,15 +257,109 @@ pub fn handle_account_delete(client: Arc<Mutex<Client>>, username: &String, pubk
         pubkeys_map.remove(username);
     }
 
-    let mut c = lock_client(&client)?;
+    let mut c = lock_client(&client);
     c.state = ClientState::Guest;
+    c.ignore_list.clear();
+    c.dm_block_list.clear();
+    c.favorites.clear();
+    c.last_room_list.clear();
+    c.dm_log.clear();
+    c.resume_token = None;
+    c.resume_room = None;
     send_message_locked(&mut c, "/GUEST_STATE")?;
     send_success_locked(&mut c, &format!("Account {username} deleted successfully, you are now a guest"))?;
 
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_account(client: Arc<Mutex<Client>>, username: &String) -> io::Result<CommandResult> {
-    send_success(&client, &format!("Currently logged in as: {username} (not in a room)"))?;
+pub fn handle_account_bans(client: Arc<Mutex<Client>>, rooms: &Rooms, username: &str) -> io::Result<CommandResult> {
+    let now_ts = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    let mut lines = Vec::new();
+    {
+        let rooms_map = lock_rooms(rooms);
+        let mut names: Vec<&String> = rooms_map.keys().collect();
+        names.sort();
+
+        let mut cleared = false;
+        for name in names {
+            let mut room_guard = lock_room(&rooms_map[name]);
+            let Some(rec) = room_guard.users.get_mut(username) else { continue; };
+            if !rec.banned {
+                continue;
+            }
+
+            let permanent = rec.ban_length == 0;
+            let remaining_text = if permanent {
+                "Permanent".to_string()
+            } else {
+                let ban_expires = rec.ban_stamp.saturating_add(rec.ban_length);
+                if now_ts >= ban_expires {
+                    // Same cleanup the join path does for a ban that has run out
+                    rec.banned = false;
+                    rec.ban_stamp = 0;
+                    rec.ban_length = 0;
+                    rec.ban_reason.clear();
+                    cleared = true;
+                    continue;
+                }
+                format!("{} remaining", format_duration(ban_expires - now_ts))
+            };
+
+            let line = if rec.ban_reason.is_empty() {
+                format!("> {name} ({remaining_text})")
+            } else {
+                format!("> {name} ({remaining_text}) - {}", rec.ban_reason)
+            };
+            lines.push(if permanent { line.red().to_string() } else { line.yellow().to_string() });
+        }
+
+        if cleared {
+            if let Err(e) = save_rooms_to_disk(&rooms_map) {
+                eprintln!("Error saving expired bans for {username}: {e}");
+            }
+        }
+    }
+
+    if lines.is_empty() {
+        send_success(&client, "You are not banned from any rooms")?;
+    } else {
+        send_message(&client, &format!("{}\n{}", "Rooms you are banned from:".yellow(), lines.join("\n")))?;
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_account(client: Arc<Mutex<Client>>, rooms: &Rooms, pubkeys: &PublicKeys, username: &str) -> io::Result<CommandResult> {
+    let created = {
+        let _lock = lock_users_storage();
+        let users = load_json("data/users.json")?;
+        users[username].get("created_at").and_then(Value::as_u64)
+    };
+    let created = match created.and_then(|ts| chrono::DateTime::from_timestamp(ts as i64, 0)) {
+        Some(dt) => dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => "unknown".to_string(),
+    };
+
+    let rooms_joined = {
+        let rooms_map = lock_rooms(rooms);
+        let mut count = 0;
+        for room_arc in rooms_map.values() {
+            if lock_room(room_arc).users.contains_key(username) {
+                count += 1;
+            }
+        }
+        count
+    };
+
+    let ignored = lock_client(&client).ignore_list.len();
+    let has_pubkey = match pubkeys.lock() {
+        Ok(map) => map.contains_key(username),
+        Err(_) => false,
+    };
+
+    send_success(&client, &format!(
+        "Account: {username} (not in a room)\n> Created: {created}\n> Rooms joined: {rooms_joined}\n> Ignored users: {ignored}\n> Public key registered: {}",
+        if has_pubkey { "Yes" } else { "No" }
+    ))?;
     Ok(CommandResult::Handled)
 }
@@ -0,0 +1,116 @@
+use std::io::{self};
+use std::sync::{Arc, Mutex};
+use serde_json::{json, Value};
+use crate::shared::types::Client;
+use crate::shared::utils::{lock_client, lock_users_storage, load_json, save_json, send_error, send_success};
+use crate::backend::dispatcher::CommandResult;
+
+pub fn handle_block_list(client: Arc<Mutex<Client>>) -> io::Result<CommandResult> {
+    let dm_block_list = {
+        let client_guard = lock_client(&client);
+        client_guard.dm_block_list.clone()
+    };
+    
+    if dm_block_list.is_empty() {
+        send_success(&client, "You do not currently have anyone blocked")?;
+    } else {
+        send_success(&client, &format!("Currently blocking DMs from: {}", dm_block_list.join(", ")))?;
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_block_add(client: Arc<Mutex<Client>>, username: &str, users: &str) -> io::Result<CommandResult> {
+    let to_add: Vec<String> = users
+        .split_whitespace()
+        .filter(|u| !u.is_empty() && *u != username)
+        .map(|u| u.to_string())
+        .collect();
+
+    let (added, already): (Vec<String>, Vec<String>) = {
+        let mut client_guard = lock_client(&client);
+        let mut added = Vec::new();
+        let mut already = Vec::new();
+        for u in &to_add {
+            if client_guard.dm_block_list.contains(u) {
+                already.push(u.clone());
+            } else {
+                client_guard.dm_block_list.push(u.clone());
+                added.push(u.clone());
+            }
+        }
+        (added, already)
+    };
+
+    if !added.is_empty() {
+        let _ulock = lock_users_storage();
+        let mut users_json = load_json("data/users.json")?;
+
+        if users_json[username].get("dm_block").is_none() {
+            users_json[username]["dm_block"] = json!([]);
+        }
+
+        if let Some(block_arr) = users_json[username]
+            .get_mut("dm_block")
+            .and_then(Value::as_array_mut)
+        {
+            for u in &added {
+                block_arr.push(json!(u));
+            }
+        }
+
+        save_json("data/users.json", &users_json)?;
+    }
+
+    if !added.is_empty() {
+        send_success(&client, &format!("Added to DM block list: {}", added.join(", ")))?;
+    }
+    if !already.is_empty() {
+        send_error(&client, &format!("Already blocked: {}", already.join(", ")))?;
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_block_remove(client: Arc<Mutex<Client>>, username: &str, users: &str) -> io::Result<CommandResult> {
+    let to_remove: Vec<String> = users
+        .split_whitespace()
+        .filter(|u| !u.is_empty())
+        .map(|u| u.to_string())
+        .collect();
+
+    let (removed, not_found): (Vec<String>, Vec<String>) = {
+        let mut client_guard = lock_client(&client);
+        let mut removed = Vec::new();
+        let mut not_found = Vec::new();
+        for u in &to_remove {
+            if client_guard.dm_block_list.contains(u) {
+                removed.push(u.clone());
+            } else {
+                not_found.push(u.clone());
+            }
+        }
+        client_guard.dm_block_list.retain(|u| !removed.contains(u));
+        (removed, not_found)
+    };
+
+    if !removed.is_empty() {
+        let _ulock = lock_users_storage();
+        let mut users_json = load_json("data/users.json")?;
+
+        if let Some(block_arr) = users_json[username]
+            .get_mut("dm_block")
+            .and_then(Value::as_array_mut)
+        {
+            block_arr.retain(|v| !removed.iter().any(|u| v == u));
+        }
+
+        save_json("data/users.json", &users_json)?;
+    }
+
+    if !removed.is_empty() {
+        send_success(&client, &format!("Removed from DM block list: {}", removed.join(", ")))?;
+    }
+    if !not_found.is_empty() {
+        send_error(&client, &format!("Not in DM block list: {}", not_found.join(", ")))?;
+    }
+    Ok(CommandResult::Handled)
+}
@@ -0,0 +1,134 @@
+use std::io::{self};
+use std::sync::{Arc, Mutex};
+use serde_json::{json, Value};
+use crate::shared::types::{Client, Rooms};
+use crate::shared::utils::{lock_client, lock_rooms, lock_users_storage, load_json, save_json, send_error, send_success};
+use crate::backend::dispatcher::CommandResult;
+
+pub fn handle_favorite_list(client: Arc<Mutex<Client>>, rooms: &Rooms) -> io::Result<CommandResult> {
+    let favorites = {
+        let client_guard = lock_client(&client);
+        client_guard.favorites.clone()
+    };
+
+    if favorites.is_empty() {
+        send_success(&client, "You do not currently have any favorite rooms")?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let lines: Vec<String> = {
+        let rooms_map = lock_rooms(rooms);
+        favorites.iter().map(|name| {
+            if rooms_map.contains_key(name) {
+                format!("> {name}")
+            } else {
+                format!("> {name} (deleted)")
+            }
+        }).collect()
+    };
+
+    send_success(&client, &format!("Favorite rooms:\n{}", lines.join("\n")))?;
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_favorite_add(client: Arc<Mutex<Client>>, rooms: &Rooms, username: &str, names: &str) -> io::Result<CommandResult> {
+    let to_add: Vec<String> = names
+        .split_whitespace()
+        .map(|r| r.to_string())
+        .collect();
+
+    let missing: Vec<String> = {
+        let rooms_map = lock_rooms(rooms);
+        to_add.iter().filter(|r| !rooms_map.contains_key(*r)).cloned().collect()
+    };
+
+    let (added, already): (Vec<String>, Vec<String>) = {
+        let mut client_guard = lock_client(&client);
+        let mut added = Vec::new();
+        let mut already = Vec::new();
+        for r in to_add.iter().filter(|r| !missing.contains(r)) {
+            if client_guard.favorites.contains(r) {
+                already.push(r.clone());
+            } else {
+                client_guard.favorites.push(r.clone());
+                added.push(r.clone());
+            }
+        }
+        (added, already)
+    };
+
+    if !added.is_empty() {
+        let _ulock = lock_users_storage();
+        let mut users_json = load_json("data/users.json")?;
+
+        if users_json[username].get("favorites").is_none() {
+            users_json[username]["favorites"] = json!([]);
+        }
+
+        if let Some(fav_arr) = users_json[username]
+            .get_mut("favorites")
+            .and_then(Value::as_array_mut)
+        {
+            for r in &added {
+                fav_arr.push(json!(r));
+            }
+        }
+
+        save_json("data/users.json", &users_json)?;
+    }
+
+    if !added.is_empty() {
+        send_success(&client, &format!("Added to favorites: {}", added.join(", ")))?;
+    }
+    if !already.is_empty() {
+        send_error(&client, &format!("Already a favorite: {}", already.join(", ")))?;
+    }
+    if !missing.is_empty() {
+        send_error(&client, &format!("Room not found: {}", missing.join(", ")))?;
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_favorite_remove(client: Arc<Mutex<Client>>, username: &str, names: &str) -> io::Result<CommandResult> {
+    let to_remove: Vec<String> = names
+        .split_whitespace()
+        .map(|r| r.to_string())
+        .collect();
+
+    let (removed, not_found): (Vec<String>, Vec<String>) = {
+        let mut client_guard = lock_client(&client);
+        let mut removed = Vec::new();
+        let mut not_found = Vec::new();
+        for r in &to_remove {
+            if client_guard.favorites.contains(r) {
+                removed.push(r.clone());
+            } else {
+                not_found.push(r.clone());
+            }
+        }
+        client_guard.favorites.retain(|r| !removed.contains(r));
+        (removed, not_found)
+    };
+
+    if !removed.is_empty() {
+        let _ulock = lock_users_storage();
+        let mut users_json = load_json("data/users.json")?;
+
+        if let Some(fav_arr) = users_json[username]
+            .get_mut("favorites")
+            .and_then(Value::as_array_mut)
+        {
+            fav_arr.retain(|v| !removed.iter().any(|r| v == r));
+        }
+
+        save_json("data/users.json", &users_json)?;
+    }
+
+    if !removed.is_empty() {
+        send_success(&client, &format!("Removed from favorites: {}", removed.join(", ")))?;
+    }
+    if !not_found.is_empty() {
+        send_error(&client, &format!("Not in favorites: {}", not_found.join(", ")))?;
+    }
+    Ok(CommandResult::Handled)
+}
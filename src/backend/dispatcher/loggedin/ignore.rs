@@ -7,7 +7,7 @@ use crate::backend::dispatcher::CommandResult;
 
 pub fn handle_ignore_list(client: Arc<Mutex<Client>>) -> io::Result<CommandResult> {
     let ignore_list = {
-        let client_guard = lock_client(&client)?;
+        let client_guard = lock_client(&client);
         client_guard.ignore_list.clone()
     };
     
@@ -19,15 +19,15 @@ pub fn handle_ignore_list(client: Arc<Mutex<Client>>) -> io::Result<CommandResul
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_ignore_add(client: Arc<Mutex<Client>>, username: &String, users: &String) -> io::Result<CommandResult> {
+pub fn handle_ignore_add(client: Arc<Mutex<Client>>, username: &str, users: &str) -> io::Result<CommandResult> {
     let to_add: Vec<String> = users
         .split_whitespace()
-        .filter(|u| !u.is_empty() && u != username)
+        .filter(|u| !u.is_empty() && *u != username)
         .map(|u| u.to_string())
         .collect();
 
     let (added, already): (Vec<String>, Vec<String>) = {
-        let mut client_guard = lock_client(&client)?;
+        let mut client_guard = lock_client(&client);
         let mut added = Vec::new();
         let mut already = Vec::new();
         for u in &to_add {
@@ -42,7 +42,7 @@ pub fn handle_ignore_add(client: Arc<Mutex<Client>>, username: &String, users: &
     };
 
     if !added.is_empty() {
-        let _ulock = lock_users_storage()?;
+        let _ulock = lock_users_storage();
         let mut users_json = load_json("data/users.json")?;
 
         if let Some(ignore_arr) = users_json[username]
@@ -66,7 +66,7 @@ pub fn handle_ignore_add(client: Arc<Mutex<Client>>, username: &String, users: &
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_ignore_remove(client: Arc<Mutex<Client>>, username: &String, users: &String) -> io::Result<CommandResult> {
+pub fn handle_ignore_remove(client: Arc<Mutex<Client>>, username: &str, users: &str) -> io::Result<CommandResult> {
     let to_remove: Vec<String> = users
         .split_whitespace()
         .filter(|u| !u.is_empty())
@@ -74,7 +74,7 @@ pub fn handle_ignore_remove(client: Arc<Mutex<Client>>, username: &String, users
         .collect();
 
     let (removed, not_found): (Vec<String>, Vec<String>) = {
-        let mut client_guard = lock_client(&client)?;
+        let mut client_guard = lock_client(&client);
         let mut removed = Vec::new();
         let mut not_found = Vec::new();
         for u in &to_remove {
@@ -89,7 +89,7 @@ pub fn handle_ignore_remove(client: Arc<Mutex<Client>>, username: &String, users
     };
 
     if !removed.is_empty() {
-        let _ulock = lock_users_storage()?;
+        let _ulock = lock_users_storage();
         let mut users_json = load_json("data/users.json")?;
 
         if let Some(ignore_arr) = users_json[username]
@@ -110,3 +110,89 @@ pub fn handle_ignore_remove(client: Arc<Mutex<Client>>, username: &String, users
     }
     Ok(CommandResult::Handled)
 }
+
+pub fn handle_ignore_share(client: Arc<Mutex<Client>>, username: &str) -> io::Result<CommandResult> {
+    let now_shared = {
+        let _ulock = lock_users_storage();
+        let mut users_json = load_json("data/users.json")?;
+
+        let Some(user_obj) = users_json.get_mut(username).and_then(Value::as_object_mut) else {
+            drop(_ulock);
+            send_error(&client, "Your account record could not be found")?;
+            return Ok(CommandResult::Handled);
+        };
+
+        let now_shared = !user_obj.get("ignore_shared").and_then(Value::as_bool).unwrap_or(false);
+        user_obj.insert("ignore_shared".to_string(), json!(now_shared));
+
+        save_json("data/users.json", &users_json)?;
+        now_shared
+    };
+
+    if now_shared {
+        send_success(&client, "Your ignore list can now be imported by other users")?;
+    } else {
+        send_success(&client, "Your ignore list is no longer shared")?;
+    }
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_ignore_import(client: Arc<Mutex<Client>>, username: &str, source: &str) -> io::Result<CommandResult> {
+    if source == username {
+        send_error(&client, "You cannot import your own ignore list")?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let _ulock = lock_users_storage();
+    let mut users_json = load_json("data/users.json")?;
+
+    let Some(source_obj) = users_json.get(source) else {
+        drop(_ulock);
+        send_error(&client, &format!("User {source} does not exist"))?;
+        return Ok(CommandResult::Handled);
+    };
+
+    if !source_obj.get("ignore_shared").and_then(Value::as_bool).unwrap_or(false) {
+        drop(_ulock);
+        send_error(&client, &format!("{source} has not shared their ignore list"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let source_list: Vec<String> = source_obj.get("ignore")
+        .and_then(Value::as_array)
+        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+
+    let added: Vec<String> = {
+        let mut client_guard = lock_client(&client);
+        let mut added = Vec::new();
+        for u in source_list {
+            if u != *username && !client_guard.ignore_list.contains(&u) {
+                client_guard.ignore_list.push(u.clone());
+                added.push(u);
+            }
+        }
+        added
+    };
+
+    if !added.is_empty() {
+        if let Some(ignore_arr) = users_json[username]
+            .get_mut("ignore")
+            .and_then(Value::as_array_mut)
+        {
+            for u in &added {
+                ignore_arr.push(json!(u));
+            }
+        }
+
+        save_json("data/users.json", &users_json)?;
+    }
+    drop(_ulock);
+
+    if added.is_empty() {
+        send_success(&client, &format!("No new users to ignore from {source}'s list"))?;
+    } else {
+        send_success(&client, &format!("Imported {} user(s) from {source}'s ignore list: {}", added.len(), added.join(", ")))?;
+    }
+    Ok(CommandResult::Handled)
+}
@@ -1,52 +1,118 @@
-use std::collections::VecDeque;
-use std::io::{self, BufRead, BufReader, Write};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufReader, Write};
 use std::fs::File;
 use serde_json::{json, Value};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::sync::{Arc, Mutex};
 use colored::*;
 
 use crate::shared::types::{Client, ClientState, Room, RoomUser, Rooms};
-use crate::shared::utils::{lock_client, lock_clients, lock_rooms, lock_room, lock_rooms_storage, send_success, send_error, send_message, load_json, save_json, send_error_locked, send_success_locked, log_event, broadcast_user_list, broadcast_room_list_to_all};
-use crate::backend::command_utils::{sync_room_members, sync_user_commands};
+use crate::shared::utils::{send_failure, find_room, format_duration, log_dm, take_mail, lock_client, lock_clients, lock_rooms, lock_room, lock_rooms_storage, send_success, send_error, send_message, load_json, save_json, send_error_locked, send_success_locked, send_message_locked, log_event, log_audit, broadcast_message, broadcast_user_list, broadcast_room_list_to_all, read_prompt_line};
+use crate::backend::command_utils::{sync_room_members, sync_user_commands, validate_room_name, expand_motd, ROOM_CREATE_COOLDOWN_SECS, MAX_OWNED_ROOMS, DEFAULT_MODERATOR_COMMANDS, DEFAULT_USER_COMMANDS};
 use crate::backend::dispatcher::CommandResult;
+use crate::backend::dispatcher::inroom::moderation::role_rank;
 use crate::shared::types::{Clients, PublicKeys};
 
-pub fn handle_room_list(client: Arc<Mutex<Client>>, rooms: &Rooms, username: &String) -> io::Result<CommandResult> {
-    let locked_rooms = lock_rooms(rooms)?;
-    let _lock = lock_rooms_storage()?;
+// Returns (room name, display line) pairs, alphabetical or, with `busy`, most online users first
+fn visible_room_lines(rooms: &Rooms, username: &str, favorites: &[String], query: Option<&str>, tag: Option<&str>, busy: bool) -> io::Result<Vec<(String, String)>> {
+    let locked_rooms = lock_rooms(rooms);
+    let _lock = lock_rooms_storage();
 
+    let query = query.map(|q| q.to_lowercase());
+    let tag = tag.map(|t| t.to_lowercase());
     let mut visible_rooms = Vec::new();
 
     for (room_name, room_arc) in locked_rooms.iter() {
+        if query.as_ref().is_some_and(|q| !room_name.to_lowercase().contains(q.as_str())) {
+            continue;
+        }
         if let Ok(room) = room_arc.lock() {
-            if !room.whitelist_enabled || room.whitelist.contains(username) {
+            if tag.as_ref().is_some_and(|t| !room.tags.contains(t)) {
+                continue;
+            }
+            if !room.whitelist_enabled || room.whitelist.iter().any(|u| u == username) {
                 let count = room.online_users.len();
+                let tags = if room.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", room.tags.join(", "))
+                };
+                let star = if favorites.contains(room_name) { "★ " } else { "" };
+                let archived = if room.archived { " [archived]" } else { "" };
                 if count == 1 {
-                    visible_rooms.push(format!("> {room_name} ({count} user online)"));
+                    visible_rooms.push((room_name.clone(), count, format!("{star}{room_name}{tags}{archived} ({count} user online)")));
                 }
                 else {
-                    visible_rooms.push(format!("> {room_name} ({count} users online)"));
+                    visible_rooms.push((room_name.clone(), count, format!("{star}{room_name}{tags}{archived} ({count} users online)")));
                 }
             }
         }
     }
 
-    drop(locked_rooms);
+    if busy {
+        visible_rooms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    } else {
+        visible_rooms.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    Ok(visible_rooms.into_iter().map(|(name, _, line)| (name, line)).collect())
+}
 
-    if visible_rooms.is_empty() {
-        send_error(&client, "No available rooms found")?;
+pub fn handle_room_list(client: Arc<Mutex<Client>>, rooms: &Rooms, username: &str, tag: Option<&str>, busy: bool) -> io::Result<CommandResult> {
+    let favorites = lock_client(&client).favorites.clone();
+    let listed = visible_room_lines(rooms, username, &favorites, None, tag, busy)?;
+    let visible_rooms: Vec<String> = listed.iter()
+        .enumerate()
+        .map(|(i, (_, line))| format!("> #{} {line}", i + 1))
+        .collect();
+    lock_client(&client).last_room_list = listed.into_iter().map(|(name, _)| name).collect();
+
+    match (visible_rooms.is_empty(), tag) {
+        (true, Some(t)) => send_error(&client, &format!("No available rooms tagged '{t}'"))?,
+        (true, None) => send_error(&client, "No available rooms found")?,
+        (false, Some(t)) => send_success(&client, &format!("Available rooms tagged '{t}':\n{}", visible_rooms.join("\n")))?,
+        (false, None) => send_success(&client, &format!("Available rooms:\n{}", visible_rooms.join("\n")))?,
+    }
+
+    Ok(CommandResult::Handled)
+}
+
+pub fn handle_room_search(client: Arc<Mutex<Client>>, rooms: &Rooms, username: &str, query: &str) -> io::Result<CommandResult> {
+    let favorites = lock_client(&client).favorites.clone();
+    let matching_rooms: Vec<String> = visible_room_lines(rooms, username, &favorites, Some(query), None, false)?
+        .into_iter()
+        .map(|(_, line)| format!("> {line}"))
+        .collect();
+
+    if matching_rooms.is_empty() {
+        send_error(&client, "No matching rooms")?;
     } else {
-        send_success(&client, &format!("Available rooms:\n{}", visible_rooms.join("\n")))?;
+        send_success(&client, &format!("Matching rooms:\n{}", matching_rooms.join("\n")))?;
     }
 
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_room_create(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, name: &String, whitelist: bool) -> io::Result<CommandResult> {
+pub fn handle_room_create(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &str, name: &str, whitelist: bool) -> io::Result<CommandResult> {
+    let cooldown_left = {
+        let c = lock_client(&client);
+        c.last_room_create
+            .map(|t| ROOM_CREATE_COOLDOWN_SECS.saturating_sub(t.elapsed().as_secs()))
+            .unwrap_or(0)
+    };
+
+    if cooldown_left > 0 {
+        send_message(&client, &format!("You're creating rooms too quickly, wait {cooldown_left}s").yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    if let Err(reason) = validate_room_name(name) {
+        send_message(&client, &reason.yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
     let id_exists = {
-        let _c = lock_client(&client)?;
-        let rooms_map = lock_rooms(rooms)?;
+        let _c = lock_client(&client);
+        let rooms_map = lock_rooms(rooms);
         rooms_map.contains_key(name)
     };
 
@@ -55,22 +121,33 @@ pub fn handle_room_create(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
         return Ok(CommandResult::Handled);
     }
     
-    let mut rooms_map = lock_rooms(rooms)?;
-    let _lock = lock_rooms_storage()?;
+    let mut rooms_map = lock_rooms(rooms);
+
+    let owned = rooms_map.values()
+        .filter(|room_arc| room_arc.lock().is_ok_and(|r| r.users.get(username).is_some_and(|u| u.role == "owner")))
+        .count();
+    if owned >= MAX_OWNED_ROOMS {
+        drop(rooms_map);
+        send_error(&client, &format!("You own too many rooms (max {MAX_OWNED_ROOMS})"))?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let _lock = lock_rooms_storage();
         
     let new_room = json!({
         "whitelist_enabled": whitelist,
-        "whitelist": if whitelist { vec![username.clone()] } else { Vec::<String>::new() },
+        "whitelist": if whitelist { vec![username.to_string()] } else { Vec::<String>::new() },
         "msg_rate": 10,
         "session_timeout": 3600,
+        "slowmode_secs": 0,
         "roles": {
-            "moderator": ["afk", "seen", "msg", "me", "super.users", "user", "mod"],
-            "user": ["afk", "seen", "msg", "me", "user"],
+            "moderator": DEFAULT_MODERATOR_COMMANDS,
+            "user": DEFAULT_USER_COMMANDS,
             "colors": {
                 "owner": "#FFD700",
                 "admin": "#FF3030",
                 "moderator": "#0080FF",
-                "user": "FFFFFF"
+                "user": "#FFFFFF"
             }
         },
         "users": {
@@ -87,9 +164,19 @@ pub fn handle_room_create(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
                 "muted": false,
                 "mute_stamp": 0,
                 "mute_length": 0,
-                "mute_reason": ""
+                "mute_reason": "",
+                "role_expiry": 0,
+                "prior_role": "",
+                "ban_history": []
             }
-        }
+        },
+        "tags": [],
+        "locked": false,
+        "filter_enabled": false,
+        "blocked_words": [],
+        "announce_staff": false,
+        "pinned": [],
+        "archived": false
     });
 
     let file_path = "data/rooms.json";
@@ -117,16 +204,30 @@ pub fn handle_room_create(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
 
     let room_obj = Room {
         whitelist_enabled: whitelist,
-        whitelist: if whitelist { vec![username.clone()] } else { vec![] },
+        whitelist: if whitelist { vec![username.to_string()] } else { vec![] },
         msg_rate: 10,
         session_timeout: 3600,
+        slowmode_secs: 0,
         roles,
         users,
+        history: VecDeque::new(),
+        tags: Vec::new(),
+        locked: false,
+        filter_enabled: false,
+        blocked_words: Vec::new(),
+        announce_staff: false,
+        pinned: Vec::new(),
+        archived: false,
+        role_labels: HashMap::new(),
+        require_ban_reason: false,
+        motd: String::new(),
+        frozen: false,
         online_users: Vec::new(),
     };
 
-    rooms_map.insert(name.clone(), Arc::new(Mutex::new(room_obj)));
+    rooms_map.insert(name.to_string(), Arc::new(Mutex::new(room_obj)));
     drop(rooms_map);
+    lock_client(&client).last_room_create = Some(Instant::now());
     let _ = broadcast_room_list_to_all(clients, rooms);
 
     if whitelist {
@@ -138,11 +239,33 @@ pub fn handle_room_create(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, username: &String, name: &String) -> io::Result<CommandResult> {
-    let mut c = lock_client(&client)?;
+pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, username: &str, name: &str) -> io::Result<CommandResult> {
+    let mut c = lock_client(&client);
+
+    let name = &match name.strip_prefix('#') {
+        Some(index) => {
+            let Ok(index) = index.parse::<usize>() else {
+                send_error_locked(&mut c, "Usage: /room join #<number from /room list>")?;
+                return Ok(CommandResult::Handled);
+            };
+            if c.last_room_list.is_empty() {
+                send_error_locked(&mut c, "No room list to pick from, run /room list first")?;
+                return Ok(CommandResult::Handled);
+            }
+            match index.checked_sub(1).and_then(|i| c.last_room_list.get(i)) {
+                Some(listed) => listed.clone(),
+                None => {
+                    let len = c.last_room_list.len();
+                    send_error_locked(&mut c, &format!("Room #{index} is out of range, the last /room list had {len} room(s)"))?;
+                    return Ok(CommandResult::Handled);
+                }
+            }
+        }
+        None => name.to_string(),
+    };
 
     let room_arc = {
-        let rooms_map = lock_rooms(rooms)?;
+        let rooms_map = lock_rooms(rooms);
         match rooms_map.get(name) {
             Some(r) => Arc::clone(r),
             None => {
@@ -152,7 +275,7 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
         }
     };
     
-    let _lock = lock_rooms_storage()?;
+    let _lock = lock_rooms_storage();
     let mut room = match room_arc.lock() {
         Ok(r) => r,
         Err(_) => {
@@ -166,11 +289,16 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
         None => false,
     };
 
-    if room.whitelist_enabled && !room.whitelist.contains(username) && !is_owner {
+    if room.whitelist_enabled && !room.whitelist.iter().any(|u| u == username) && !is_owner {
         send_error_locked(&mut c, "You aren't whitelisted for this room")?;
         return Ok(CommandResult::Handled);
     }
 
+    if room.archived && !is_owner {
+        send_error_locked(&mut c, "This room is archived")?;
+        return Ok(CommandResult::Handled);
+    }
+
     let now_ts: u64 = match SystemTime::now().duration_since(UNIX_EPOCH) {
         Ok(d)  => d.as_secs(),
         Err(_) => 0,
@@ -190,21 +318,7 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
                 let remaining_text = if inf {
                     "Permanent".to_string()
                 } else {
-                    let remaining_secs = ban_expires.saturating_sub(now_ts);
-                    let days = remaining_secs / 86_400;
-                    let hrs = (remaining_secs % 86_400) / 3_600;
-                    let mins = (remaining_secs % 3_600) / 60;
-                    let secs = remaining_secs % 60;
-
-                    if days > 0 {
-                        format!("{days}d {hrs}h {mins}m {secs}s remaining")
-                    } else if hrs > 0 {
-                        format!("{hrs}h {mins}m {secs}s remaining")
-                    } else if mins > 0 {
-                        format!("{mins}m {secs}s remaining")
-                    } else {
-                        format!("{secs}s remaining")
-                    }
+                    format!("{} remaining", format_duration(ban_expires.saturating_sub(now_ts)))
                 };
 
                 let reason_txt = if rec.ban_reason.is_empty() {
@@ -238,7 +352,7 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
     }
 
     if !room.users.contains_key(username) {
-        room.users.insert(username.clone(), RoomUser {
+        room.users.insert(username.to_string(), RoomUser {
             nick: "".to_string(),
             color: "".to_string(),
             role: "user".to_string(),
@@ -251,7 +365,10 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
             muted: false,
             mute_stamp: 0,
             mute_length: 0,
-            mute_reason: "".to_string()
+            mute_reason: "".to_string(),
+            role_expiry: 0,
+            prior_role: "".to_string(),
+            ban_history: Vec::new()
         });
 
         let mut rooms_json = load_json("data/rooms.json")?;
@@ -270,25 +387,31 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
                 "muted": false,
                 "mute_stamp": 0,
                 "mute_length": 0,
-                "mute_reason": ""
+                "mute_reason": "",
+                "role_expiry": 0,
+                "prior_role": "",
+                "ban_history": []
             });
 
             save_json("data/rooms.json", &rooms_json)?;
         }
     }
 
-    if !room.online_users.contains(username) {
-        room.online_users.push(username.clone());
+    if !room.online_users.iter().any(|u| u == username) {
+        room.online_users.push(username.to_string());
     }
 
     let peer = c.addr;
     c.state = ClientState::InRoom {
-        username: username.clone(),
+        username: username.to_string(),
         room: name.clone(),
         room_time: Some(SystemTime::now()),
         msg_timestamps: VecDeque::new(),
+        last_msg: None,
         inactive_time: Some(SystemTime::now()),
-        is_afk: false
+        is_afk: false,
+        afk_reason: String::new(),
+        warned: false
     };
 
     writeln!(c.stream, "/ROOM_STATE")?;
@@ -299,7 +422,41 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
         .unwrap_or("user");
     writeln!(c.stream, "/ROLE {user_role}")?;
 
+    let staff_alert = match room.users.get(username) {
+        Some(u) if room.announce_staff && !u.hidden && role_rank(&u.role) >= role_rank("moderator") => {
+            let title = room.role_label(&u.role);
+            Some(format!("{title} {username} is now online"))
+        }
+        _ => None,
+    };
+
     send_success_locked(&mut c, &format!("Joined room: {name}"))?;
+    if !room.pinned.is_empty() {
+        let mut lines = vec!["Pinned:".to_string()];
+        for (i, pin) in room.pinned.iter().enumerate() {
+            lines.push(format!("  {}. {pin}", i + 1));
+        }
+        send_message_locked(&mut c, &lines.join("\n").bright_magenta().to_string())?;
+    }
+    if !room.motd.is_empty() {
+        let motd = expand_motd(&room.motd, username, name, room.online_users.len());
+        send_message_locked(&mut c, &motd.bright_yellow().to_string())?;
+    }
+    match take_mail(username, name) {
+        Ok(mail) if !mail.is_empty() => {
+            let now_ts = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            send_message_locked(&mut c, &format!("You have {} message(s) sent while you were away:", mail.len()).cyan().to_string())?;
+            for (from, text, sent_at) in &mail {
+                writeln!(c.stream, "{}", format!("(Private, {} ago) {from}: {text}", format_duration(now_ts.saturating_sub(*sent_at))).cyan().italic())?;
+                log_dm(&mut c, from, text, false);
+            }
+            if let Some((from, _, _)) = mail.last() {
+                c.last_dm_from = Some(from.clone());
+            }
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Error delivering mail for {username} in {name}: {e}"),
+    }
     drop(room);
     drop(c);
     log_event(&peer, Some(username), Some(name), &format!("Joined room {}", name));
@@ -307,15 +464,18 @@ pub fn handle_room_join(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &R
     let _ = sync_room_members(rooms, clients, pubkeys, name);
     let _ = broadcast_user_list(clients, rooms, name);
     let _ = broadcast_room_list_to_all(clients, rooms);
+    if let Some(msg) = staff_alert {
+        let _ = broadcast_message(clients, name, username, &msg.bright_cyan().to_string(), false, true, None);
+    }
 
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_room_import(client: Arc<Mutex<Client>>, rooms: &Rooms, filename: &String) -> io::Result<CommandResult> {
+pub fn handle_room_import(client: Arc<Mutex<Client>>, rooms: &Rooms, filename: &str) -> io::Result<CommandResult> {
     let safe_filename = if !filename.ends_with(".json") {
         format!("{filename}.json")
     } else {
-        filename.clone()
+        filename.to_string()
     };
 
     let import_path = format!("data/vault/rooms/{safe_filename}");
@@ -344,8 +504,13 @@ pub fn handle_room_import(client: Arc<Mutex<Client>>, rooms: &Rooms, filename: &
         }
     };
 
-    let mut rooms_map = lock_rooms(rooms)?;
-    let _lock = lock_rooms_storage()?;
+    if let Err(reason) = validate_room_name(&room_name) {
+        send_message(&client, &reason.yellow().to_string())?;
+        return Ok(CommandResult::Handled);
+    }
+
+    let mut rooms_map = lock_rooms(rooms);
+    let _lock = lock_rooms_storage();
 
     let mut rooms_json = load_json("data/rooms.json")?;
 
@@ -374,24 +539,18 @@ pub fn handle_room_import(client: Arc<Mutex<Client>>, rooms: &Rooms, filename: &
     Ok(CommandResult::Handled)
 }
 
-pub fn handle_room_delete(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &String, name: &String, force: bool) -> io::Result<CommandResult> {
+pub fn handle_room_delete(client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, username: &str, name: &str, force: bool, dry: bool) -> io::Result<CommandResult> {
     {
-        let rooms_map = lock_rooms(rooms)?;
-        let room_arc = match rooms_map.get(name) {
-            Some(r) => Arc::clone(r),
-            None => {
-                send_message(&client, &format!("Error: Room {name} not found").yellow().to_string())?;
+        let rooms_map = lock_rooms(rooms);
+        let room_arc = match find_room(&rooms_map, name) {
+            Ok(r) => r,
+            Err(e) => {
+                send_failure(&client, &e)?;
                 return Ok(CommandResult::Handled);
             }
         };
 
-        let room = match lock_room(&room_arc) {
-            Ok(g) => g,
-            Err(_) => {
-                send_error(&client, "Could not lock room")?;
-                return Ok(CommandResult::Handled);
-            }
-        };
+        let room = lock_room(&room_arc);
 
         match room.users.get(username) {
             Some(user) if user.role == "owner" => (),
@@ -400,18 +559,22 @@ pub fn handle_room_delete(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
                 return Ok(CommandResult::Handled);
             }
         }
+
+        if dry {
+            send_message(&client, &format!("[dry run] Would delete room {name}: {} online user(s) sent to the lobby, {} user record(s) removed", room.online_users.len(), room.users.len()).cyan().to_string())?;
+            return Ok(CommandResult::Handled);
+        }
     }
 
     if !force {
-        let mut c = lock_client(&client)?;
-        use std::io::Write;
-        writeln!(c.stream, "{}", format!("Are you sure you want to delete room {name}? (y/n): ").red())?;
+        let mut c = lock_client(&client);
+        send_error_locked(&mut c, &format!("Are you sure you want to delete room {name}? (y/n): "))?;
 
         let mut reader = BufReader::new(c.stream.try_clone()?);
         drop(c);
         loop {
             let mut line = String::new();
-            let bytes_read = reader.read_line(&mut line)?;
+            let bytes_read = read_prompt_line(&mut reader, &mut line, &client)?;
             if bytes_read == 0 {
                 let clone = Arc::clone(&client);
                 send_error(&clone, "Connection closed")?;
@@ -431,8 +594,8 @@ pub fn handle_room_delete(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
         }
     }
 
-    let mut rooms_map = lock_rooms(rooms)?;
-    let _lock = lock_rooms_storage()?;
+    let mut rooms_map = lock_rooms(rooms);
+    let _lock = lock_rooms_storage();
     rooms_map.remove(name);
 
     let mut rooms_json = load_json("data/rooms.json")?;
@@ -448,7 +611,7 @@ pub fn handle_room_delete(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
     }
 
     {
-        let clients_map = lock_clients(clients)?;
+        let clients_map = lock_clients(clients);
         for c_arc in clients_map.values() {
             if let Ok(mut target_c) = c_arc.try_lock() {
                 let in_room = if let ClientState::InRoom { room: r, .. } = &target_c.state {
@@ -459,7 +622,7 @@ pub fn handle_room_delete(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
 
                 if in_room {
                     let _ = writeln!(target_c.stream, "/LOBBY_STATE");
-                    let _ = writeln!(target_c.stream, "{}", format!("The room '{name}' has been deleted by its owner.").red());
+                    let _ = send_error_locked(&mut target_c, &format!("The room '{name}' has been deleted by its owner."));
                     target_c.state = ClientState::LoggedIn { username: match &target_c.state {
                         ClientState::InRoom { username, .. } => username.clone(),
                         _ => "Guest".to_string(),
@@ -473,6 +636,7 @@ pub fn handle_room_delete(client: Arc<Mutex<Client>>, clients: &Clients, rooms:
     drop(rooms_map);
 
     send_success(&client, &format!("Room {name} deleted successfully"))?;
+    log_audit(username, "-", name, "room.delete");
     
     let _ = broadcast_room_list_to_all(clients, rooms);
     
@@ -12,17 +12,17 @@ pub enum CommandResult {
 }
 
 use crate::backend::parser::Command;
-use crate::shared::types::{Client, ClientState, Clients, PublicKeys, Rooms};
+use crate::shared::types::{Client, ClientState, Clients, LoginFailures, PublicKeys, ResumeTokens, Rooms};
 use std::io;
 
-pub fn dispatch_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys) -> io::Result<CommandResult> {
+pub fn dispatch_command(cmd: Command, client: Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys, login_failures: &LoginFailures, resume_tokens: &ResumeTokens) -> io::Result<CommandResult> {
     let state = {
-        let locked = lock_client(&client)?;
+        let locked = lock_client(&client);
         locked.state.clone()
     };
 
     match state {
-        ClientState::Guest => guest::guest_command(cmd, client, clients, rooms),
+        ClientState::Guest => guest::guest_command(cmd, client, clients, rooms, pubkeys, login_failures, resume_tokens),
         ClientState::LoggedIn { username } => loggedin::loggedin_command(cmd, client, clients, rooms, &username, pubkeys),
         ClientState::InRoom { username, room, .. } => inroom::inroom_command(cmd, client, clients, rooms, &username, &room, pubkeys)
     }
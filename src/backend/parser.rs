@@ -1,68 +1,130 @@
 use colored::*;
-use crate::backend::command_utils::{duration_format_passes};
+use crate::backend::command_utils::{duration_format_passes, parse_duration, AUDIT_LOG_DEFAULT_ENTRIES, AUDIT_LOG_MAX_ENTRIES};
 
 impl ToString for Command {
     fn to_string(&self) -> String {
         match self {
-            Command::Help |
+            Command::Help { .. } |
+            Command::HelpRoles |
             Command::Ping { .. } |
             Command::PubKey { .. } |
+            Command::Edit { .. } |
             Command::Quit |
             Command::Leave |
             Command::Status |
+            Command::Stats |
+            Command::Mode { .. } |
+            Command::DmHistory |
             Command::IgnoreList |
             Command::IgnoreAdd { .. } |
-            Command::IgnoreRemove { .. } => "",
+            Command::IgnoreRemove { .. } |
+            Command::IgnoreShare |
+            Command::IgnoreImport { .. } |
+            Command::BlockList |
+            Command::BlockAdd { .. } |
+            Command::BlockRemove { .. } => "",
             
             Command::Account |
             Command::AccountRegister { .. } |
             Command::AccountLogin { .. } |
+            Command::AccountResume { .. } |
             Command::AccountLogout |
             Command::AccountEditUsername { .. } |
             Command::AccountEditPassword { .. } |
             Command::AccountImport { .. } |
             Command::AccountExport { .. } |
-            Command::AccountDelete { .. } => "",
+            Command::AccountDelete { .. } |
+            Command::AccountBans => "",
             
-            Command::RoomList |
+            Command::RoomList { .. } |
+            Command::RoomSearch { .. } |
             Command::RoomCreate { .. } |
             Command::RoomJoin { .. } |
             Command::RoomImport { .. } |
-            Command::RoomDelete { .. } => "",
-
-            Command::AFK => "afk",
-            Command::DM { .. } => "msg",
+            Command::RoomDelete { .. } |
+            Command::RoomHistory { .. } |
+            Command::RoomFavoriteList |
+            Command::RoomFavoriteAdd { .. } |
+            Command::RoomFavoriteRemove { .. } |
+            Command::RoomTransfer { .. } => "",
+
+            Command::AFK { .. } => "afk",
+            Command::DM { .. } |
+            Command::DmRole { .. } |
+            Command::Reply { .. } => "msg",
             Command::Me { .. } => "me",
             Command::Seen { .. } => "seen",
             Command::Announce { .. } => "announce",
 
-            Command::SuperUsers => "super.users",
+            Command::SuperInfo => "super.info",
+            Command::SuperUsers { .. } => "super.users",
             Command::SuperRename { .. } => "super.rename",
+            Command::SuperLock => "super.lock",
+            Command::SuperStaffAlerts => "super.staffalerts",
+            Command::SuperPolicyBanReason { .. } => "super.policy",
+            Command::SuperMotd { .. } => "super.motd",
+            Command::SuperArchive => "super.archive",
+            Command::SuperFreeze => "super.freeze",
+            Command::SuperLog { .. } => "super.log",
+            Command::SuperSlowmode { .. } => "super.slowmode",
+            Command::SuperPurge { .. } => "super.purge",
+            Command::SuperKickAll { .. } => "super.kickall",
+            Command::SuperKickRole { .. } => "super.kick-role",
+            Command::SuperRun { .. } => "super.run",
+            Command::SuperHandoff { .. } => "super.handoff",
+            Command::SuperImportProfile { .. } => "super.import-profile",
             Command::SuperExport { .. } => "super.export",
+            Command::SuperExportAll => "super.export",
             Command::SuperWhitelist => "super.whitelist",
             Command::SuperWhitelistToggle => "super.whitelist",
+            Command::SuperWhitelistEnforce => "super.whitelist",
             Command::SuperWhitelistAdd { .. } => "super.whitelist.add",
             Command::SuperWhitelistRemove { .. } => "super.whitelist.remove",
+            Command::SuperWhitelistImport { .. } => "super.whitelist.import",
             Command::SuperLimit => "super.limit",
             Command::SuperLimitRate { .. } => "super.limit.rate",
             Command::SuperLimitSession { .. } => "super.limit.session",
             Command::SuperRoles => "super.roles",
             Command::SuperRolesAdd { .. } => "super.roles.add",
             Command::SuperRolesRevoke { .. } => "super.roles.revoke",
+            Command::SuperRolesPreview { .. } => "super.roles",
             Command::SuperRolesAssign { .. } => "super.roles.assign",
             Command::SuperRolesRecolor { .. } => "super.roles.recolor",
+            Command::SuperRolesReset => "super.roles.reset",
+            Command::SuperRolesLabel { .. } => "super.roles",
+            Command::SuperRolesCopy { .. } => "super.roles.copy",
+            Command::SuperRolesExport { .. } => "super.roles",
+            Command::SuperRolesImport { .. } => "super.roles.import",
+            Command::SuperTagsAdd { .. } |
+            Command::SuperTagsRemove { .. } => "super.tags",
+            Command::SuperFilterList |
+            Command::SuperFilterToggle |
+            Command::SuperFilterAdd { .. } |
+            Command::SuperFilterRemove { .. } => "super.filter",
+            Command::SuperPinList |
+            Command::SuperPinAdd { .. } |
+            Command::SuperPinRemove { .. } |
+            Command::SuperPinClear => "super.pin",
             
-            Command::Users => "user.list",
+            Command::Users |
+            Command::UsersCount |
+            Command::RoomMembers => "user.list",
             Command::UsersRename { .. } => "user.rename",
-            Command::UsersRecolor { .. } => "user.recolor",
+            Command::UsersRecolor { .. } |
+            Command::UsersRecolorPreview { .. } => "user.recolor",
+            Command::UsersProfile { .. } => "user.profile",
             Command::UsersHide => "user.hide",
+            Command::UsersWhoami => "user.whoami",
 
-            Command::ModInfo => "mod.info",
+            Command::ModInfo { .. } => "mod.info",
             Command::ModKick { .. } => "mod.kick",
             Command::ModMute { .. } => "mod.mute",
             Command::ModUnmute { .. } => "mod.unmute",
             Command::ModBan { .. } => "mod.ban",
             Command::ModUnban { .. } => "mod.unban",
+            Command::ModWhois { .. } => "mod.whois",
+            Command::ModExport { .. } => "mod.export",
+            Command::ModHistory { .. } => "mod.history",
 
             Command::InvalidSyntax { .. } | Command::Unavailable => ""
         }.to_string()
@@ -71,65 +133,127 @@ impl ToString for Command {
 
 #[derive(Clone)]
 pub enum Command {
-    Help,
-    Ping { start_time: Option<u128> },
+    Help { query: String },
+    HelpRoles,
+    Ping { start_time: Option<u64> },
     PubKey { pubkey: String },
+    Edit { recipient: String, ciphertext: String, is_first: bool },
     Quit,
     Leave,
     Status,
+    Stats,
+    Mode { json: bool },
     IgnoreList,
     IgnoreAdd { users: String },
     IgnoreRemove { users: String },
+    IgnoreShare,
+    IgnoreImport { user: String },
+    BlockList,
+    BlockAdd { users: String },
+    BlockRemove { users: String },
 
-    AFK,
+    AFK { reason: String },
     DM { recipient: String, message: String },
-    Me { action: String },
-    Announce { message: String },
+    DmRole { role: String, message: String },
+    DmHistory,
+    Reply { message: String },
+    Me { action: String, target: Option<String> },
+    Announce { message: String, scope: Option<String> },
     Seen { username: String },
 
     Account,
     AccountRegister { username: String, password: String, confirm: String },
     AccountLogin { username: String, password: String },
+    AccountResume { token: String },
     AccountLogout,
     AccountEditUsername { username: String },
     AccountEditPassword { current_password: String, new_password: String },
     AccountImport { filename: String },
     AccountExport { filename: String },
     AccountDelete { force: bool },
+    AccountBans,
 
-    RoomList,
+    RoomList { tag: Option<String>, busy: bool },
+    RoomSearch { query: String },
     RoomCreate { name: String, whitelist: bool },
     RoomJoin { name: String },
     RoomImport { filename: String },
-    RoomDelete { name: String, force: bool },
-
-    SuperUsers,
-    SuperRename { name: String },
-    SuperExport { filename: String },
+    RoomDelete { name: String, force: bool, dry: bool },
+    RoomHistory { count: usize },
+    RoomMembers,
+    RoomTransfer { username: String },
+    RoomFavoriteList,
+    RoomFavoriteAdd { rooms: String },
+    RoomFavoriteRemove { rooms: String },
+
+    SuperInfo,
+    SuperUsers { filter: Option<String> },
+    SuperRename { name: String, force: bool },
+    SuperLock,
+    SuperStaffAlerts,
+    SuperPolicyBanReason { enabled: bool },
+    SuperMotd { text: Option<String> },
+    SuperArchive,
+    SuperFreeze,
+    SuperLog { count: usize },
+    SuperSlowmode { secs: u32 },
+    SuperPurge { days: u64, dry: bool },
+    SuperKickAll { reason: String, dry: bool },
+    SuperKickRole { role: String },
+    SuperRun { filename: String },
+    SuperHandoff { username: String },
+    SuperImportProfile { target: String, source: String },
+    SuperExport { filename: String, section: Option<String> },
+    SuperExportAll,
     SuperWhitelist,
     SuperWhitelistToggle,
+    SuperWhitelistEnforce,
     SuperWhitelistAdd { users: String },
     SuperWhitelistRemove { users: String },
+    SuperWhitelistImport { filename: String },
     SuperLimit,
     SuperLimitRate { limit: u8 },
     SuperLimitSession { limit: u32 },
     SuperRoles,
     SuperRolesAdd { role: String, commands: String },
     SuperRolesRevoke { role: String, commands: String },
-    SuperRolesAssign { role: String, users: String },
+    SuperRolesPreview { role: String, commands: String },
+    SuperRolesAssign { role: String, users: String, duration: u64 },
     SuperRolesRecolor { role: String, color: String },
+    SuperRolesReset,
+    SuperRolesLabel { role: String, label: String },
+    SuperRolesCopy { source: String, colors: bool },
+    SuperRolesExport { filename: String },
+    SuperRolesImport { filename: String },
+    SuperTagsAdd { tags: String },
+    SuperTagsRemove { tags: String },
+    SuperFilterList,
+    SuperFilterToggle,
+    SuperFilterAdd { words: String },
+    SuperFilterRemove { words: String },
+    SuperPinList,
+    SuperPinAdd { text: String },
+    SuperPinRemove { index: usize },
+    SuperPinClear,
 
     Users,
+    UsersCount,
     UsersRename { name: String },
     UsersRecolor { color: String },
+    UsersRecolorPreview { color: String },
+    UsersProfile { nick: String, color: String },
     UsersHide,
+    UsersWhoami,
 
-    ModInfo,
+    ModInfo { page: usize },
     ModKick { username: String, reason: String },
     ModMute { username: String, duration: String, reason: String },
-    ModUnmute { username: String },
+    ModUnmute { users: String },
     ModBan { username: String, duration: String, reason: String },
-    ModUnban { username: String },
+    ModUnban { users: String },
+    ModWhois { username: String },
+    ModExport { filename: String },
+    ModHistory { username: String },
 
     InvalidSyntax { err_msg: String },
     Unavailable
@@ -137,16 +261,52 @@ pub enum Command {
 
 pub fn parse_command(input: &str) -> Command {
     let mut tokens: Vec<&str> = input.split_whitespace().collect();
-    tokens[0] = &tokens[0][1..];
+    match tokens.first().and_then(|t| t.strip_prefix('/')) {
+        Some(first) if !first.is_empty() => tokens[0] = first,
+        _ => return Command::Unavailable,
+    }
 
     match tokens.as_slice() {
-        ["help"] | ["h"] => Command::Help,
-        ["ping", ts] => Command::Ping { start_time: ts.parse::<u128>().ok() },
+        ["help"] | ["h"] => Command::Help { query: String::new() },
+        ["help", "roles"] | ["h", "roles"] | ["h", "r"] => Command::HelpRoles,
+        ["help", query @ ..] | ["h", query @ ..] => Command::Help { query: query.join(" ") },
+        ["ping", ts] => Command::Ping { start_time: ts.parse::<u64>().ok() },
         ["ping"] => Command::Ping { start_time: None },
         ["pubkey", pubkey] => Command::PubKey { pubkey: pubkey.to_string() },
+
+        ["edit", recipient, ciphertext] => Command::Edit {
+            recipient: recipient.to_string(),
+            ciphertext: ciphertext.to_string(),
+            is_first: false
+        },
+
+        ["edit", recipient, ciphertext, "f"] => Command::Edit {
+            recipient: recipient.to_string(),
+            ciphertext: ciphertext.to_string(),
+            is_first: true
+        },
+
+        ["edit", ..] => {
+            let err_msg = format!("{}", "Usage: /edit <new message>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
         ["quit"] | ["exit"] | ["q"] | ["e"] => Command::Quit,
         ["leave"] => Command::Leave,
         ["status"] => Command::Status,
+        ["stats"] => Command::Stats,
+
+        ["stats", ..] => {
+            let err_msg = format!("{}", "Usage: /stats".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["mode", "json"] => Command::Mode { json: true },
+        ["mode", "text"] => Command::Mode { json: false },
+
+        ["mode", ..] => {
+            let err_msg = format!("{}", "Usage: /mode json|text".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
 
         ["ignore", "list"] |
         ["ignore", "l"] |
@@ -191,13 +351,105 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["ignore", "share"] |
+        ["ignore", "s"] |
+        ["i", "share"] |
+        ["i", "s"] => Command::IgnoreShare,
+
+        ["ignore", "share", ..] |
+        ["ignore", "s", ..] |
+        ["i", "share", ..] |
+        ["i", "s", ..] => {
+            let err_msg = format!("{}", "Usage: /ignore share".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["ignore", "import", user] |
+        ["i", "import", user] => Command::IgnoreImport {
+            user: user.to_string()
+        },
+
+        ["ignore", "import", ..] |
+        ["i", "import", ..] => {
+            let err_msg = format!("{}", "Usage: /ignore import <username>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["ignore", ..] |
         ["i", ..] => {
-            let err_msg = format!("{}", "Ignore commands:\n> /ignore list\n> /ignore add <user1> <user2> ...\n> /ignore remove <user1> <user2> ...".bright_blue());
+            let err_msg = format!("{}", "Ignore commands:\n> /ignore list\n> /ignore add <user1> <user2> ...\n> /ignore remove <user1> <user2> ...\n> /ignore share\n> /ignore import <username>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["block", "list"] |
+        ["block", "l"] |
+        ["b", "list"] |
+        ["b", "l"] => Command::BlockList,
+
+        ["block", "list", ..] |
+        ["block", "l", ..] |
+        ["b", "list", ..] |
+        ["b", "l", ..] => {
+            let err_msg = format!("{}", "Usage: /block list".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["block", "add", users @ ..] |
+        ["block", "a", users @ ..] |
+        ["b", "add", users @ ..] |
+        ["b", "a", users @ ..] if !users.is_empty() => Command::BlockAdd {
+            users: users.join(" ")
+        },
+
+        ["block", "add", ..] |
+        ["block", "a", ..] |
+        ["b", "add", ..] |
+        ["b", "a", ..] => {
+            let err_msg = format!("{}", "Usage: /block add <user1> <user2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["block", "remove", users @ ..] |
+        ["block", "r", users @ ..] |
+        ["b", "remove", users @ ..] |
+        ["b", "r", users @ ..] if !users.is_empty() => Command::BlockRemove {
+            users: users.join(" ")
+        },
+
+        ["block", "remove", ..] |
+        ["block", "r", ..] |
+        ["b", "remove", ..] |
+        ["b", "r", ..] => {
+            let err_msg = format!("{}", "Usage: /block remove <user1> <user2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["block", ..] |
+        ["b", ..] => {
+            let err_msg = format!("{}", "Block commands:\n> /block list\n> /block add <user1> <user2> ...\n> /block remove <user1> <user2> ...".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
-        ["afk"] => Command::AFK,
+        ["afk", reason @ ..] => Command::AFK {
+            reason: reason.join(" ")
+        },
+
+        ["message", "history"] |
+        ["msg", "history"] |
+        ["dm", "history"] => Command::DmHistory,
+
+        ["message", recipient, message @ ..] |
+        ["msg", recipient, message @ ..] |
+        ["dm", recipient, message @ ..] if !message.is_empty() && recipient.starts_with('@') => {
+            match *recipient {
+                "@mods" => Command::DmRole { role: "moderator".to_string(), message: message.join(" ") },
+                "@admins" => Command::DmRole { role: "admin".to_string(), message: message.join(" ") },
+                _ => {
+                    let err_msg = format!("{}", "Usage: /message @mods|@admins <message>".bright_blue());
+                    Command::InvalidSyntax { err_msg }
+                }
+            }
+        },
 
         ["message", recipient, message @ ..] |
         ["msg", recipient, message @ ..] |
@@ -209,16 +461,36 @@ pub fn parse_command(input: &str) -> Command {
         ["message", ..] |
         ["msg", ..] |
         ["dm", ..] => {
-            let err_msg = format!("{}", "Usage: /message <recipient> <message>".bright_blue());
+            let err_msg = format!("{}", "Usage: /message <recipient|@mods|@admins> <message>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["reply", message @ ..] if !message.is_empty() => Command::Reply {
+            message: message.join(" ")
+        },
+
+        ["reply", ..] => {
+            let err_msg = format!("{}", "Usage: /reply <message>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["me", target, action @ ..] if target.len() > 1 && target.starts_with('@') && !action.is_empty() => Command::Me {
+            action: action.join(" "),
+            target: Some(target[1..].to_string())
+        },
+
+        ["me", target] if target.starts_with('@') => {
+            let err_msg = format!("{}", "Usage: /me @<username> <action>".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
         ["me", action @ ..] if !action.is_empty() => Command::Me {
-            action: action.join(" ")
+            action: action.join(" "),
+            target: None
         },
 
         ["me", ..] => {
-            let err_msg = format!("{}", "Usage: /me <action>".bright_blue());
+            let err_msg = format!("{}", "Usage: /me <action> | /me @<username> <action>".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -231,14 +503,46 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["whois", username] |
+        ["mod", "whois", username] |
+        ["m", "whois", username] => Command::ModWhois {
+            username: username.to_string()
+        },
+
+        ["whois", ..] |
+        ["mod", "whois", ..] |
+        ["m", "whois", ..] => {
+            let err_msg = format!("{}", "Usage: /whois <username>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["announce", "mods", message @ ..] |
+        ["an", "mods", message @ ..] if !message.is_empty() => Command::Announce {
+            message: message.join(" "),
+            scope: Some("moderator".to_string())
+        },
+
+        ["announce", "admins", message @ ..] |
+        ["an", "admins", message @ ..] if !message.is_empty() => Command::Announce {
+            message: message.join(" "),
+            scope: Some("admin".to_string())
+        },
+
+        ["announce", "mods" | "admins"] |
+        ["an", "mods" | "admins"] => {
+            let err_msg = format!("{}", "Usage: /announce mods|admins <message>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["announce", message @ ..] |
         ["an", message @ ..] if !message.is_empty() => Command::Announce {
-            message: message.join(" ")
+            message: message.join(" "),
+            scope: None
         },
 
         ["announce", ..] |
         ["an", ..] => {
-            let err_msg = format!("{}", "Usage: /announce <message>".bright_blue());
+            let err_msg = format!("{}", "Usage: /announce <mods|admins>? <message>".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -275,6 +579,17 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["account", "resume", token] |
+        ["a", "resume", token] => Command::AccountResume {
+            token: token.to_string()
+        },
+
+        ["account", "resume", ..] |
+        ["a", "resume", ..] => {
+            let err_msg = format!("{}", "Usage: /account resume <token>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["account", "logout"] |
         ["a", "logout"] => Command::AccountLogout {},
 
@@ -388,6 +703,15 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["account", "bans"] |
+        ["a", "bans"] => Command::AccountBans,
+
+        ["account", "bans", ..] |
+        ["a", "bans", ..] => {
+            let err_msg = format!("{}", "Usage: /account bans".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["account", "info"] |
         ["a", "info"] |
         ["account", "i"] |
@@ -403,7 +727,7 @@ pub fn parse_command(input: &str) -> Command {
 
         ["account", ..] |
         ["a", ..] => {
-            let err_msg = format!("{}", "Account commands:\n> /account info\n> /account register <username> <password> <password confirm>\n> /account login <username> <password>\n> /account logout\n> /account edit\n> /account import <filename>\n> /account export <filename>?\n> /account delete force?".bright_blue());
+            let err_msg = format!("{}", "Account commands:\n> /account info\n> /account register <username> <password> <password confirm>\n> /account login <username> <password>\n> /account resume <token>\n> /account logout\n> /account edit\n> /account import <filename>\n> /account export <filename>?\n> /account delete force?\n> /account bans".bright_blue());
             Command::InvalidSyntax { err_msg }
         }
 
@@ -454,7 +778,7 @@ pub fn parse_command(input: &str) -> Command {
         ["r", "join", ..] |
         ["room", "j", ..] |
         ["r", "j", ..] => {
-            let err_msg = format!("{}", "Usage: /room join <room name>".bright_blue());
+            let err_msg = format!("{}", "Usage: /room join <room name>|#<number>".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -474,7 +798,8 @@ pub fn parse_command(input: &str) -> Command {
         ["room", "d", name] |
         ["r", "d", name] => Command::RoomDelete{
             name: name.to_string(),
-            force: false
+            force: false,
+            dry: false
         },
 
         ["room", "delete", "force", name] |
@@ -486,46 +811,208 @@ pub fn parse_command(input: &str) -> Command {
         ["room", "d", "f", name] |
         ["r", "d", "f", name] => Command::RoomDelete {
             name: name.to_string(),
-            force: true
+            force: true,
+            dry: false
+        },
+
+        ["room", "delete", name, "dry"] |
+        ["r", "delete", name, "dry"] |
+        ["room", "d", name, "dry"] |
+        ["r", "d", name, "dry"] |
+        ["room", "delete", "force" | "f", name, "dry"] |
+        ["r", "delete", "force" | "f", name, "dry"] |
+        ["room", "d", "force" | "f", name, "dry"] |
+        ["r", "d", "force" | "f", name, "dry"] => Command::RoomDelete {
+            name: name.to_string(),
+            force: true,
+            dry: true
         },
 
         ["room", "delete", ..] |
         ["r", "delete", ..] |
         ["room", "d", ..] |
         ["r", "d", ..] => {
-            let err_msg = format!("{}", "Usage: /room delete force? <room name>".bright_blue());
+            let err_msg = format!("{}", "Usage: /room delete force? <room name> dry?".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
         ["room", "list"] |
         ["r", "list"] |
         ["room", "l"] |
-        ["r", "l"] => Command::RoomList,
+        ["r", "l"] => Command::RoomList { tag: None, busy: false },
+
+        ["room", "list", "busy"] |
+        ["r", "list", "busy"] |
+        ["room", "l", "busy"] |
+        ["r", "l", "busy"] => Command::RoomList { tag: None, busy: true },
+
+        ["room", "list", "busy", tag] |
+        ["r", "list", "busy", tag] |
+        ["room", "l", "busy", tag] |
+        ["r", "l", "busy", tag] => Command::RoomList {
+            tag: Some(tag.to_string()),
+            busy: true
+        },
+
+        ["room", "list", tag] |
+        ["r", "list", tag] |
+        ["room", "l", tag] |
+        ["r", "l", tag] => Command::RoomList {
+            tag: Some(tag.to_string()),
+            busy: false
+        },
 
         ["room", "list", ..] |
         ["r", "list", ..] |
         ["room", "l", ..] |
         ["r", "l", ..] => {
-            let err_msg = format!("{}", "Usage: /room list".bright_blue());
+            let err_msg = format!("{}", "Usage: /room list busy? <tag>?".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["room", "search", query] |
+        ["r", "search", query] |
+        ["room", "s", query] |
+        ["r", "s", query] => Command::RoomSearch {
+            query: query.to_string()
+        },
+
+        ["room", "search", ..] |
+        ["r", "search", ..] |
+        ["room", "s", ..] |
+        ["r", "s", ..] => {
+            let err_msg = format!("{}", "Usage: /room search <query>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["room", "favorite", "list"] |
+        ["room", "fav", "list"] |
+        ["r", "favorite", "list"] |
+        ["r", "fav", "list"] => Command::RoomFavoriteList,
+
+        ["room", "favorite", "add", rooms @ ..] |
+        ["room", "fav", "add", rooms @ ..] |
+        ["r", "favorite", "add", rooms @ ..] |
+        ["r", "fav", "add", rooms @ ..] if !rooms.is_empty() => Command::RoomFavoriteAdd {
+            rooms: rooms.join(" ")
+        },
+
+        ["room", "favorite", "add", ..] |
+        ["room", "fav", "add", ..] |
+        ["r", "favorite", "add", ..] |
+        ["r", "fav", "add", ..] => {
+            let err_msg = format!("{}", "Usage: /room favorite add <room1> <room2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["room", "favorite", "remove", rooms @ ..] |
+        ["room", "fav", "remove", rooms @ ..] |
+        ["r", "favorite", "remove", rooms @ ..] |
+        ["r", "fav", "remove", rooms @ ..] if !rooms.is_empty() => Command::RoomFavoriteRemove {
+            rooms: rooms.join(" ")
+        },
+
+        ["room", "favorite", "remove", ..] |
+        ["room", "fav", "remove", ..] |
+        ["r", "favorite", "remove", ..] |
+        ["r", "fav", "remove", ..] => {
+            let err_msg = format!("{}", "Usage: /room favorite remove <room1> <room2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["room", "favorite", ..] |
+        ["room", "fav", ..] |
+        ["r", "favorite", ..] |
+        ["r", "fav", ..] => {
+            let err_msg = format!("{}", "Favorite commands:\n> /room favorite list\n> /room favorite add <room1> <room2> ...\n> /room favorite remove <room1> <room2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["room", "history", count] |
+        ["r", "history", count] |
+        ["room", "h", count] |
+        ["r", "h", count] => {
+            match count.parse::<usize>() {
+                Ok(c) if c > 0 => Command::RoomHistory { count: c },
+                _ => {
+                    let err_msg = format!("{}", "Usage: /room history <count>".bright_blue());
+                    Command::InvalidSyntax { err_msg }
+                }
+            }
+        },
+
+        ["room", "history", ..] |
+        ["r", "history", ..] |
+        ["room", "h", ..] |
+        ["r", "h", ..] => {
+            let err_msg = format!("{}", "Usage: /room history <count>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["room", "members"] |
+        ["r", "members"] |
+        ["room", "m"] |
+        ["r", "m"] => Command::RoomMembers,
+
+        ["room", "members", ..] |
+        ["r", "members", ..] |
+        ["room", "m", ..] |
+        ["r", "m", ..] => {
+            let err_msg = format!("{}", "Usage: /room members".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["room", "transfer", username] |
+        ["r", "transfer", username] |
+        ["room", "t", username] |
+        ["r", "t", username] => Command::RoomTransfer {
+            username: username.to_string()
+        },
+
+        ["room", "transfer", ..] |
+        ["r", "transfer", ..] |
+        ["room", "t", ..] |
+        ["r", "t", ..] => {
+            let err_msg = format!("{}", "Usage: /room transfer <username>".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
         ["room", ..] |
         ["r", ..] => {
-            let err_msg = format!("{}", "Room commands:\n> /room list\n> /room create <room name> whitelist?\n> /room join <room name>\n> /room import <filename>\n> /room delete force? <room name>".bright_blue());
+            let err_msg = format!("{}", "Room commands:\n> /room list busy? <tag>?\n> /room search <query>\n> /room create <room name> whitelist?\n> /room join <room name>\n> /room import <filename>\n> /room delete force? <room name> dry?\n> /room history <count>\n> /room members\n> /room transfer <username>\n> /room favorite list|add|remove".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "info"] |
+        ["s", "info"] |
+        ["super", "i"] |
+        ["s", "i"] => Command::SuperInfo,
+
+        ["super", "info", ..] |
+        ["s", "info", ..] |
+        ["super", "i", ..] |
+        ["s", "i", ..] => {
+            let err_msg = format!("{}", "Usage: /super info".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
         ["super", "users"] |
         ["s", "users"] |
         ["super", "u"] |
-        ["s", "u"] => Command::SuperUsers,
+        ["s", "u"] => Command::SuperUsers { filter: None },
+
+        ["super", "users", filter] |
+        ["s", "users", filter] |
+        ["super", "u", filter] |
+        ["s", "u", filter] if matches!(filter.to_lowercase().as_str(), "afk" | "hidden" | "muted" | "banned") => Command::SuperUsers {
+            filter: Some(filter.to_lowercase())
+        },
 
         ["super", "users", ..] |
         ["s", "users", ..] |
         ["super", "u", ..] |
         ["s", "u", ..] => {
-            let err_msg = format!("{}", "Usage: /super users".bright_blue());
+            let err_msg = format!("{}", "Usage: /super users afk|hidden|muted|banned?".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -533,30 +1020,248 @@ pub fn parse_command(input: &str) -> Command {
         ["s", "rename", name] |
         ["super", "rn", name] |
         ["s", "rn", name] => Command::SuperRename {
-            name: name.to_string()
+            name: name.to_string(),
+            force: false
+        },
+
+        ["super", "rename", name, "force"] |
+        ["s", "rename", name, "force"] |
+        ["super", "rn", name, "force"] |
+        ["s", "rn", name, "force"] |
+        ["super", "rename", name, "f"] |
+        ["s", "rename", name, "f"] |
+        ["super", "rn", name, "f"] |
+        ["s", "rn", name, "f"] => Command::SuperRename {
+            name: name.to_string(),
+            force: true
         },
 
         ["super", "rename", ..] |
         ["s", "rename", ..] |
         ["super", "rn", ..] |
         ["s", "rn", ..] => {
-            let err_msg = format!("{}", "Usage: /super rename <new room name>".bright_blue());
+            let err_msg = format!("{}", "Usage: /super rename <new room name> force?".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "lock"] |
+        ["s", "lock"] => Command::SuperLock,
+
+        ["super", "lock", ..] |
+        ["s", "lock", ..] => {
+            let err_msg = format!("{}", "Usage: /super lock".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "staffalerts"] |
+        ["s", "staffalerts"] => Command::SuperStaffAlerts,
+
+        ["super", "motd"] |
+        ["s", "motd"] => Command::SuperMotd { text: None },
+
+        ["super", "motd", text @ ..] |
+        ["s", "motd", text @ ..] => Command::SuperMotd {
+            text: Some(text.join(" "))
+        },
+
+        ["super", "policy", "banreason", state @ ("on" | "off")] |
+        ["s", "policy", "banreason", state @ ("on" | "off")] => Command::SuperPolicyBanReason {
+            enabled: *state == "on"
+        },
+
+        ["super", "policy", ..] |
+        ["s", "policy", ..] => {
+            let err_msg = format!("{}", "Super policy commands:\n> /super policy banreason <on|off>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "staffalerts", ..] |
+        ["s", "staffalerts", ..] => {
+            let err_msg = format!("{}", "Usage: /super staffalerts".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "archive"] |
+        ["s", "archive"] => Command::SuperArchive,
+
+        ["super", "archive", ..] |
+        ["s", "archive", ..] => {
+            let err_msg = format!("{}", "Usage: /super archive".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "freeze"] |
+        ["s", "freeze"] => Command::SuperFreeze,
+
+        ["super", "freeze", ..] |
+        ["s", "freeze", ..] => {
+            let err_msg = format!("{}", "Usage: /super freeze".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "log"] |
+        ["s", "log"] => Command::SuperLog {
+            count: AUDIT_LOG_DEFAULT_ENTRIES
+        },
+
+        ["super", "log", count] |
+        ["s", "log", count] => {
+            match count.parse::<usize>() {
+                Ok(n) if n > 0 => Command::SuperLog { count: n.min(AUDIT_LOG_MAX_ENTRIES) },
+                _ => {
+                    let err_msg = format!("{}", format!("Usage: /super log <count (1-{AUDIT_LOG_MAX_ENTRIES})>?").bright_blue());
+                    Command::InvalidSyntax { err_msg }
+                }
+            }
+        },
+
+        ["super", "log", ..] |
+        ["s", "log", ..] => {
+            let err_msg = format!("{}", format!("Usage: /super log <count (1-{AUDIT_LOG_MAX_ENTRIES})>?").bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "slowmode", secs] |
+        ["s", "slowmode", secs] |
+        ["super", "sm", secs] |
+        ["s", "sm", secs] => {
+            if *secs == "*" {
+                Command::SuperSlowmode { secs: 0 }
+            }
+            else {
+                match secs.parse::<u32>() {
+                    Ok(n) if n > 0 => Command::SuperSlowmode { secs: n },
+                    _ => {
+                        let err_msg = format!("{}", "Usage: /super slowmode <secs (1-4294967295) | *>".bright_blue());
+                        Command::InvalidSyntax { err_msg }
+                    }
+                }
+            }
+        },
+
+        ["super", "slowmode", ..] |
+        ["s", "slowmode", ..] |
+        ["super", "sm", ..] |
+        ["s", "sm", ..] => {
+            let err_msg = format!("{}", "Usage: /super slowmode <secs (1-4294967295) | *>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "kickall", reason @ .., "dry"] |
+        ["s", "kickall", reason @ .., "dry"] => Command::SuperKickAll {
+            reason: reason.join(" "),
+            dry: true
+        },
+
+        ["super", "kickall", reason @ ..] |
+        ["s", "kickall", reason @ ..] => Command::SuperKickAll {
+            reason: reason.join(" "),
+            dry: false
+        },
+
+        ["super", "kick-role", role] |
+        ["s", "kick-role", role] |
+        ["super", "kr", role] |
+        ["s", "kr", role] => Command::SuperKickRole {
+            role: role.to_string()
+        },
+
+        ["super", "kick-role", ..] |
+        ["s", "kick-role", ..] |
+        ["super", "kr", ..] |
+        ["s", "kr", ..] => {
+            let err_msg = format!("{}", "Usage: /super kick-role <user|mod|admin>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "run", filename] |
+        ["s", "run", filename] => Command::SuperRun {
+            filename: filename.to_string()
+        },
+
+        ["super", "run", ..] |
+        ["s", "run", ..] => {
+            let err_msg = format!("{}", "Usage: /super run <filename>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "handoff", username] |
+        ["s", "handoff", username] => Command::SuperHandoff {
+            username: username.to_string()
+        },
+
+        ["super", "handoff", ..] |
+        ["s", "handoff", ..] => {
+            let err_msg = format!("{}", "Usage: /super handoff <username>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "import-profile", target, source] |
+        ["s", "import-profile", target, source] |
+        ["super", "ip", target, source] |
+        ["s", "ip", target, source] => Command::SuperImportProfile {
+            target: target.to_string(),
+            source: source.to_string()
+        },
+
+        ["super", "import-profile", ..] |
+        ["s", "import-profile", ..] |
+        ["super", "ip", ..] |
+        ["s", "ip", ..] => {
+            let err_msg = format!("{}", "Usage: /super import-profile <username> <source room>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "purge", days] |
+        ["s", "purge", days] |
+        ["super", "purge", days, "dry"] |
+        ["s", "purge", days, "dry"] => {
+            let dry = tokens.len() == 4;
+            match days.parse::<u64>() {
+                Ok(d) if d > 0 => Command::SuperPurge { days: d, dry },
+                _ => {
+                    let err_msg = format!("{}", "Usage: /super purge <days> dry?".bright_blue());
+                    Command::InvalidSyntax { err_msg }
+                }
+            }
+        },
+
+        ["super", "purge", ..] |
+        ["s", "purge", ..] => {
+            let err_msg = format!("{}", "Usage: /super purge <days> dry?".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
         ["super", "export"] |
         ["s", "export"] => Command::SuperExport {
-            filename: "".to_string()
+            filename: "".to_string(),
+            section: None
+        },
+
+        ["super", "export", "all"] |
+        ["s", "export", "all"] => Command::SuperExportAll,
+
+        ["super", "export", section @ ("roles" | "config")] |
+        ["s", "export", section @ ("roles" | "config")] => Command::SuperExport {
+            filename: "".to_string(),
+            section: Some(section.to_string())
+        },
+
+        ["super", "export", section @ ("roles" | "config"), filename] |
+        ["s", "export", section @ ("roles" | "config"), filename] => Command::SuperExport {
+            filename: filename.to_string(),
+            section: Some(section.to_string())
         },
 
         ["super", "export", filename] |
         ["s", "export", filename] => Command::SuperExport {
-            filename: filename.to_string()
+            filename: filename.to_string(),
+            section: None
         },
 
         ["super", "export", ..] |
         ["s", "export", ..] => {
-            let err_msg = format!("{}", "Usage: /super export <filename>?".bright_blue());
+            let err_msg = format!("{}", "Usage: /super export roles|config? <filename>?\n> /super export all".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -582,6 +1287,15 @@ pub fn parse_command(input: &str) -> Command {
         ["s", "whitelist", "t"] |
         ["s", "wl", "t"] => Command::SuperWhitelistToggle,
 
+        ["super", "whitelist", "enforce"] |
+        ["super", "wl", "enforce"] |
+        ["s", "whitelist", "enforce"] |
+        ["s", "wl", "enforce"] |
+        ["super", "whitelist", "e"] |
+        ["super", "wl", "e"] |
+        ["s", "whitelist", "e"] |
+        ["s", "wl", "e"] => Command::SuperWhitelistEnforce,
+
         ["super", "whitelist", "add", users @ ..] |
         ["super", "wl", "add", users @ ..] |
         ["s", "whitelist", "add", users @ ..] |
@@ -628,11 +1342,26 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["super", "whitelist", "import", filename] |
+        ["super", "wl", "import", filename] |
+        ["s", "whitelist", "import", filename] |
+        ["s", "wl", "import", filename] => Command::SuperWhitelistImport {
+            filename: filename.to_string(),
+        },
+
+        ["super", "whitelist", "import", ..] |
+        ["super", "wl", "import", ..] |
+        ["s", "whitelist", "import", ..] |
+        ["s", "wl", "import", ..] => {
+            let err_msg = format!("{}", "Usage: /super whitelist import <filename>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["super", "whitelist", ..] |
         ["super", "wl", ..] |
         ["s", "whitelist", ..] |
         ["s", "wl", ..] => {
-            let err_msg = format!("{}", "Super whitelist commands:\n> /super whitelist info\n> /super whitelist toggle\n> /super whitelist add <user1> <user2> ...\n> /super whitelist remove <user1> <user2> ...".bright_blue());
+            let err_msg = format!("{}", "Super whitelist commands:\n> /super whitelist info\n> /super whitelist toggle\n> /super whitelist enforce\n> /super whitelist add <user1> <user2> ...\n> /super whitelist remove <user1> <user2> ...\n> /super whitelist import <filename>".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -805,6 +1534,30 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["super", "roles", "preview", role, commands @ ..] |
+        ["super", "r", "preview", role, commands @ ..] |
+        ["s", "roles", "preview", role, commands @ ..] |
+        ["s", "r", "preview", role, commands @ ..] |
+        ["super", "roles", "p", role, commands @ ..] |
+        ["super", "r", "p", role, commands @ ..] |
+        ["s", "roles", "p", role, commands @ ..] |
+        ["s", "r", "p", role, commands @ ..] if !commands.is_empty() => Command::SuperRolesPreview {
+            role: role.to_string(),
+            commands: commands.join(" ")
+        },
+
+        ["super", "roles", "preview", ..] |
+        ["super", "r", "preview", ..] |
+        ["s", "roles", "preview", ..] |
+        ["s", "r", "preview", ..] |
+        ["super", "roles", "p", ..] |
+        ["super", "r", "p", ..] |
+        ["s", "roles", "p", ..] |
+        ["s", "r", "p", ..] => {
+            let err_msg = format!("{}", "Usage: /super roles preview <user|mod> <command1> <command2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["super", "roles", "assign", role, users @ ..] |
         ["super", "r", "assign", role, users @ ..] |
         ["s", "roles", "assign", role, users @ ..] |
@@ -812,9 +1565,27 @@ pub fn parse_command(input: &str) -> Command {
         ["super", "roles", "as", role, users @ ..] |
         ["super", "r", "as", role, users @ ..] |
         ["s", "roles", "as", role, users @ ..] |
-        ["s", "r", "as", role, users @ ..] if !users.is_empty() => Command::SuperRolesAssign {
-            role: role.to_string(),
-            users: users.join(" ")
+        ["s", "r", "as", role, users @ ..] if !users.is_empty() => {
+            match users.split_last() {
+                Some((last, rest)) if !rest.is_empty() && *last != "*" && duration_format_passes(last) => {
+                    match parse_duration(last) {
+                        Ok(secs) => Command::SuperRolesAssign {
+                            role: role.to_string(),
+                            users: rest.join(" "),
+                            duration: secs
+                        },
+                        Err(_) => {
+                            let err_msg = format!("{}", "Usage: /super roles assign <user|mod|admin|owner> <user1> <user2> ... <_d_h_m_s>?".bright_blue());
+                            Command::InvalidSyntax { err_msg }
+                        }
+                    }
+                }
+                _ => Command::SuperRolesAssign {
+                    role: role.to_string(),
+                    users: users.join(" "),
+                    duration: 0
+                }
+            }
         },
 
         ["super", "roles", "assign", ..] |
@@ -825,7 +1596,7 @@ pub fn parse_command(input: &str) -> Command {
         ["super", "r", "as", ..] |
         ["s", "roles", "as", ..] |
         ["s", "r", "as", ..] => {
-            let err_msg = format!("{}", "Usage: /super roles assign <user|mod|admin|owner> <user1> <user2> ...".bright_blue());
+            let err_msg = format!("{}", "Usage: /super roles assign <user|mod|admin|owner> <user1> <user2> ... <_d_h_m_s>?".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -853,17 +1624,380 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["super", "roles", "reset"] |
+        ["super", "r", "reset"] |
+        ["s", "roles", "reset"] |
+        ["s", "r", "reset"] |
+        ["super", "roles", "rs"] |
+        ["super", "r", "rs"] |
+        ["s", "roles", "rs"] |
+        ["s", "r", "rs"] => Command::SuperRolesReset,
+
+        ["super", "roles", "reset", ..] |
+        ["super", "r", "reset", ..] |
+        ["s", "roles", "reset", ..] |
+        ["s", "r", "reset", ..] |
+        ["super", "roles", "rs", ..] |
+        ["super", "r", "rs", ..] |
+        ["s", "roles", "rs", ..] |
+        ["s", "r", "rs", ..] => {
+            let err_msg = format!("{}", "Usage: /super roles reset".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "roles", "copy", source] |
+        ["super", "r", "copy", source] |
+        ["s", "roles", "copy", source] |
+        ["s", "r", "copy", source] |
+        ["super", "roles", "cp", source] |
+        ["super", "r", "cp", source] |
+        ["s", "roles", "cp", source] |
+        ["s", "r", "cp", source] => Command::SuperRolesCopy {
+            source: source.to_string(),
+            colors: false
+        },
+
+        ["super", "roles", "copy", source, "colors"] |
+        ["super", "r", "copy", source, "colors"] |
+        ["s", "roles", "copy", source, "colors"] |
+        ["s", "r", "copy", source, "colors"] |
+        ["super", "roles", "cp", source, "colors"] |
+        ["super", "r", "cp", source, "colors"] |
+        ["s", "roles", "cp", source, "colors"] |
+        ["s", "r", "cp", source, "colors"] => Command::SuperRolesCopy {
+            source: source.to_string(),
+            colors: true
+        },
+
+        ["super", "roles", "copy", ..] |
+        ["super", "r", "copy", ..] |
+        ["s", "roles", "copy", ..] |
+        ["s", "r", "copy", ..] |
+        ["super", "roles", "cp", ..] |
+        ["super", "r", "cp", ..] |
+        ["s", "roles", "cp", ..] |
+        ["s", "r", "cp", ..] => {
+            let err_msg = format!("{}", "Usage: /super roles copy <source room> colors?".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "roles", "export", filename] |
+        ["super", "r", "export", filename] |
+        ["s", "roles", "export", filename] |
+        ["s", "r", "export", filename] => Command::SuperRolesExport {
+            filename: filename.to_string()
+        },
+
+        ["super", "roles", "export", ..] |
+        ["super", "r", "export", ..] |
+        ["s", "roles", "export", ..] |
+        ["s", "r", "export", ..] => {
+            let err_msg = format!("{}", "Usage: /super roles export <filename>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "roles", "import", filename] |
+        ["super", "r", "import", filename] |
+        ["s", "roles", "import", filename] |
+        ["s", "r", "import", filename] => Command::SuperRolesImport {
+            filename: filename.to_string()
+        },
+
+        ["super", "roles", "import", ..] |
+        ["super", "r", "import", ..] |
+        ["s", "roles", "import", ..] |
+        ["s", "r", "import", ..] => {
+            let err_msg = format!("{}", "Usage: /super roles import <filename>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "roles", "label", role, label @ ..] |
+        ["super", "r", "label", role, label @ ..] |
+        ["s", "roles", "label", role, label @ ..] |
+        ["s", "r", "label", role, label @ ..] |
+        ["super", "roles", "lb", role, label @ ..] |
+        ["super", "r", "lb", role, label @ ..] |
+        ["s", "roles", "lb", role, label @ ..] |
+        ["s", "r", "lb", role, label @ ..] if !label.is_empty() => Command::SuperRolesLabel {
+            role: role.to_string(),
+            label: label.join(" ")
+        },
+
+        ["super", "roles", "label", ..] |
+        ["super", "r", "label", ..] |
+        ["s", "roles", "label", ..] |
+        ["s", "r", "label", ..] |
+        ["super", "roles", "lb", ..] |
+        ["super", "r", "lb", ..] |
+        ["s", "roles", "lb", ..] |
+        ["s", "r", "lb", ..] => {
+            let err_msg = format!("{}", "Usage: /super roles label <user|mod|admin|owner> <display name>|*".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["super", "roles", ..] |
         ["super", "r", ..] |
         ["s", "roles", ..] |
         ["s", "r", ..] => {
-            let err_msg = format!("{}", "Super roles commands:\n> /super roles list\n> /super roles add <user|mod> <command1> <command2> ...\n> /super roles revoke <user|mod> <command1> <command2> ...\n> /super roles assign <user|mod|admin|owner> <user1> <user2> ...\n> /super roles recolor <user|mod|admin|owner> <color>".bright_blue());
+            let err_msg = format!("{}", "Super roles commands:\n> /super roles list\n> /super roles add <user|mod> <command1> <command2> ...\n> /super roles revoke <user|mod> <command1> <command2> ...\n> /super roles preview <user|mod> <command1> <command2> ...\n> /super roles assign <user|mod|admin|owner> <user1> <user2> ... <_d_h_m_s>?\n> /super roles recolor <user|mod|admin|owner> <color>\n> /super roles label <user|mod|admin|owner> <display name>|*\n> /super roles copy <source room> colors?\n> /super roles export <filename>\n> /super roles import <filename>\n> /super roles reset".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "tags", "add", tags @ ..] |
+        ["super", "t", "add", tags @ ..] |
+        ["s", "tags", "add", tags @ ..] |
+        ["s", "t", "add", tags @ ..] |
+        ["super", "tags", "a", tags @ ..] |
+        ["super", "t", "a", tags @ ..] |
+        ["s", "tags", "a", tags @ ..] |
+        ["s", "t", "a", tags @ ..] if !tags.is_empty() => Command::SuperTagsAdd {
+            tags: tags.join(" ")
+        },
+
+        ["super", "tags", "add", ..] |
+        ["super", "t", "add", ..] |
+        ["s", "tags", "add", ..] |
+        ["s", "t", "add", ..] |
+        ["super", "tags", "a", ..] |
+        ["super", "t", "a", ..] |
+        ["s", "tags", "a", ..] |
+        ["s", "t", "a", ..] => {
+            let err_msg = format!("{}", "Usage: /super tags add <tag1> <tag2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "tags", "remove", tags @ ..] |
+        ["super", "t", "remove", tags @ ..] |
+        ["s", "tags", "remove", tags @ ..] |
+        ["s", "t", "remove", tags @ ..] |
+        ["super", "tags", "r", tags @ ..] |
+        ["super", "t", "r", tags @ ..] |
+        ["s", "tags", "r", tags @ ..] |
+        ["s", "t", "r", tags @ ..] if !tags.is_empty() => Command::SuperTagsRemove {
+            tags: tags.join(" ")
+        },
+
+        ["super", "tags", "remove", ..] |
+        ["super", "t", "remove", ..] |
+        ["s", "tags", "remove", ..] |
+        ["s", "t", "remove", ..] |
+        ["super", "tags", "r", ..] |
+        ["super", "t", "r", ..] |
+        ["s", "tags", "r", ..] |
+        ["s", "t", "r", ..] => {
+            let err_msg = format!("{}", "Usage: /super tags remove <tag1> <tag2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "tags", ..] |
+        ["super", "t", ..] |
+        ["s", "tags", ..] |
+        ["s", "t", ..] => {
+            let err_msg = format!("{}", "Super tags commands:\n> /super tags add <tag1> <tag2> ...\n> /super tags remove <tag1> <tag2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "filter", "list"] |
+        ["super", "f", "list"] |
+        ["s", "filter", "list"] |
+        ["s", "f", "list"] |
+        ["super", "filter", "l"] |
+        ["super", "f", "l"] |
+        ["s", "filter", "l"] |
+        ["s", "f", "l"] => Command::SuperFilterList,
+
+        ["super", "filter", "list", ..] |
+        ["super", "f", "list", ..] |
+        ["s", "filter", "list", ..] |
+        ["s", "f", "list", ..] |
+        ["super", "filter", "l", ..] |
+        ["super", "f", "l", ..] |
+        ["s", "filter", "l", ..] |
+        ["s", "f", "l", ..] => {
+            let err_msg = format!("{}", "Usage: /super filter list".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "filter", "toggle"] |
+        ["super", "f", "toggle"] |
+        ["s", "filter", "toggle"] |
+        ["s", "f", "toggle"] |
+        ["super", "filter", "t"] |
+        ["super", "f", "t"] |
+        ["s", "filter", "t"] |
+        ["s", "f", "t"] => Command::SuperFilterToggle,
+
+        ["super", "filter", "toggle", ..] |
+        ["super", "f", "toggle", ..] |
+        ["s", "filter", "toggle", ..] |
+        ["s", "f", "toggle", ..] |
+        ["super", "filter", "t", ..] |
+        ["super", "f", "t", ..] |
+        ["s", "filter", "t", ..] |
+        ["s", "f", "t", ..] => {
+            let err_msg = format!("{}", "Usage: /super filter toggle".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "filter", "add", words @ ..] |
+        ["super", "f", "add", words @ ..] |
+        ["s", "filter", "add", words @ ..] |
+        ["s", "f", "add", words @ ..] |
+        ["super", "filter", "a", words @ ..] |
+        ["super", "f", "a", words @ ..] |
+        ["s", "filter", "a", words @ ..] |
+        ["s", "f", "a", words @ ..] if !words.is_empty() => Command::SuperFilterAdd {
+            words: words.join(" ")
+        },
+
+        ["super", "filter", "add", ..] |
+        ["super", "f", "add", ..] |
+        ["s", "filter", "add", ..] |
+        ["s", "f", "add", ..] |
+        ["super", "filter", "a", ..] |
+        ["super", "f", "a", ..] |
+        ["s", "filter", "a", ..] |
+        ["s", "f", "a", ..] => {
+            let err_msg = format!("{}", "Usage: /super filter add <word1> <word2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "filter", "remove", words @ ..] |
+        ["super", "f", "remove", words @ ..] |
+        ["s", "filter", "remove", words @ ..] |
+        ["s", "f", "remove", words @ ..] |
+        ["super", "filter", "r", words @ ..] |
+        ["super", "f", "r", words @ ..] |
+        ["s", "filter", "r", words @ ..] |
+        ["s", "f", "r", words @ ..] if !words.is_empty() => Command::SuperFilterRemove {
+            words: words.join(" ")
+        },
+
+        ["super", "filter", "remove", ..] |
+        ["super", "f", "remove", ..] |
+        ["s", "filter", "remove", ..] |
+        ["s", "f", "remove", ..] |
+        ["super", "filter", "r", ..] |
+        ["super", "f", "r", ..] |
+        ["s", "filter", "r", ..] |
+        ["s", "f", "r", ..] => {
+            let err_msg = format!("{}", "Usage: /super filter remove <word1> <word2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "filter", ..] |
+        ["super", "f", ..] |
+        ["s", "filter", ..] |
+        ["s", "f", ..] => {
+            let err_msg = format!("{}", "Super filter commands:\n> /super filter list\n> /super filter toggle\n> /super filter add <word1> <word2> ...\n> /super filter remove <word1> <word2> ...".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "pin", "list"] |
+        ["super", "p", "list"] |
+        ["s", "pin", "list"] |
+        ["s", "p", "list"] |
+        ["super", "pin", "l"] |
+        ["super", "p", "l"] |
+        ["s", "pin", "l"] |
+        ["s", "p", "l"] => Command::SuperPinList,
+
+        ["super", "pin", "list", ..] |
+        ["super", "p", "list", ..] |
+        ["s", "pin", "list", ..] |
+        ["s", "p", "list", ..] |
+        ["super", "pin", "l", ..] |
+        ["super", "p", "l", ..] |
+        ["s", "pin", "l", ..] |
+        ["s", "p", "l", ..] => {
+            let err_msg = format!("{}", "Usage: /super pin list".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "pin", "add", text @ ..] |
+        ["super", "p", "add", text @ ..] |
+        ["s", "pin", "add", text @ ..] |
+        ["s", "p", "add", text @ ..] |
+        ["super", "pin", "a", text @ ..] |
+        ["super", "p", "a", text @ ..] |
+        ["s", "pin", "a", text @ ..] |
+        ["s", "p", "a", text @ ..] if !text.is_empty() => Command::SuperPinAdd {
+            text: text.join(" ")
+        },
+
+        ["super", "pin", "add", ..] |
+        ["super", "p", "add", ..] |
+        ["s", "pin", "add", ..] |
+        ["s", "p", "add", ..] |
+        ["super", "pin", "a", ..] |
+        ["super", "p", "a", ..] |
+        ["s", "pin", "a", ..] |
+        ["s", "p", "a", ..] => {
+            let err_msg = format!("{}", "Usage: /super pin add <text>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "pin", "remove", index] |
+        ["super", "p", "remove", index] |
+        ["s", "pin", "remove", index] |
+        ["s", "p", "remove", index] |
+        ["super", "pin", "r", index] |
+        ["super", "p", "r", index] |
+        ["s", "pin", "r", index] |
+        ["s", "p", "r", index] => {
+            match index.parse::<usize>() {
+                Ok(i) if i > 0 => Command::SuperPinRemove { index: i },
+                _ => {
+                    let err_msg = format!("{}", "Usage: /super pin remove <number>".bright_blue());
+                    Command::InvalidSyntax { err_msg }
+                }
+            }
+        },
+
+        ["super", "pin", "remove", ..] |
+        ["super", "p", "remove", ..] |
+        ["s", "pin", "remove", ..] |
+        ["s", "p", "remove", ..] |
+        ["super", "pin", "r", ..] |
+        ["super", "p", "r", ..] |
+        ["s", "pin", "r", ..] |
+        ["s", "p", "r", ..] => {
+            let err_msg = format!("{}", "Usage: /super pin remove <number>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "pin", "clear"] |
+        ["super", "p", "clear"] |
+        ["s", "pin", "clear"] |
+        ["s", "p", "clear"] |
+        ["super", "pin", "c"] |
+        ["super", "p", "c"] |
+        ["s", "pin", "c"] |
+        ["s", "p", "c"] => Command::SuperPinClear,
+
+        ["super", "pin", "clear", ..] |
+        ["super", "p", "clear", ..] |
+        ["s", "pin", "clear", ..] |
+        ["s", "p", "clear", ..] |
+        ["super", "pin", "c", ..] |
+        ["super", "p", "c", ..] |
+        ["s", "pin", "c", ..] |
+        ["s", "p", "c", ..] => {
+            let err_msg = format!("{}", "Usage: /super pin clear".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["super", "pin", ..] |
+        ["super", "p", ..] |
+        ["s", "pin", ..] |
+        ["s", "p", ..] => {
+            let err_msg = format!("{}", "Super pin commands:\n> /super pin list\n> /super pin add <text>\n> /super pin remove <number>\n> /super pin clear".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
         ["super", ..] |
         ["s", ..] => {
-            let err_msg = format!("{}", "Super commands:\n> /super users\n> /super rename <new room name>\n> /super export <filename>?\n> /super whitelist\n> /super limit\n> /super roles".bright_blue());
+            let err_msg = format!("{}", "Super commands:\n> /super info\n> /super users afk|hidden|muted|banned?\n> /super rename <new room name> force?\n> /super lock\n> /super staffalerts\n> /super policy banreason <on|off>\n> /super motd <text|*>?\n> /super archive\n> /super freeze\n> /super log <count>?\n> /super slowmode <secs|*>\n> /super purge <days> dry?\n> /super kickall <reason>? dry?\n> /super kick-role <user|mod|admin>\n> /super run <filename>\n> /super handoff <username>\n> /super import-profile <username> <source room>\n> /super export roles|config? <filename>?|all\n> /super whitelist\n> /super limit\n> /super roles\n> /super tags\n> /super filter\n> /super pin".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -880,6 +2014,32 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         }
 
+        ["user", "whoami"] |
+        ["u", "whoami"] |
+        ["user", "w"] |
+        ["u", "w"] => Command::UsersWhoami,
+
+        ["user", "whoami", ..] |
+        ["u", "whoami", ..] |
+        ["user", "w", ..] |
+        ["u", "w", ..] => {
+            let err_msg = format!("{}", "Usage: /user whoami".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        }
+
+        ["user", "count"] |
+        ["u", "count"] |
+        ["user", "c"] |
+        ["u", "c"] => Command::UsersCount,
+
+        ["user", "count", ..] |
+        ["u", "count", ..] |
+        ["user", "c", ..] |
+        ["u", "c", ..] => {
+            let err_msg = format!("{}", "Usage: /user count".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        }
+
         ["user", "rename", name] |
         ["u", "rename", name] |
         ["user", "rn", name] |
@@ -895,6 +2055,29 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["user", "recolor", "preview", color] |
+        ["u", "recolor", "preview", color] |
+        ["user", "rc", "preview", color] |
+        ["u", "rc", "preview", color] |
+        ["user", "recolor", "p", color] |
+        ["u", "recolor", "p", color] |
+        ["user", "rc", "p", color] |
+        ["u", "rc", "p", color] => Command::UsersRecolorPreview {
+            color: color.to_string()
+        },
+
+        ["user", "recolor", "preview", ..] |
+        ["u", "recolor", "preview", ..] |
+        ["user", "rc", "preview", ..] |
+        ["u", "rc", "preview", ..] |
+        ["user", "recolor", "p", ..] |
+        ["u", "recolor", "p", ..] |
+        ["user", "rc", "p", ..] |
+        ["u", "rc", "p", ..] => {
+            let err_msg = format!("{}", "Usage: /user recolor preview <color hex>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["user", "recolor", color] |
         ["u", "recolor", color] |
         ["user", "rc", color] |
@@ -910,6 +2093,22 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
+        ["user", "profile", nick, color] |
+        ["u", "profile", nick, color] |
+        ["user", "p", nick, color] |
+        ["u", "p", nick, color] => Command::UsersProfile {
+            nick: nick.to_string(),
+            color: color.to_string()
+        },
+
+        ["user", "profile", ..] |
+        ["u", "profile", ..] |
+        ["user", "p", ..] |
+        ["u", "p", ..] => {
+            let err_msg = format!("{}", "Usage: /user profile <new name|*> <color hex|*>".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
         ["user", "hide"] |
         ["u", "hide"] |
         ["user", "h"] |
@@ -923,20 +2122,33 @@ pub fn parse_command(input: &str) -> Command {
 
         ["user", ..] |
         ["u", ..] => {
-            let err_msg = format!("{}", "User commands:\n> /user list\n> /user rename <new name|*>\n> /user recolor <color hex|*>\n> /user hide".bright_blue());
+            let err_msg = format!("{}", "User commands:\n> /user list\n> /user count\n> /user rename <new name|*>\n> /user recolor <color hex|*>\n> /user recolor preview <color hex>\n> /user profile <new name|*> <color hex|*>\n> /user hide\n> /user whoami".bright_blue());
             Command::InvalidSyntax { err_msg }
         }
 
         ["mod", "info"] |
         ["m", "info"] |
         ["mod", "i"] |
-        ["m", "i"] => Command::ModInfo,
+        ["m", "i"] => Command::ModInfo { page: 1 },
+
+        ["mod", "info", page] |
+        ["m", "info", page] |
+        ["mod", "i", page] |
+        ["m", "i", page] => {
+            match page.parse::<usize>() {
+                Ok(p) if p > 0 => Command::ModInfo { page: p },
+                _ => {
+                    let err_msg = format!("{}", "Usage: /mod info <page>?".bright_blue());
+                    Command::InvalidSyntax { err_msg }
+                }
+            }
+        },
 
         ["mod", "info", ..] |
         ["m", "info", ..] |
         ["mod", "i", ..] |
         ["m", "i", ..] => {
-            let err_msg = format!("{}", "Usage: /mod info".bright_blue());
+            let err_msg = format!("{}", "Usage: /mod info <page>?".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -999,18 +2211,18 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
-        ["mod", "unban", username] |
-        ["m", "unban", username] |
-        ["mod", "ub", username] |
-        ["m", "ub", username] => Command::ModUnban {
-            username: username.to_string()
+        ["mod", "unban", users @ ..] |
+        ["m", "unban", users @ ..] |
+        ["mod", "ub", users @ ..] |
+        ["m", "ub", users @ ..] if !users.is_empty() => Command::ModUnban {
+            users: users.join(" ")
         },
 
         ["mod", "unban", ..] |
         ["m", "unban", ..] |
         ["mod", "ub", ..] |
         ["m", "ub", ..] => {
-            let err_msg = format!("{}", "Usage: /mod unban <username>".bright_blue());
+            let err_msg = format!("{}", "Usage: /mod unban <user1> <user2> ...|all".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
@@ -1049,24 +2261,61 @@ pub fn parse_command(input: &str) -> Command {
             Command::InvalidSyntax { err_msg }
         },
 
-        ["mod", "unmute", username] |
-        ["m", "unmute", username] |
-        ["mod", "um", username] |
-        ["m", "um", username] => Command::ModUnmute {
-            username: username.to_string()
+        ["mod", "unmute", users @ ..] |
+        ["m", "unmute", users @ ..] |
+        ["mod", "um", users @ ..] |
+        ["m", "um", users @ ..] if !users.is_empty() => Command::ModUnmute {
+            users: users.join(" ")
         },
 
         ["mod", "unmute", ..] |
         ["m", "unmute", ..] |
         ["mod", "um", ..] |
         ["m", "um", ..] => {
-            let err_msg = format!("{}", "Usage: /mod unmute <username>".bright_blue());
+            let err_msg = format!("{}", "Usage: /mod unmute <user1> <user2> ...|all".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["mod", "export"] |
+        ["m", "export"] |
+        ["mod", "e"] |
+        ["m", "e"] => Command::ModExport {
+            filename: "".to_string()
+        },
+
+        ["mod", "export", filename] |
+        ["m", "export", filename] |
+        ["mod", "e", filename] |
+        ["m", "e", filename] => Command::ModExport {
+            filename: filename.to_string()
+        },
+
+        ["mod", "export", ..] |
+        ["m", "export", ..] |
+        ["mod", "e", ..] |
+        ["m", "e", ..] => {
+            let err_msg = format!("{}", "Usage: /mod export <filename>?".bright_blue());
+            Command::InvalidSyntax { err_msg }
+        },
+
+        ["mod", "history", username] |
+        ["m", "history", username] |
+        ["mod", "h", username] |
+        ["m", "h", username] => Command::ModHistory {
+            username: username.to_string()
+        },
+
+        ["mod", "history", ..] |
+        ["m", "history", ..] |
+        ["mod", "h", ..] |
+        ["m", "h", ..] => {
+            let err_msg = format!("{}", "Usage: /mod history <username>".bright_blue());
             Command::InvalidSyntax { err_msg }
         },
 
         ["mod", ..] |
         ["m", ..] => {
-            let err_msg = format!("{}", "Mod commands:\n> /mod info\n> /mod kick <username> <reason>?\n> /mod ban <username> <_d_h_m_s|*>? <reason>?\n> /mod unban <username>\n> /mod mute <username> <_d_h_m_s|*>? <reason>?\n> /mod unmute <username>".bright_blue());
+            let err_msg = format!("{}", "Mod commands:\n> /mod info <page>?\n> /mod kick <username> <reason>?\n> /mod ban <username> <_d_h_m_s|*>? <reason>?\n> /mod unban <user1> <user2> ...|all\n> /mod mute <username> <_d_h_m_s|*>? <reason>?\n> /mod unmute <user1> <user2> ...|all\n> /mod whois <username>\n> /mod history <username>\n> /mod export <filename>?".bright_blue());
             Command::InvalidSyntax { err_msg }
         }
 
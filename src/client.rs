@@ -2,12 +2,12 @@ use std::io::{self, Write};
 use std::net::TcpStream;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::{env, thread};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 mod shared;
 mod frontend;
 
-use crate::shared::crypto::{broadcast_message};
+use crate::shared::crypto::{broadcast_message, broadcast_edit};
 use crate::frontend::app::{App, AppMessage, ClientState, MY_STATE, get_room_members};
 use crate::frontend::ui::ui;
 use crate::frontend::network::handle_recv;
@@ -88,13 +88,28 @@ where
 {
     loop {
         while let Ok(msg) = rx.try_recv() {
-            let text = match msg {
-                AppMessage::ServerMessage(s) => s,
-                AppMessage::NetworkError(s) => format!("⚠ {}", s),
-                AppMessage::ControlResult(s) => s,
+            let (id, text) = match msg {
+                AppMessage::ServerMessage(s) => (None, s),
+                AppMessage::ChatMessage(id, s) => (Some(id), s),
+                AppMessage::EditMessage(id, s) => {
+                    app.edit_message(&id, s);
+                    continue;
+                }
+                AppMessage::NetworkError(s) => (None, format!("⚠ {}", s)),
+                AppMessage::ControlResult(s) => (None, s),
+                AppMessage::Typing(user) => {
+                    app.typing.insert(user, Instant::now());
+                    continue;
+                }
             };
+            if let Some((sender, _)) = id.as_ref().and_then(|id| id.split_once(':')) {
+                app.typing.remove(sender);
+            }
             let was_at_bottom = app.scroll_offset == 0;
-            app.push(text);
+            match id {
+                Some(id) => app.push_chat(id, text),
+                None => app.push(text),
+            }
             app.refresh_member_names();
             app.update_status();
             if !was_at_bottom {
@@ -102,7 +117,8 @@ where
             }
         }
 
-        terminal.draw(|f| ui(f, app))?;
+        let theme = app.theme;
+        terminal.draw(|f| ui(f, app, &theme))?;
 
         if !event::poll(std::time::Duration::from_millis(50))? {
             continue;
@@ -145,24 +161,37 @@ where
 
                     let msg = app.input.trim().to_string();
                     app.input.clear();
-                    app.history_pos = None;
-                    app.input_draft.clear();
+                    app.last_typing_sent = None;
+                    app.record_input(&msg);
 
                     if msg.is_empty() { continue; }
 
-                    if app.input_history.last().map(|s| s.as_str()) != Some(&msg) {
-                        app.input_history.push(msg.clone());
-                    }
-
                     if msg == "/quit" { return Ok(()); }
 
                     if msg.starts_with('/') {
                         if msg == "/clear" || msg == "/c" {
-                            app.messages.clear();
+                            app.clear_messages();
+                            continue;
+                        }
+                        if msg == "/theme" || msg.starts_with("/theme ") {
+                            app.handle_theme_command(&msg["/theme".len()..]);
+                            continue;
+                        }
+                        if msg == "/time" {
+                            app.show_timestamps = !app.show_timestamps;
                             continue;
                         }
+                        if let Some(text) = msg.strip_prefix("/edit ") {
+                            let in_room = matches!(MY_STATE.lock().as_deref(), Ok(ClientState::InRoom));
+                            let members = get_room_members();
+                            if in_room && !members.is_empty() {
+                                let mut stream_clone = stream.try_clone()?;
+                                let _ = broadcast_edit(&mut stream_clone, &members, text.trim());
+                                continue;
+                            }
+                        }
                         if msg == "/ping" {
-                            let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+                            let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
                             stream.write_all(format!("/ping {}\n", now_ms).as_bytes())?;
                             continue;
                         }
@@ -228,6 +257,9 @@ where
 
                 KeyCode::Char(c) => {
                     app.input.push(c);
+                    if app.should_send_typing() {
+                        stream.write_all(b"/typing\n")?;
+                    }
                     app.popup_visible = false;
                     app.popup_candidates.clear();
                     app.popup_selected = 0;
@@ -242,32 +274,14 @@ where
                             app.popup_selected - 1
                         };
                     } else {
-                        let hist_len = app.input_history.len();
-                        if hist_len == 0 { continue; }
-                        let new_pos = match app.history_pos {
-                            None => {
-                                app.input_draft = app.input.clone();
-                                hist_len - 1
-                            }
-                            Some(0) => 0,
-                            Some(p) => p - 1,
-                        };
-                        app.history_pos = Some(new_pos);
-                        app.input = app.input_history[new_pos].clone();
+                        app.history_prev();
                     }
                 }
                 KeyCode::Down => {
                     if app.popup_visible && !app.popup_candidates.is_empty() {
                         app.popup_selected = (app.popup_selected + 1) % app.popup_candidates.len();
-                    } else if let Some(pos) = app.history_pos {
-                        if pos + 1 < app.input_history.len() {
-                            let new_pos = pos + 1;
-                            app.history_pos = Some(new_pos);
-                            app.input = app.input_history[new_pos].clone();
-                        } else {
-                            app.history_pos = None;
-                            app.input = app.input_draft.clone();
-                        }
+                    } else {
+                        app.history_next();
                     }
                 }
                 KeyCode::PageUp => {
@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use once_cell::sync::Lazy;
 
+use crate::frontend::ui::{Theme, THEMES};
+
 pub enum ClientState {
     Guest,
     LoggedIn,
@@ -22,6 +25,37 @@ pub static AVAILABLE_ROOMS: Lazy<Mutex<Vec<(String, usize)>>> = Lazy::new(|| Mut
 
 pub static VISIBLE_USERS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
 
+pub const TYPING_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub const INPUT_HISTORY_LIMIT: usize = 100;
+
+pub const CLIENT_CONFIG_FILE: &str = ".streamline_client.json";
+
+fn client_config_path() -> std::path::PathBuf {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match home {
+        Some(dir) => std::path::Path::new(&dir).join(CLIENT_CONFIG_FILE),
+        None => std::path::PathBuf::from(CLIENT_CONFIG_FILE),
+    }
+}
+
+fn load_theme() -> Theme {
+    std::fs::read_to_string(client_config_path()).ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.get("theme").and_then(|t| t.as_str()).and_then(Theme::by_name))
+        .unwrap_or_default()
+}
+
+fn save_theme(theme: &Theme) -> std::io::Result<()> {
+    let path = client_config_path();
+    let mut config = std::fs::read_to_string(&path).ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    config["theme"] = serde_json::json!(theme.name);
+    std::fs::write(path, serde_json::to_string_pretty(&config)?)
+}
+
 pub fn get_room_members() -> HashMap<String, String> {
     let (lock, _) = &*MEMBERS;
     match lock.lock() {
@@ -33,9 +67,20 @@ pub fn get_room_members() -> HashMap<String, String> {
     }
 }
 
+// Every top-level command and alias the client or server accepts. Only used to hint at typos
+// in the input box, since the server's parser has the final say
+pub const KNOWN_COMMANDS: &[&str] = &[
+    "help", "h", "clear", "c", "time", "theme", "quit", "exit", "q", "e", "ping", "pubkey",
+    "account", "a", "room", "r", "ignore", "i", "block", "b", "user", "u", "mod", "m", "super", "s",
+    "msg", "message", "dm", "reply", "me", "announce", "an", "afk", "seen", "whois",
+    "edit", "leave", "status", "stats", "mode",
+];
+
 pub const COMMANDS_ALWAYS: &[&str] = &[
     "/help",
     "/clear",
+    "/time",
+    "/theme",
     "/quit",
     "/ping",
 ];
@@ -43,6 +88,7 @@ pub const COMMANDS_ALWAYS: &[&str] = &[
 pub const COMMANDS_GUEST: &[&str] = &[
     "/account register",
     "/account login",
+    "/account resume",
     "/account import",
 ];
 
@@ -53,11 +99,18 @@ pub const COMMANDS_LOGGEDIN: &[&str] = &[
     "/account edit password",
     "/account export",
     "/account delete",
+    "/account bans",
+    "/stats",
+    "/msg history",
     "/room list",
+    "/room search",
     "/room join",
     "/room create",
     "/room import",
     "/room delete",
+    "/room favorite list",
+    "/room favorite add",
+    "/room favorite remove",
 ];
 
 pub const COMMANDS_IGNORE: &[&str] = &[
@@ -65,17 +118,31 @@ pub const COMMANDS_IGNORE: &[&str] = &[
     "/ignore list",
     "/ignore add",
     "/ignore remove",
+    "/block",
+    "/block list",
+    "/block add",
+    "/block remove",
 ];
 
 pub const COMMANDS_INROOM_BASE: &[&str] = &[
+    "/edit",
+    "/help roles",
     "/leave",
+    "/msg history",
     "/status",
+    "/stats",
+    "/room history",
+    "/room members",
+    "/room transfer",
 ];
 
 pub enum AppMessage {
     ServerMessage(String),
+    ChatMessage(String, String),
+    EditMessage(String, String),
     NetworkError(String),
     ControlResult(String),
+    Typing(String),
 }
 
 pub struct Autocomplete {
@@ -162,7 +229,10 @@ impl Autocomplete {
 }
 
 pub struct App {
-    pub messages: Vec<String>,
+    pub messages: Vec<(SystemTime, String)>,
+    pub show_timestamps: bool,
+    pub theme: Theme,
+    pub message_ids: HashMap<String, usize>,
     pub input: String,
     pub should_quit: bool,
     pub autocomplete: Autocomplete,
@@ -170,18 +240,23 @@ pub struct App {
 
     pub status: String,
     pub scroll_offset: usize,
-    pub input_history: Vec<String>,
+    pub input_history: VecDeque<String>,
     pub history_pos: Option<usize>,
     pub input_draft: String,
     pub popup_visible: bool,
     pub popup_selected: usize,
     pub popup_candidates: Vec<String>,
+    pub typing: HashMap<String, Instant>,
+    pub last_typing_sent: Option<Instant>,
 }
 
 impl App {
     pub fn new() -> App {
         App {
             messages: Vec::new(),
+            show_timestamps: true,
+            theme: load_theme(),
+            message_ids: HashMap::new(),
             input: String::new(),
             should_quit: false,
             autocomplete: Autocomplete::new(),
@@ -189,17 +264,59 @@ impl App {
 
             status: String::from("Not logged in"),
             scroll_offset: 0,
-            input_history: Vec::new(),
+            input_history: VecDeque::new(),
             history_pos: None,
             input_draft: String::new(),
             popup_visible: false,
             popup_selected: 0,
             popup_candidates: Vec::new(),
+            typing: HashMap::new(),
+            last_typing_sent: None,
         }
     }
 
     pub fn push(&mut self, msg: String) {
-        self.messages.push(msg);
+        self.messages.push((SystemTime::now(), msg));
+    }
+
+    pub fn push_chat(&mut self, id: String, msg: String) {
+        self.message_ids.insert(id, self.messages.len());
+        self.messages.push((SystemTime::now(), msg));
+    }
+
+    pub fn edit_message(&mut self, id: &str, msg: String) -> bool {
+        match self.message_ids.get(id).and_then(|&i| self.messages.get_mut(i)) {
+            Some((_, line)) => {
+                *line = format!("{msg} (edited)");
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear_messages(&mut self) {
+        self.messages.clear();
+        self.message_ids.clear();
+        self.scroll_offset = 0;
+    }
+
+    pub fn handle_theme_command(&mut self, args: &str) {
+        let names: Vec<&str> = THEMES.iter().map(|t| t.name).collect();
+        let args = args.trim();
+        if args.is_empty() {
+            self.push(format!("Current theme: {} (available: {})", self.theme.name, names.join(", ")));
+            return;
+        }
+        match Theme::by_name(args) {
+            Some(theme) => {
+                self.theme = theme;
+                match save_theme(&theme) {
+                    Ok(()) => self.push(format!("Theme set to {}", theme.name)),
+                    Err(e) => self.push(format!("Theme set to {}, but failed to save it: {e}", theme.name)),
+                }
+            }
+            None => self.push(format!("Error: Unknown theme '{args}' (available: {})", names.join(", "))),
+        }
     }
 
     pub fn refresh_member_names(&mut self) {
@@ -208,6 +325,80 @@ impl App {
         }
     }
 
+    // Remembers a submitted line for Up/Down recall, skipping repeats of the previous line
+    pub fn record_input(&mut self, msg: &str) {
+        self.history_pos = None;
+        self.input_draft.clear();
+        if msg.is_empty() || self.input_history.back().map(|s| s.as_str()) == Some(msg) {
+            return;
+        }
+        if self.input_history.len() >= INPUT_HISTORY_LIMIT {
+            self.input_history.pop_front();
+        }
+        self.input_history.push_back(msg.to_string());
+    }
+
+    // Steps back through history, saving whatever was being typed so history_next can restore it
+    pub fn history_prev(&mut self) {
+        if self.input_history.is_empty() { return; }
+        let new_pos = match self.history_pos {
+            None => {
+                self.input_draft = self.input.clone();
+                self.input_history.len() - 1
+            }
+            Some(p) => p.saturating_sub(1),
+        };
+        self.history_pos = Some(new_pos);
+        self.input = self.input_history[new_pos].clone();
+    }
+
+    pub fn history_next(&mut self) {
+        let Some(pos) = self.history_pos else { return; };
+        if pos + 1 < self.input_history.len() {
+            self.history_pos = Some(pos + 1);
+            self.input = self.input_history[pos + 1].clone();
+        } else {
+            self.history_pos = None;
+            self.input = std::mem::take(&mut self.input_draft);
+        }
+    }
+
+    // True when the input is a command whose first word can't be any known command.
+    // While the first word is still being typed, any prefix of a known command counts as valid
+    pub fn input_is_unknown_command(&self) -> bool {
+        let Some(rest) = self.input.strip_prefix('/') else { return false; };
+        if rest.is_empty() {
+            return false;
+        }
+        match rest.split_once(char::is_whitespace) {
+            Some((head, _)) => !KNOWN_COMMANDS.contains(&head),
+            None => !KNOWN_COMMANDS.iter().any(|cmd| cmd.starts_with(rest)),
+        }
+    }
+
+    pub fn should_send_typing(&mut self) -> bool {
+        if self.input.starts_with('/') || !matches!(MY_STATE.lock().as_deref(), Ok(ClientState::InRoom)) {
+            return false;
+        }
+        if self.last_typing_sent.is_some_and(|t| t.elapsed() < TYPING_TIMEOUT) {
+            return false;
+        }
+        self.last_typing_sent = Some(Instant::now());
+        true
+    }
+
+    pub fn typing_status(&mut self) -> Option<String> {
+        self.typing.retain(|_, t| t.elapsed() < TYPING_TIMEOUT);
+        let mut names: Vec<&String> = self.typing.keys().collect();
+        names.sort();
+        match names.as_slice() {
+            [] => None,
+            [one] => Some(format!("{one} is typing...")),
+            [a, b] => Some(format!("{a} and {b} are typing...")),
+            _ => Some("Several people are typing...".into()),
+        }
+    }
+
     pub fn update_status(&mut self) {
         let user = CURRENT_USER.lock().map(|u| u.clone()).unwrap_or_default();
         let room = CURRENT_ROOM.lock().map(|r| r.clone()).unwrap_or_default();
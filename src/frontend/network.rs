@@ -9,16 +9,31 @@ use crate::shared::crypto::{generate_or_load_keys, decrypt};
 
 pub fn handle_control_packets(stream: &mut TcpStream, msg: &str, tx: &Sender<AppMessage>) -> std::io::Result<()> {
     if let Some(frt_latency) = msg.strip_prefix("/PONG ") {
-        if let Ok(sent_ms) = frt_latency.trim().parse::<u128>() {
-            let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
-            let rtt_s = now_ms.saturating_sub(sent_ms) as f64 / 1000.0;
-            let _ = tx.send(AppMessage::ControlResult(format!("◎ Pong! Round-trip: {rtt_s:.3}s")));
+        if let Ok(sent_ms) = frt_latency.trim().parse::<u64>() {
+            let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+            let rtt_ms = now_ms.saturating_sub(sent_ms);
+            let _ = tx.send(AppMessage::ControlResult(format!("◎ Pong! {rtt_ms}ms")));
         } else {
             let _ = tx.send(AppMessage::ControlResult("⚠ Warning: received /PONG with invalid timestamp".into()));
         }
         return Ok(());
     }
 
+    if msg == "/PING" {
+        stream.write_all(b"/pong\n")?;
+        return Ok(());
+    }
+
+    if let Some(username) = msg.strip_prefix("/typing ") {
+        let _ = tx.send(AppMessage::Typing(username.trim().to_string()));
+        return Ok(());
+    }
+
+    if let Some(token) = msg.strip_prefix("/TOKEN ") {
+        let _ = tx.send(AppMessage::ControlResult(format!("Resume token: {} (use /account resume <token> within 5 minutes of a dropped connection)", token.trim())));
+        return Ok(());
+    }
+
     if let Some(username) = msg.strip_prefix("/LOGIN_OK ") {
         {
             let mut state = MY_STATE.lock().map_err(|e| io::Error::other(e.to_string()))?;
@@ -156,15 +171,25 @@ pub fn handle_recv(stream: TcpStream, tx: Sender<AppMessage>) -> std::io::Result
     for line in reader.lines() {
         match line {
             Ok(msg) => {
-                if msg.starts_with("/enc ") {
-                    if let Some(enc_line) = msg.strip_prefix("/enc ") {
-                        if let Some((prefix, cipher_b64)) = enc_line.split_once(": ") {
+                if msg.starts_with("/enc ") || msg.starts_with("/edit ") {
+                    let is_edit = msg.starts_with("/edit ");
+                    let enc_line = msg.split_once(' ').map(|(_, rest)| rest).unwrap_or("");
+                    match enc_line.split_once(' ').and_then(|(id, rest)| rest.split_once(": ").map(|(prefix, cipher)| (id, prefix, cipher))) {
+                        Some((id, prefix, cipher_b64)) => {
                             match decrypt(cipher_b64) {
-                                Ok(plaintext) => { let _ = tx.send(AppMessage::ServerMessage(format!("{prefix}: {plaintext}"))); }
+                                Ok(plaintext) => {
+                                    let line = format!("{prefix}: {plaintext}");
+                                    let _ = if is_edit {
+                                        tx.send(AppMessage::EditMessage(id.to_string(), line))
+                                    } else {
+                                        tx.send(AppMessage::ChatMessage(id.to_string(), line))
+                                    };
+                                }
                                 Err(e) => { let _ = tx.send(AppMessage::NetworkError(format!("Decryption error: {e}"))); }
                             }
-                        } else {
-                            let _ = tx.send(AppMessage::NetworkError("Malformed /enc message".into()));
+                        }
+                        None => {
+                            let _ = tx.send(AppMessage::NetworkError("Malformed encrypted message".into()));
                         }
                     }
                     continue;
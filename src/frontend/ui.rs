@@ -8,18 +8,82 @@ use ratatui::{
 
 use crate::frontend::app::{App, AVAILABLE_ROOMS, VISIBLE_USERS, MY_STATE, ClientState};
 
-pub const C_BG: Color = Color::Rgb(18, 18, 18);
-pub const C_SURFACE: Color = Color::Rgb(28, 28, 28);
-pub const C_BORDER: Color = Color::Rgb(60, 60, 60);
-pub const C_BORDER_ACTIVE: Color = Color::Rgb(160, 160, 160);
-pub const C_TEXT: Color = Color::Rgb(240, 240, 240);
-pub const C_DIM: Color = Color::DarkGray;
-pub const C_ACCENT: Color = Color::Rgb(200, 200, 200);
-pub const C_ACCENT2: Color = Color::Rgb(150, 150, 150);
-pub const C_YELLOW: Color = Color::Rgb(220, 220, 220);
-pub const C_RED: Color = Color::Red;
-pub const C_GREEN: Color = Color::Rgb(120, 160, 120);
-pub const C_SYSTEM: Color = Color::Gray;
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub name: &'static str,
+    pub bg: Color,
+    pub surface: Color,
+    pub border: Color,
+    pub border_active: Color,
+    pub text: Color,
+    pub dim: Color,
+    pub accent: Color,
+    pub accent2: Color,
+    pub input: Color,
+    pub red: Color,
+    pub green: Color,
+    pub system: Color,
+}
+
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "dark",
+        bg: Color::Rgb(18, 18, 18),
+        surface: Color::Rgb(28, 28, 28),
+        border: Color::Rgb(60, 60, 60),
+        border_active: Color::Rgb(160, 160, 160),
+        text: Color::Rgb(240, 240, 240),
+        dim: Color::DarkGray,
+        accent: Color::Rgb(200, 200, 200),
+        accent2: Color::Rgb(150, 150, 150),
+        input: Color::Rgb(220, 220, 220),
+        red: Color::Red,
+        green: Color::Rgb(120, 160, 120),
+        system: Color::Gray,
+    },
+    Theme {
+        name: "light",
+        bg: Color::Rgb(250, 250, 250),
+        surface: Color::Rgb(232, 232, 232),
+        border: Color::Rgb(190, 190, 190),
+        border_active: Color::Rgb(90, 90, 90),
+        text: Color::Rgb(20, 20, 20),
+        dim: Color::Rgb(130, 130, 130),
+        accent: Color::Rgb(50, 50, 50),
+        accent2: Color::Rgb(90, 90, 90),
+        input: Color::Rgb(30, 30, 30),
+        red: Color::Rgb(180, 30, 30),
+        green: Color::Rgb(40, 120, 40),
+        system: Color::Rgb(80, 80, 80),
+    },
+    Theme {
+        name: "high-contrast",
+        bg: Color::Black,
+        surface: Color::Black,
+        border: Color::White,
+        border_active: Color::LightYellow,
+        text: Color::White,
+        dim: Color::Gray,
+        accent: Color::LightYellow,
+        accent2: Color::LightCyan,
+        input: Color::LightYellow,
+        red: Color::LightRed,
+        green: Color::LightGreen,
+        system: Color::LightCyan,
+    },
+];
+
+impl Theme {
+    pub fn by_name(name: &str) -> Option<Theme> {
+        THEMES.iter().find(|t| t.name.eq_ignore_ascii_case(name)).copied()
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEMES[0]
+    }
+}
 
 pub enum LineKind {
     System,
@@ -96,6 +160,8 @@ pub fn classify_line(s: &str) -> LineKind {
         || s.starts_with("Already ")
         || s.starts_with("Not in ")
         || s.starts_with("Command not")
+        || s.starts_with("Stat ")
+        || s.starts_with("Resume token")
         || s.starts_with("◎")
     {
         return LineKind::System;
@@ -104,21 +170,21 @@ pub fn classify_line(s: &str) -> LineKind {
     LineKind::Plain
 }
 
-pub fn styled_line(s: &str) -> Line<'static> {
+pub fn styled_line(s: &str, theme: &Theme) -> Line<'static> {
     if s.contains('\x1b') {
-        return parse_ansi(s);
+        return parse_ansi(s, theme);
     }
 
     match classify_line(s) {
-        LineKind::System  => Line::from(Span::styled(s.to_owned(), Style::default().fg(C_SYSTEM))),
-        LineKind::Error   => Line::from(Span::styled(s.to_owned(), Style::default().fg(C_RED))),
-        LineKind::Success => Line::from(Span::styled(s.to_owned(), Style::default().fg(C_GREEN))),
+        LineKind::System  => Line::from(Span::styled(s.to_owned(), Style::default().fg(theme.system))),
+        LineKind::Error   => Line::from(Span::styled(s.to_owned(), Style::default().fg(theme.red))),
+        LineKind::Success => Line::from(Span::styled(s.to_owned(), Style::default().fg(theme.green))),
         LineKind::SelfMsg => {
             let split_at = 4.min(s.len());
             let (label, rest) = s.split_at(split_at);
             Line::from(vec![
-                Span::styled(label.to_owned(), Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD)),
-                Span::styled(rest.to_owned(),  Style::default().fg(C_TEXT)),
+                Span::styled(label.to_owned(), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::styled(rest.to_owned(),  Style::default().fg(theme.text)),
             ])
         }
         LineKind::UserMsg(full_prefix) => {
@@ -148,22 +214,22 @@ pub fn styled_line(s: &str) -> Line<'static> {
                     let role_tag = &full_prefix[..bracket_end + 1];
                     let name_part = &full_prefix[bracket_end + 2..];
                     return Line::from(vec![
-                        Span::styled(role_tag.to_owned(), Style::default().fg(C_DIM)),
+                        Span::styled(role_tag.to_owned(), Style::default().fg(theme.dim)),
                         Span::styled(" ".to_owned(),      Style::default()),
                         Span::styled(name_part.to_owned(), Style::default().fg(name_color).add_modifier(Modifier::BOLD)),
-                        Span::styled(": ".to_owned(),      Style::default().fg(C_DIM)),
-                        Span::styled(rest,                 Style::default().fg(C_TEXT)),
+                        Span::styled(": ".to_owned(),      Style::default().fg(theme.dim)),
+                        Span::styled(rest,                 Style::default().fg(theme.text)),
                     ]);
                 }
             }
 
             Line::from(vec![
                 Span::styled(full_prefix, Style::default().fg(name_color).add_modifier(Modifier::BOLD)),
-                Span::styled(": ".to_owned(),  Style::default().fg(C_DIM)),
-                Span::styled(rest,             Style::default().fg(C_TEXT)),
+                Span::styled(": ".to_owned(),  Style::default().fg(theme.dim)),
+                Span::styled(rest,             Style::default().fg(theme.text)),
             ])
         }
-        LineKind::Plain => Line::from(Span::styled(s.to_owned(), Style::default().fg(C_TEXT))),
+        LineKind::Plain => Line::from(Span::styled(s.to_owned(), Style::default().fg(theme.text))),
     }
 }
 
@@ -215,10 +281,11 @@ pub fn wrap_line(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
     lines
 }
 
-pub fn parse_ansi(s: &str) -> Line<'static> {
+pub fn parse_ansi(s: &str, theme: &Theme) -> Line<'static> {
+    let base_style = Style::default().fg(theme.text);
     let mut spans = Vec::new();
     let mut current_text = String::new();
-    let mut current_style = Style::default();
+    let mut current_style = base_style;
 
     let mut chars = s.chars().peekable();
     while let Some(c) = chars.next() {
@@ -245,7 +312,7 @@ pub fn parse_ansi(s: &str) -> Line<'static> {
                     while i < codes.len() {
                         if let Ok(c) = codes[i].parse::<u32>() {
                             match c {
-                                0 => current_style = Style::default(),
+                                0 => current_style = base_style,
                                 1 => current_style = current_style.add_modifier(Modifier::BOLD),
                                 3 => current_style = current_style.add_modifier(Modifier::ITALIC),
                                 4 => current_style = current_style.add_modifier(Modifier::UNDERLINED),
@@ -304,11 +371,11 @@ pub fn parse_ansi(s: &str) -> Line<'static> {
     Line::from(spans)
 }
 
-pub fn ui(f: &mut Frame, app: &mut App) {
+pub fn ui(f: &mut Frame, app: &mut App, theme: &Theme) {
     let area = f.area();
 
     f.render_widget(
-        Block::default().style(Style::default().bg(C_BG)),
+        Block::default().style(Style::default().bg(theme.bg)),
         area,
     );
 
@@ -324,11 +391,11 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 
     let title = Paragraph::new(
         Line::from(vec![
-            Span::styled("  ◈ ", Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD)),
-            Span::styled("StreamLine", Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD))
+            Span::styled("  ◈ ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+            Span::styled("StreamLine", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
         ])
     )
-    .style(Style::default().bg(C_SURFACE))
+    .style(Style::default().bg(theme.surface))
     .alignment(Alignment::Left);
     f.render_widget(title, chunks[0]);
 
@@ -355,8 +422,13 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     let start_idx = app.messages.len().saturating_sub(display_buffer);
     
     let mut all_lines = Vec::new();
-    for m in app.messages.iter().skip(start_idx) {
-        all_lines.extend(wrap_line(styled_line(m), inner_width));
+    for (received, m) in app.messages.iter().skip(start_idx) {
+        let mut line = styled_line(m, theme);
+        if app.show_timestamps {
+            let stamp = chrono::DateTime::<chrono::Local>::from(*received).format("%H:%M ").to_string();
+            line.spans.insert(0, Span::styled(stamp, Style::default().fg(theme.dim)));
+        }
+        all_lines.extend(wrap_line(line, inner_width));
     }
 
     let total_lines = all_lines.len();
@@ -381,9 +453,9 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     let messages_block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(C_BORDER))
-        .title(Span::styled(scroll_indicator, Style::default().fg(C_DIM)))
-        .style(Style::default().bg(C_BG));
+        .border_style(Style::default().fg(theme.border))
+        .title(Span::styled(scroll_indicator, Style::default().fg(theme.dim)))
+        .style(Style::default().bg(theme.bg));
 
     let msg_list = List::new(visible_lines)
         .block(messages_block);
@@ -397,7 +469,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
                 let room_items: Vec<ListItem> = if rooms.is_empty() {
                     vec![ListItem::new(Line::from(Span::styled(
                         "  No rooms available",
-                        Style::default().fg(C_DIM)
+                        Style::default().fg(theme.dim)
                     )))]
             } else {
                 rooms.iter().map(|(name, count)| {
@@ -406,16 +478,16 @@ pub fn ui(f: &mut Frame, app: &mut App) {
                     } else {
                         format!("{name} ({count} users)")
                     };
-                    ListItem::new(Line::from(Span::styled(text, Style::default().fg(C_TEXT))))
+                    ListItem::new(Line::from(Span::styled(text, Style::default().fg(theme.text))))
                 }).collect()
             };
             
             let panel_block = Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_BORDER))
-                .title(Span::styled(" Rooms ", Style::default().fg(C_DIM)))
-                .style(Style::default().bg(C_BG));
+                .border_style(Style::default().fg(theme.border))
+                .title(Span::styled(" Rooms ", Style::default().fg(theme.dim)))
+                .style(Style::default().bg(theme.bg));
             
             let panel_list = List::new(room_items).block(panel_block);
             f.render_widget(panel_list, panel_area);
@@ -425,16 +497,16 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             let user_items: Vec<ListItem> = if users.is_empty() {
                 vec![ListItem::new(Line::from(Span::styled(
                     "  No users online",
-                    Style::default().fg(C_DIM)
+                    Style::default().fg(theme.dim)
                 )))]
             } else {
                 users.iter().map(|formatted_user| {
                     let line = if formatted_user.contains('\x1b') {
-                        parse_ansi(formatted_user)
+                        parse_ansi(formatted_user, theme)
                     } else {
                         Line::from(Span::styled(
                             formatted_user.clone(),
-                            Style::default().fg(C_TEXT)
+                            Style::default().fg(theme.text)
                         ))
                     };
 
@@ -447,9 +519,9 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             let panel_block = Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_BORDER))
-                .title(Span::styled(" Users ", Style::default().fg(C_DIM)))
-                .style(Style::default().bg(C_BG));
+                .border_style(Style::default().fg(theme.border))
+                .title(Span::styled(" Users ", Style::default().fg(theme.dim)))
+                .style(Style::default().bg(theme.bg));
             
             let panel_list = List::new(user_items).block(panel_block);
             f.render_widget(panel_list, panel_area);
@@ -460,23 +532,31 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 
     let input_area = chunks[2];
 
-    let mut spans = vec![Span::styled(app.input.clone(), Style::default().fg(C_YELLOW))];
-    spans.push(Span::styled("█".to_owned(), Style::default().fg(C_ACCENT).add_modifier(Modifier::SLOW_BLINK)));
+    let mut spans = vec![Span::styled(app.input.clone(), Style::default().fg(theme.input))];
+    spans.push(Span::styled("█".to_owned(), Style::default().fg(theme.accent).add_modifier(Modifier::SLOW_BLINK)));
 
+    let unknown_command = app.input_is_unknown_command();
     let input_title = if app.popup_visible {
         " Input  [↑↓] navigate · [Tab/Enter] accept · [Esc] close "
+    } else if unknown_command {
+        " Input  unknown command · [Tab] autocomplete · [Esc] quit "
     } else {
         " Input  [Tab] autocomplete · [Esc] quit "
     };
+    let (input_border, input_title_color) = if unknown_command {
+        (theme.red, theme.red)
+    } else {
+        (theme.border_active, theme.dim)
+    };
 
     let input_widget = Paragraph::new(Line::from(spans))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(C_BORDER_ACTIVE))
-                .title(Span::styled(input_title, Style::default().fg(C_DIM)))
-                .style(Style::default().bg(C_SURFACE)),
+                .border_style(Style::default().fg(input_border))
+                .title(Span::styled(input_title, Style::default().fg(input_title_color)))
+                .style(Style::default().bg(theme.surface)),
         );
     f.render_widget(input_widget, input_area);
 
@@ -504,9 +584,9 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             .map(|(i, cmd)| {
                 let actual_idx = win_start + i;
                 let style = if actual_idx == app.popup_selected {
-                    Style::default().fg(C_BG).bg(C_ACCENT).add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.bg).bg(theme.accent).add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(C_TEXT).bg(C_SURFACE)
+                    Style::default().fg(theme.text).bg(theme.surface)
                 };
                 ListItem::new(Span::styled(format!(" {cmd} "), style))
             }).collect();
@@ -532,19 +612,23 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         let popup_block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(C_BORDER_ACTIVE))
-            .title(Span::styled(popup_title, Style::default().fg(C_ACCENT2)))
-            .style(Style::default().bg(C_SURFACE));
+            .border_style(Style::default().fg(theme.border_active))
+            .title(Span::styled(popup_title, Style::default().fg(theme.accent2)))
+            .style(Style::default().bg(theme.surface));
 
         let popup_list = List::new(popup_items).block(popup_block);
         f.render_widget(ratatui::widgets::Clear, popup_area);
         f.render_widget(popup_list, popup_area);
     }
 
-    let status_line = Paragraph::new(Line::from(vec![
-        Span::styled(app.status.clone(), Style::default().fg(C_ACCENT2)),
-    ]))
-    .style(Style::default().bg(C_SURFACE))
+    let mut status_spans = vec![
+        Span::styled(app.status.clone(), Style::default().fg(theme.accent2)),
+    ];
+    if let Some(typing) = app.typing_status() {
+        status_spans.push(Span::styled(format!("  ·  {typing}"), Style::default().fg(theme.dim).add_modifier(Modifier::ITALIC)));
+    }
+    let status_line = Paragraph::new(Line::from(status_spans))
+    .style(Style::default().bg(theme.surface))
     .alignment(Alignment::Left);
     f.render_widget(status_line, chunks[3]);
 }
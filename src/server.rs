@@ -1,24 +1,101 @@
 use std::collections::{HashMap, VecDeque};
 use std::io::{BufReader, BufRead, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
 use std::{env, thread};
-use std::time::{SystemTime, Instant, Duration};
+use std::time::{SystemTime, UNIX_EPOCH, Instant, Duration};
 use colored::Colorize;
 mod backend;
 mod shared;
 
 use crate::backend::parser::{Command, parse_command};
 use crate::backend::dispatcher::{dispatch_command, CommandResult};
-use crate::backend::command_utils::{sync_room_members, unix_timestamp};
-use crate::shared::types::{Client, ClientState, Clients, PublicKeys, Room, Rooms};
-use crate::shared::utils::{check_mute, format_broadcast, lock_client, lock_clients, lock_room, lock_rooms, log_event};
+use crate::backend::command_utils::{sync_room_commands, sync_room_members, unix_timestamp, MAX_MESSAGE_BYTES};
+use crate::shared::types::{Client, ClientState, Clients, LoginFailures, PublicKeys, ResumeTokens, Room, Rooms, RESUME_TOKEN_TTL_SECS, SERVER_START, SHUTDOWN};
+use crate::shared::utils::{find_room, send_failure, check_mute, is_archived, disconnect_all_clients, format_broadcast, check_rate_limit, lock_client, lock_clients, lock_resume_tokens, lock_room, lock_rooms, log_audit, log_event, record_history, save_rooms_to_disk, send_error, send_message, send_message_locked};
+
+pub fn revert_expired_roles(clients: &Clients, rooms: &Rooms, pubkeys: &PublicKeys) -> std::io::Result<()> {
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        Err(_) => 0,
+    };
+
+    let mut changed_rooms = Vec::new();
+    {
+        let rooms_map = lock_rooms(rooms);
+        for (name, arc) in rooms_map.iter() {
+            let mut room = match arc.lock() {
+                Ok(r) => r,
+                Err(_) => continue,
+            };
+            let mut changed = false;
+            for (uname, rec) in room.users.iter_mut() {
+                if rec.role_expiry == 0 || now < rec.role_expiry { continue; }
+
+                rec.role = if rec.prior_role.is_empty() { "user".to_string() } else { rec.prior_role.clone() };
+                rec.role_expiry = 0;
+                rec.prior_role.clear();
+                changed = true;
+                println!("Temporary role for {uname} in {name} expired, reverted to {}", rec.role);
+                log_audit("server", uname, name, &format!("role.revert {}", rec.role));
+            }
+            if changed {
+                changed_rooms.push(name.clone());
+            }
+        }
 
-pub fn session_housekeeper(clients: Clients, rooms: Rooms, pubkeys: PublicKeys) -> std::io::Result<()> {
+        if !changed_rooms.is_empty() {
+            save_rooms_to_disk(&rooms_map)?;
+        }
+    }
+
+    for room_name in changed_rooms {
+        let _ = sync_room_commands(rooms, clients, &room_name);
+        let _ = sync_room_members(rooms, clients, pubkeys, &room_name);
+        let _ = crate::shared::utils::broadcast_user_list(clients, rooms, &room_name);
+    }
+
+    Ok(())
+}
+
+const READ_TIMEOUT_SECS: u64 = 5;
+const GUEST_IDLE_TIMEOUT_SECS: u64 = 300;
+const HOUSEKEEPER_MIN_SECS: u64 = 5;
+const HOUSEKEEPER_MAX_SECS: u64 = 60;
+const HEARTBEAT_TIMEOUT_SECS: u64 = 150;
+
+pub fn session_housekeeper(clients: Clients, rooms: Rooms, pubkeys: PublicKeys, resume_tokens: ResumeTokens) -> std::io::Result<()> {
     loop {
-        thread::sleep(Duration::from_secs(60));
+        let interval = {
+            let min_timeout = match rooms.lock() {
+                Ok(rooms_guard) => rooms_guard
+                    .values()
+                    .filter_map(|arc| arc.lock().ok().map(|r| r.session_timeout as u64))
+                    .filter(|t| *t > 0)
+                    .min(),
+                Err(_) => None,
+            };
+            match min_timeout {
+                Some(t) => (t / 5).clamp(HOUSEKEEPER_MIN_SECS, HOUSEKEEPER_MAX_SECS),
+                None => HOUSEKEEPER_MAX_SECS,
+            }
+        };
+
+        for _ in 0..interval {
+            if SHUTDOWN.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
         let now = SystemTime::now();
 
+        if let Err(e) = revert_expired_roles(&clients, &rooms, &pubkeys) {
+            eprintln!("Error reverting expired roles: {e}");
+        }
+
+        lock_resume_tokens(&resume_tokens).retain(|_, (_, _, dropped)| dropped.elapsed().as_secs() < RESUME_TOKEN_TTL_SECS);
+
         let room_timeouts: HashMap<String, u32> = {
             let rooms_guard = match rooms.lock() {
                 Ok(g)  => g,
@@ -40,7 +117,15 @@ pub fn session_housekeeper(clients: Clients, rooms: Rooms, pubkeys: PublicKeys)
 
         for client_arc in client_arcs {
             if let Ok(mut client) = client_arc.lock() {
-                if let ClientState::InRoom { username, room, inactive_time, .. } = &mut client.state {
+                // Shutting the socket down makes the client's own thread hit EOF and run its usual cleanup
+                if client.last_pong.elapsed().as_secs() >= HEARTBEAT_TIMEOUT_SECS {
+                    log_event(&client.addr, None, None, "Missed heartbeats, closing connection");
+                    let _ = client.stream.shutdown(Shutdown::Both);
+                    continue;
+                }
+                let _ = writeln!(client.stream, "/PING");
+
+                if let ClientState::InRoom { username, room, inactive_time, warned, .. } = &mut client.state {
                     let timeout = match room_timeouts.get(room) {
                         Some(t) => *t,
                         None => 0,
@@ -57,18 +142,26 @@ pub fn session_housekeeper(clients: Clients, rooms: Rooms, pubkeys: PublicKeys)
                         Err(_) => 0,
                     };
 
+                    let warn_at = timeout as u64 * 4 / 5;
+                    if idle_secs >= warn_at && idle_secs < timeout as u64 && !*warned {
+                        *warned = true;
+                        let remaining = timeout as u64 - idle_secs;
+                        send_message_locked(&mut client, &format!("You'll be kicked for inactivity in {remaining}s").yellow().to_string())?;
+                        continue;
+                    }
+
                     if idle_secs >= timeout as u64
                     {
                         let user = username.clone();
                         let room_name = room.clone();
 
                         client.state = ClientState::LoggedIn { username: user.clone() };
-                        writeln!(client.stream, "{}", format!("/LOBBY_STATE"))?;
-                        writeln!(client.stream, "{}", "Session timed out, returned to lobby".yellow())?;
+                        send_message_locked(&mut client, "/LOBBY_STATE")?;
+                        send_message_locked(&mut client, &"Session timed out, returned to lobby".yellow().to_string())?;
                         drop(client);
 
                         {
-                            let rooms_map = lock_rooms(&rooms)?;
+                            let rooms_map = lock_rooms(&rooms);
                             if let Some(room_arc) = rooms_map.get(&room_name) {
                                 if let Ok(mut r) = room_arc.lock() {
                                     r.online_users.retain(|u| u != &user);
@@ -89,83 +182,121 @@ pub fn session_housekeeper(clients: Clients, rooms: Rooms, pubkeys: PublicKeys)
     }
 }
 
-pub fn check_rate_limit(client_arc: &Arc<Mutex<Client>>, rooms: &Rooms, is_first: bool) -> std::io::Result<bool> {
-    let now = Instant::now();
-
-    let mut c = lock_client(client_arc)?;
-    if let ClientState::InRoom { room: rname, msg_timestamps, .. } = &mut c.state {
-        let rooms_map = lock_rooms(rooms)?;
-        let rate = match rooms_map.get(rname) {
-            Some(room_arc) => match lock_room(room_arc) {
-                Ok(room) => room.msg_rate,
-                Err(_) => {
-                    writeln!(c.stream, "{}", "Error: could not lock room".red())?;
-                    return Ok(false);
-                }
-            },
-            None => {
-                writeln!(c.stream, "{}", "Error: room not found".yellow())?;
-                return Ok(false);
-            }
-        };
-
-        while let Some(ts) = msg_timestamps.front() {
-            if now.duration_since(*ts).as_secs() >= 5 {
-                msg_timestamps.pop_front();
-            }
-            else {
-                break;
-            }
+fn relay_typing(client_arc: &Arc<Mutex<Client>>, clients: &Clients, rooms: &Rooms) -> std::io::Result<()> {
+    let (username, room_name) = {
+        let c = lock_client(client_arc);
+        match &c.state {
+            ClientState::InRoom { username, room, .. } => (username.clone(), room.clone()),
+            _ => return Ok(()),
         }
+    };
 
-        if rate > 0 && msg_timestamps.len() as u8 >= rate {
-            if is_first {
-                writeln!(c.stream, "{}", "Rate limit exceeded, slow down your messages!".yellow())?;
-            }
-            return Ok(false);
+    if is_archived(rooms, &room_name) || check_mute(rooms, &room_name, &username)?.is_some() {
+        return Ok(());
+    }
+
+    let hidden = {
+        let rooms_map = lock_rooms(rooms);
+        match rooms_map.get(&room_name) {
+            Some(room_arc) => lock_room(room_arc).users.get(&username).map(|u| u.hidden).unwrap_or(false),
+            None => return Ok(()),
         }
+    };
+    if hidden {
+        return Ok(());
+    }
 
-        if is_first {
-            msg_timestamps.push_back(now);
+    let clients_map = lock_clients(clients);
+    for arc in clients_map.values() {
+        if Arc::ptr_eq(arc, client_arc) {
+            continue;
+        }
+        if let Ok(mut c) = arc.try_lock() {
+            let same_room = matches!(&c.state, ClientState::InRoom { room, .. } if room == &room_name);
+            if same_room && !c.ignore_list.contains(&username) {
+                let _ = writeln!(c.stream, "/typing {username}");
+            }
         }
     }
-    Ok(true)
+
+    Ok(())
 }
 
-fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: Rooms, pubkeys: PublicKeys) -> std::io::Result<()> {
-    let reader = BufReader::new(stream.try_clone()?);
+fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: Rooms, pubkeys: PublicKeys, login_failures: LoginFailures, resume_tokens: ResumeTokens) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(READ_TIMEOUT_SECS)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
 
     let client_arc = Arc::new(Mutex::new(Client {
         stream: stream.try_clone()?,
         addr: peer,
         state: ClientState::Guest,
         ignore_list: Vec::new(),
+        dm_block_list: Vec::new(),
+        favorites: Vec::new(),
+        last_room_list: Vec::new(),
         pubkey: String::new(),
         login_attempts: VecDeque::new(),
+        last_dm_from: None,
+        last_msg_id: 0,
+        dm_log: VecDeque::new(),
+        last_room_create: None,
+        resume_token: None,
+        resume_room: None,
+        json_output: false,
+        last_pong: Instant::now(),
     }));
 
     {
-        let mut locked = lock_clients(&clients)?;
+        let mut locked = lock_clients(&clients);
         locked.insert(peer, Arc::clone(&client_arc));
     }
 
     log_event(&peer, None, None, "Connected to server");
 
-    for line in reader.lines() {
+    let mut buf = String::new();
+    let mut last_read = Instant::now();
+    loop {
+        let line = match reader.read_line(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => {
+                last_read = Instant::now();
+                lock_client(&client_arc).last_pong = last_read;
+                Ok(std::mem::take(&mut buf))
+            }
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                if SHUTDOWN.load(Ordering::SeqCst) { break; }
+                let is_guest = matches!(lock_client(&client_arc).state, ClientState::Guest);
+                if is_guest && last_read.elapsed().as_secs() >= GUEST_IDLE_TIMEOUT_SECS {
+                    let mut c = lock_client(&client_arc);
+                    let _ = send_message_locked(&mut c, &"Connection closed due to inactivity".yellow().to_string());
+                    drop(c);
+                    log_event(&peer, None, None, "Timed out while idle");
+                    break;
+                }
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+
         match line {
             Ok(msg) => {
                 let msg = msg.trim().to_string();
 
                 if msg.is_empty() { continue };
 
+                // Heartbeat replies prove the connection is alive but don't count as activity
+                if msg == "/pong" { continue };
+
                 {
-                    let mut s = lock_client(&client_arc)?;
-                    let should_broadcast = if let ClientState::InRoom { inactive_time, is_afk, room, .. } = &mut s.state {
-                        let was_afk = *is_afk;
+                    let mut s = lock_client(&client_arc);
+                    let is_afk_cmd = msg == "/afk" || msg.starts_with("/afk ") || msg == "/typing";
+                    let should_broadcast = if let ClientState::InRoom { inactive_time, is_afk, afk_reason, room, warned, .. } = &mut s.state {
                         *inactive_time = Some(SystemTime::now());
-                        *is_afk = false;
+                        *warned = false;
 
-                        if was_afk {
+                        if *is_afk && !is_afk_cmd {
+                            *is_afk = false;
+                            afk_reason.clear();
                             Some(room.clone())
                         } else {
                             None
@@ -181,43 +312,28 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
 
                 if msg.starts_with("/") {
                     if let Some(rest) = msg.strip_prefix("/members? ") {
-                        let (username, room_name) = {
-                            let client = lock_client(&client_arc)?;
-                            match &client.state {
-                                ClientState::InRoom { username, room, .. } => (username.clone(), room.clone()),
-                                _ => {
-                                    let mut client = lock_client(&client_arc)?;
-                                    writeln!(client.stream, "{}", "You are not in a room".yellow())?;
-                                    continue;
-                                }
+                        let in_room = match &lock_client(&client_arc).state {
+                            ClientState::InRoom { username, room, .. } => Some((username.clone(), room.clone())),
+                            _ => None,
+                        };
+                        let (username, room_name) = match in_room {
+                            Some(pair) => pair,
+                            None => {
+                                send_message(&client_arc, &"You are not in a room".yellow().to_string())?;
+                                continue;
                             }
                         };
 
                         let (can_see_hidden, online_in_room) = {
-                            let rooms_map = match lock_rooms(&rooms) {
-                                Ok(m) => m,
-                                Err(_) => {
-                                    let mut client = lock_client(&client_arc)?;
-                                    writeln!(client.stream, "{}", "Failed to lock rooms".red())?;
-                                    continue;
-                                }
-                            };
-                            let room_arc = match rooms_map.get(&room_name) {
-                                Some(r) => Arc::clone(r),
-                                None => {
-                                    let mut client = lock_client(&client_arc)?;
-                                    writeln!(client.stream, "{}", format!("Room {room_name} not found").yellow())?;
-                                    continue;
-                                }
-                            };
-                            let room_guard = match lock_room(&room_arc) {
-                                Ok(g) => g,
-                                Err(_) => {
-                                    let mut client = lock_client(&client_arc)?;
-                                    writeln!(client.stream, "{}", "Failed to lock room".red())?;
+                            let rooms_map = lock_rooms(&rooms);
+                            let room_arc = match find_room(&rooms_map, &room_name) {
+                                Ok(r) => r,
+                                Err(e) => {
+                                    send_failure(&client_arc, &e)?;
                                     continue;
                                 }
                             };
+                            let room_guard = lock_room(&room_arc);
                             
                             let role = room_guard.users.get(&username).map(|u| u.role.as_str()).unwrap_or("user");
                             let can_see = role == "owner" || role == "admin";
@@ -230,12 +346,11 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                             (can_see, online_visibility)
                         };
 
-                        let clients_map = lock_clients(&clients)?;
+                        let clients_map = lock_clients(&clients);
                         let pubkeys_map = match pubkeys.lock() {
                             Ok(map) => map,
                             Err(_) => {
-                                let mut client = lock_client(&client_arc)?;
-                                writeln!(client.stream, "{}", "Failed to lock pubkeys".red())?;
+                                send_error(&client_arc, "Failed to lock pubkeys")?;
                                 continue;
                             }
                         };
@@ -254,8 +369,8 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                                                     continue;
                                                 }
                                                 if let Some(key) = pubkeys_map.get(u) {
-                                                    let mut requester = lock_client(&client_arc)?;
-                                                    writeln!(requester.stream, "/members {u}:{key}")?;
+                                                    let mut requester = lock_client(&client_arc);
+                                                    send_message_locked(&mut requester, &format!("/members {u}:{key}"))?;
                                                 }
                                                 break;
                                             }
@@ -281,8 +396,8 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                                     }
                                 }
                                 let line = format!("/members {}", pairs.join(" "));
-                                let mut requester = lock_client(&client_arc)?;
-                                writeln!(requester.stream, "{line}")?;
+                                let mut requester = lock_client(&client_arc);
+                                send_message_locked(&mut requester, &line)?;
                             }
 
                             ["full"] => {
@@ -302,28 +417,32 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                                     }
                                 }
                                 let line = format!("/members {}", pairs.join(" "));
-                                let mut requester = lock_client(&client_arc)?;
-                                writeln!(requester.stream, "{line}")?;
+                                let mut requester = lock_client(&client_arc);
+                                send_message_locked(&mut requester, &line)?;
                             }
 
                             _ => {
-                                let mut client = lock_client(&client_arc)?;
-                                writeln!(client.stream, "{}", "Invalid /members? usage".red())?;
+                                send_error(&client_arc, "Invalid /members? usage")?;
                             }
                         }
 
                         continue;
                     }
 
+                    if msg == "/typing" {
+                        relay_typing(&client_arc, &clients, &rooms)?;
+                        continue;
+                    }
+
                     let command: Command = parse_command(&msg);
                     
-                    match dispatch_command(command, Arc::clone(&client_arc), &clients, &rooms, &pubkeys)? {
+                    match dispatch_command(command, Arc::clone(&client_arc), &clients, &rooms, &pubkeys, &login_failures, &resume_tokens)? {
                         CommandResult::Handled => continue,
                         CommandResult::Stop => break
                     }
                 }
 
-                let mut sender = lock_client(&client_arc)?;
+                let mut sender = lock_client(&client_arc);
                 
                 match &sender.state {
                     ClientState::InRoom { username, room, .. } => {
@@ -342,10 +461,23 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                         let recipient   = pieces[0];
                         let ciphertext  = pieces[1];
 
+                        if msg.len() > MAX_MESSAGE_BYTES {
+                            if is_first {
+                                send_error(&client_arc, "Message too long")?;
+                            }
+                            continue;
+                        }
+
+                        if is_archived(&rooms, &room_name) {
+                            if is_first {
+                                send_error(&client_arc, "This room is archived")?;
+                            }
+                            continue;
+                        }
+
                         if let Some(msg) = check_mute(&rooms, &room_name, &username)? {
                             if is_first {
-                                let mut client = lock_client(&client_arc)?;
-                                writeln!(client.stream, "{}", msg.red())?;
+                                send_error(&client_arc, &msg)?;
                             }
                             continue;
                         }
@@ -358,7 +490,19 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                             continue;
                         }
 
-                        let clients_map = lock_clients(&clients)?;
+                        let msg_id = {
+                            let mut c = lock_client(&client_arc);
+                            if is_first {
+                                c.last_msg_id += 1;
+                            }
+                            c.last_msg_id
+                        };
+
+                        if is_first {
+                            record_history(&rooms, &room_name, &username)?;
+                        }
+
+                        let clients_map = lock_clients(&clients);
                         if let Some(rec_arc) = clients_map.values().find(|arc| {
                             let c = match arc.lock() {
                                 Ok(c) => c,
@@ -372,18 +516,18 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                                 if u == recipient && r == &room_name)
                         }).cloned()
                         {
-                            let mut rec = lock_client(&rec_arc)?;
+                            let mut rec = lock_client(&rec_arc);
                             if rec.ignore_list.contains(&username) {
                                 continue;
                             }
-                            writeln!(rec.stream, "/enc {} {}: {}", role_prefix, display_name, ciphertext)?;
+                            send_message_locked(&mut rec, &format!("/enc {username}:{msg_id} {role_prefix} {display_name}: {ciphertext}"))?;
                         }
                     }
                     ClientState::LoggedIn { .. } => {
-                        writeln!(sender.stream, "{}", "You must join a room to chat".yellow())?;
+                        send_message_locked(&mut sender, &"You must join a room to chat".yellow().to_string())?;
                     }
                     ClientState::Guest => {
-                        writeln!(sender.stream, "{}", "You must log in to chat".yellow())?;
+                        send_message_locked(&mut sender, &"You must log in to chat".yellow().to_string())?;
                     }
                 }
             }
@@ -395,12 +539,21 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
     }
 
     let removed = {
-        let mut locked = lock_clients(&clients)?;
+        let mut locked = lock_clients(&clients);
         locked.remove(&peer)
     };
 
     if let Some(client_arc) = removed {
-        let client = lock_client(&client_arc)?;
+        let client = lock_client(&client_arc);
+
+        let resumable = match &client.state {
+            ClientState::Guest => None,
+            ClientState::LoggedIn { username } => Some((username.clone(), None)),
+            ClientState::InRoom { username, room, .. } => Some((username.clone(), Some(room.clone()))),
+        };
+        if let (Some(token), Some((username, room))) = (client.resume_token.clone(), resumable) {
+            lock_resume_tokens(&resume_tokens).insert(token, (username, room, Instant::now()));
+        }
 
         match &client.state {
             ClientState::Guest => log_event(&peer, None, None, "Disconnected from server"),
@@ -410,7 +563,7 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
                 let uname = username.clone();
                 let rname = room.clone();
                 {
-                    let rmap = lock_rooms(&rooms)?;
+                    let rmap = lock_rooms(&rooms);
                     if let Some(rarc) = rmap.get(&rname) {
                         if let Ok(mut r) = rarc.lock() {
                             r.online_users.retain(|u| u != &uname);
@@ -430,51 +583,107 @@ fn handle_client(stream: TcpStream, peer: SocketAddr, clients: Clients, rooms: R
 fn main() -> std::io::Result<()> {
     let args: Vec<String> = env::args().collect();
     if args.len() > 2 {
-        eprintln!("Usage: ./cargo run --bin server -q <port>?");
+        eprintln!("Usage: ./cargo run --bin server -q <ip:port|port>?");
         std::process::exit(1);
     }
 
-    let port: u16 = if args.len() == 2 {
-        match args[1].parse::<u16>() {
-            Ok(p) => p,
-            Err(_) => 8000,
+    let default_addr = SocketAddr::from(([0, 0, 0, 0], 8000));
+    let bind_addr: SocketAddr = if args.len() == 2 {
+        let arg = &args[1];
+        let parsed = if arg.contains(':') {
+            arg.parse::<SocketAddr>().ok()
+        } else {
+            arg.parse::<u16>().ok().map(|p| SocketAddr::from(([0, 0, 0, 0], p)))
+        };
+        match parsed {
+            Some(addr) => addr,
+            None => {
+                eprintln!("{}", format!("Warning: invalid bind address '{arg}', falling back to {default_addr}").yellow());
+                default_addr
+            }
+        }
+    } else if let Ok(port) = env::var("STREAMLINE_PORT") {
+        match port.trim().parse::<u16>() {
+            Ok(p) => SocketAddr::from(([0, 0, 0, 0], p)),
+            Err(_) => {
+                eprintln!("{}", format!("Warning: invalid STREAMLINE_PORT '{port}', falling back to {default_addr}").yellow());
+                default_addr
+            }
         }
     } else {
-        8000
+        default_addr
     };
-    
-    let listener = TcpListener::bind(format!("0.0.0.0:{port}"))?;
+
+    let listener = TcpListener::bind(bind_addr)?;
+    let _ = SERVER_START.set(Instant::now());
 
     let clients: Clients = Arc::new(Mutex::new(HashMap::new()));
     let pubkeys: PublicKeys = Arc::new(Mutex::new(HashMap::new()));
+    let login_failures: LoginFailures = Arc::new(Mutex::new(HashMap::new()));
+    let resume_tokens: ResumeTokens = Arc::new(Mutex::new(HashMap::new()));
     let room_file = std::fs::File::open("data/rooms.json")?;
     let room_reader = BufReader::new(room_file);
     let parsed_rooms: HashMap<String, Room> = serde_json::from_reader(room_reader)?;
 
     let mut rooms_map: HashMap<String, Arc<Mutex<Room>>> = HashMap::new();
-    for (name, room) in parsed_rooms {
+    for (name, mut room) in parsed_rooms {
+        room.online_users.clear();
         rooms_map.insert(name, Arc::new(Mutex::new(room)));
     }
 
     let rooms: Rooms = Arc::new(Mutex::new(rooms_map));
     
-    println!("Server listening on port {port}");
+    println!("Server listening on {bind_addr}");
 
-    {
+    let housekeeper = {
         let clients = Arc::clone(&clients);
         let rooms = Arc::clone(&rooms);
         let pubkeys = Arc::clone(&pubkeys);
+        let resume_tokens = Arc::clone(&resume_tokens);
 
         thread::Builder::new()
             .name("session-housekeeper".into())
             .spawn(move || {
-                if let Err(e) = session_housekeeper(clients, rooms, pubkeys) {
+                if let Err(e) = session_housekeeper(clients, rooms, pubkeys, resume_tokens) {
                     eprintln!("Thread for session housekeeping exited with error: {e}");
                 }
-            })?;
+            })?
+    };
+
+    {
+        let clients = Arc::clone(&clients);
+        let rooms = Arc::clone(&rooms);
+        let mut wake_addr = listener.local_addr()?;
+        if wake_addr.ip().is_unspecified() {
+            wake_addr.set_ip(match wake_addr.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+
+        ctrlc::set_handler(move || {
+            if SHUTDOWN.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            println!("Server shutting down...");
+
+            if let Err(e) = disconnect_all_clients(&clients, "Server shutting down") {
+                eprintln!("Failed to notify clients of shutdown: {e}");
+            }
+
+            if let Err(e) = save_rooms_to_disk(&lock_rooms(&rooms)) {
+                eprintln!("Failed to save rooms on shutdown: {e}");
+            }
+
+            let _ = TcpStream::connect(wake_addr);
+        }).map_err(std::io::Error::other)?;
     }
 
     for stream in listener.incoming() {
+        if SHUTDOWN.load(Ordering::SeqCst) {
+            break;
+        }
+
         match stream {
             Ok(stream) => {
                 let _ = stream.set_nodelay(true);
@@ -483,11 +692,13 @@ fn main() -> std::io::Result<()> {
                 let clients = Arc::clone(&clients);
                 let rooms = Arc::clone(&rooms);
                 let pubkeys = Arc::clone(&pubkeys);
+                let login_failures = Arc::clone(&login_failures);
+                let resume_tokens = Arc::clone(&resume_tokens);
 
                 thread::Builder::new()
                     .name(format!("client-{peer}"))
                     .spawn(move || {
-                        if let Err(e) = handle_client(stream, peer, clients, rooms, pubkeys) {
+                        if let Err(e) = handle_client(stream, peer, clients, rooms, pubkeys, login_failures, resume_tokens) {
                             eprintln!("Thread for {peer} exited with error: {e}");
                         }
                     })?;
@@ -498,6 +709,7 @@ fn main() -> std::io::Result<()> {
             }
         }
     }
-    
+
+    let _ = housekeeper.join();
     Ok(())
 }
@@ -135,15 +135,23 @@ pub fn decrypt(msg: &str) -> Result<String, Box<dyn std::error::Error>> {
 }
 
 pub fn broadcast_message(stream: &mut TcpStream, members: &HashMap<String, String>, msg: &str) -> io::Result<()> {
+    broadcast_encrypted(stream, members, "", msg)
+}
+
+pub fn broadcast_edit(stream: &mut TcpStream, members: &HashMap<String, String>, msg: &str) -> io::Result<()> {
+    broadcast_encrypted(stream, members, "/edit ", msg)
+}
+
+fn broadcast_encrypted(stream: &mut TcpStream, members: &HashMap<String, String>, command: &str, msg: &str) -> io::Result<()> {
     let mut first = true;
     for (recipient, pubkey) in members {
         match encrypt(msg, pubkey) {
             Ok(cipher_b64) => {
                 let wire = if first {
                     first = false;
-                    format!("{recipient} {cipher_b64} f")
+                    format!("{command}{recipient} {cipher_b64} f")
                 } else {
-                    format!("{recipient} {cipher_b64}")
+                    format!("{command}{recipient} {cipher_b64}")
                 };
 
                 stream.write_all(wire.as_bytes())?;
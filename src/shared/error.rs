@@ -0,0 +1,44 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum StreamlineError {
+    RoomNotFound(String),
+    Storage(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, StreamlineError>;
+
+impl fmt::Display for StreamlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamlineError::RoomNotFound(room) => write!(f, "Room {room} not found"),
+            StreamlineError::Storage(e) => write!(f, "Storage error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamlineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamlineError::Storage(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StreamlineError {
+    fn from(e: io::Error) -> Self {
+        StreamlineError::Storage(e)
+    }
+}
+
+// Lets `?` keep working inside the existing io::Result handler signatures
+impl From<StreamlineError> for io::Error {
+    fn from(e: StreamlineError) -> Self {
+        match e {
+            StreamlineError::Storage(inner) => inner,
+            StreamlineError::RoomNotFound(_) => io::Error::new(io::ErrorKind::NotFound, e),
+        }
+    }
+}
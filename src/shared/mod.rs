@@ -1,3 +1,4 @@
 pub mod utils;
 pub mod types;
 pub mod crypto;
+pub mod error;
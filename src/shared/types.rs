@@ -1,7 +1,8 @@
 #![allow(dead_code)]
 use std::collections::{HashMap, VecDeque};
 use std::net::{TcpStream, SocketAddr};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::AtomicBool;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
@@ -15,8 +16,11 @@ pub enum ClientState {
         room: String,
         room_time: Option<std::time::SystemTime>,
         msg_timestamps: VecDeque<Instant>,
+        last_msg: Option<Instant>,
         inactive_time: Option<std::time::SystemTime>,
-        is_afk: bool
+        is_afk: bool,
+        afk_reason: String,
+        warned: bool
     }
 }
 
@@ -25,8 +29,19 @@ pub struct Client {
     pub addr: SocketAddr,
     pub state: ClientState,
     pub ignore_list: Vec<String>,
+    pub dm_block_list: Vec<String>,
+    pub favorites: Vec<String>,
+    pub last_room_list: Vec<String>,
     pub pubkey: String,
     pub login_attempts: VecDeque<Instant>,
+    pub last_dm_from: Option<String>,
+    pub last_msg_id: u64,
+    pub dm_log: VecDeque<(String, String, bool)>,
+    pub last_room_create: Option<Instant>,
+    pub resume_token: Option<String>,
+    pub resume_room: Option<String>,
+    pub json_output: bool,
+    pub last_pong: Instant,
 }
 
 pub type Clients = Arc<Mutex<HashMap<SocketAddr, Arc<Mutex<Client>>>>>;
@@ -37,12 +52,62 @@ pub struct Room {
     pub whitelist: Vec<String>,
     pub msg_rate: u8,
     pub session_timeout: u32,
+    #[serde(default)]
+    pub slowmode_secs: u32,
     pub roles: Roles,
     pub users: HashMap<String, RoomUser>,
+    #[serde(default)]
+    pub history: VecDeque<HistoryEntry>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub filter_enabled: bool,
+    #[serde(default)]
+    pub blocked_words: Vec<String>,
+    #[serde(default)]
+    pub announce_staff: bool,
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub role_labels: HashMap<String, String>,
+    #[serde(default)]
+    pub require_ban_reason: bool,
+    #[serde(default)]
+    pub motd: String,
+    #[serde(default, skip_serializing, skip_deserializing)]
+    pub frozen: bool,
     #[serde(default, skip_serializing, skip_deserializing)]
     pub online_users: Vec<String>
 }
 
+impl Room {
+    /// Display name for a role key, falling back to the capitalized key when no label is set
+    pub fn role_label(&self, role: &str) -> String {
+        if let Some(label) = self.role_labels.get(role) {
+            return label.clone();
+        }
+        let mut ch = role.chars();
+        match ch.next() {
+            Some(f) => f.to_uppercase().collect::<String>() + ch.as_str(),
+            None => String::new(),
+        }
+    }
+}
+
+pub const ROOM_HISTORY_LIMIT: usize = 100;
+pub const DM_LOG_LIMIT: usize = 50;
+pub const MAILBOX_LIMIT: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub sender: String,
+    pub timestamp: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Roles {
     pub moderator: Vec<String>,
@@ -64,12 +129,27 @@ pub struct RoomUser {
     pub muted: bool,
     pub mute_stamp: u64,
     pub mute_length: u64,
-    pub mute_reason: String
+    pub mute_reason: String,
+    #[serde(default)]
+    pub role_expiry: u64,
+    #[serde(default)]
+    pub prior_role: String,
+    #[serde(default)]
+    pub ban_history: Vec<(u64, String)>
 }
 
 pub type Rooms = Arc<Mutex<HashMap<String, Arc<Mutex<Room>>>>>;
 
 pub static USERS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 pub static ROOMS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+pub static AUDIT_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+pub static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+pub static SERVER_START: OnceLock<Instant> = OnceLock::new();
 
 pub type PublicKeys = Arc<Mutex<HashMap<String, String>>>;
+
+pub type LoginFailures = Arc<Mutex<HashMap<String, (u32, Instant)>>>;
+
+pub type ResumeEntry = (String, Option<String>, Instant);
+pub type ResumeTokens = Arc<Mutex<HashMap<String, ResumeEntry>>>;
+pub const RESUME_TOKEN_TTL_SECS: u64 = 300;
@@ -4,9 +4,10 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::io;
 use std::io::{Write};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use colored::Colorize;
-use crate::shared::types::{Client, ClientState, Clients, Room, Rooms, ROOMS_LOCK, USERS_LOCK};
+use crate::shared::error::{self, StreamlineError};
+use crate::shared::types::{AUDIT_LOCK, Client, ClientState, Clients, DM_LOG_LIMIT, HistoryEntry, MAILBOX_LIMIT, LoginFailures, ResumeEntry, ResumeTokens, Room, Rooms, ROOMS_LOCK, ROOM_HISTORY_LIMIT, USERS_LOCK};
 
 pub trait ColorizeExt {
     fn truecolor_from_hex(self, hex: &str) -> colored::ColoredString;
@@ -32,7 +33,7 @@ impl ColorizeExt for String {
 }
 
 pub fn save_rooms_to_disk(map: &HashMap<String, Arc<Mutex<Room>>>) -> std::io::Result<()> {
-    let _lock = lock_rooms_storage()?;
+    let _lock = lock_rooms_storage();
 
     let mut snapshot = HashMap::new();
     for (name, arc) in map.iter() {
@@ -42,24 +43,101 @@ pub fn save_rooms_to_disk(map: &HashMap<String, Arc<Mutex<Room>>>) -> std::io::R
             eprintln!("Failed to lock room '{name}'");
         }
     }
-    let file = std::fs::OpenOptions::new().create(true).write(true).truncate(true).open("data/rooms.json")?;
+    retry_with_backoff(|| write_json_atomic("data/rooms.json", &snapshot))
+}
+
+const WRITE_ATTEMPTS: u32 = 3;
+const WRITE_BACKOFF: Duration = Duration::from_millis(50);
+
+// Runs a disk write up to WRITE_ATTEMPTS times, doubling the sleep between attempts, so a
+// transient failure (e.g. on a networked filesystem) doesn't surface as a user-facing error.
+pub fn retry_with_backoff<T>(mut write: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut attempt = 1;
+    let mut delay = WRITE_BACKOFF;
+    loop {
+        match write() {
+            Err(e) if attempt < WRITE_ATTEMPTS => {
+                eprintln!("Disk write failed (attempt {attempt}/{WRITE_ATTEMPTS}), retrying: {e}");
+                std::thread::sleep(delay);
+                attempt += 1;
+                delay *= 2;
+            }
+            res => return res,
+        }
+    }
+}
+
+fn write_json_atomic<T: serde::Serialize>(path: &str, data: &T) -> io::Result<()> {
+    let tmp_path = format!("{path}.tmp");
+    let file = std::fs::OpenOptions::new().create(true).write(true).truncate(true).open(&tmp_path)?;
     let mut writer = std::io::BufWriter::new(file);
     let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
     let mut ser = serde_json::Serializer::with_formatter(&mut writer, formatter);
-    serde::Serialize::serialize(&snapshot, &mut ser).map_err(io::Error::other)?;
+    data.serialize(&mut ser).map_err(io::Error::other)?;
 
-    Ok(())
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)
 }
 
-pub fn broadcast_message(clients: &Clients, room_name: &str, sender: &str, msg: &str, include_sender: bool, bypass_ignores: bool) -> io::Result<()> {
-    let client_arcs: Vec<Arc<Mutex<Client>>> =
-        match lock_clients(clients) {
-            Ok(map) => map.values().cloned().collect(),
-            Err(_)  => return Ok(()),
+pub fn check_rate_limit(client_arc: &Arc<Mutex<Client>>, rooms: &Rooms, is_first: bool) -> io::Result<bool> {
+    let now = Instant::now();
+
+    let mut c = lock_client(client_arc);
+    if let ClientState::InRoom { username, room: rname, msg_timestamps, last_msg, .. } = &mut c.state {
+        let rooms_map = lock_rooms(rooms);
+        let (rate, slowmode) = match find_room(&rooms_map, rname) {
+            Ok(room_arc) => {
+                let room = lock_room(&room_arc);
+                let exempt = room.users.get(username).is_some_and(|u| u.role == "admin" || u.role == "owner");
+                (room.msg_rate, if exempt { 0 } else { room.slowmode_secs })
+            },
+            Err(e) => {
+                drop(rooms_map);
+                send_failure_locked(&mut c, &e)?;
+                return Ok(false);
+            }
         };
 
+        while let Some(ts) = msg_timestamps.front() {
+            if now.duration_since(*ts).as_secs() >= 5 {
+                msg_timestamps.pop_front();
+            }
+            else {
+                break;
+            }
+        }
+
+        if rate > 0 && msg_timestamps.len() as u8 >= rate {
+            if is_first {
+                send_message_locked(&mut c, &"Rate limit exceeded, slow down your messages!".yellow().to_string())?;
+            }
+            return Ok(false);
+        }
+
+        if let Some(last) = last_msg {
+            let elapsed = now.duration_since(*last).as_secs();
+            if elapsed < slowmode as u64 {
+                if is_first {
+                    send_message_locked(&mut c, &format!("Slowmode is on, you can send another message in {} sec", slowmode as u64 - elapsed).yellow().to_string())?;
+                }
+                return Ok(false);
+            }
+        }
+
+        if is_first {
+            msg_timestamps.push_back(now);
+            *last_msg = Some(now);
+        }
+    }
+    Ok(true)
+}
+
+pub fn broadcast_message(clients: &Clients, room_name: &str, sender: &str, msg: &str, include_sender: bool, bypass_ignores: bool, recipients: Option<&[String]>) -> io::Result<()> {
+    let client_arcs: Vec<Arc<Mutex<Client>>> = lock_clients(clients).values().cloned().collect();
+
     for arc in client_arcs {
-        let mut c = lock_client(&arc)?;
+        let mut c = lock_client(&arc);
         
         if let ClientState::InRoom { username, room, .. } = &c.state {
             if room != room_name { continue; }
@@ -72,18 +150,53 @@ pub fn broadcast_message(clients: &Clients, room_name: &str, sender: &str, msg:
                 continue;
             }
 
+            if username != sender && recipients.is_some_and(|allowed| !allowed.contains(username)) {
+                continue;
+            }
+
             writeln!(c.stream, "{msg}")?;
         }
     }
     Ok(())
 }
 
-pub fn check_mute(rooms: &Rooms, room: &str, username: &str) -> io::Result<Option<String>> {
+pub fn format_duration(secs: u64) -> String {
+    let (d, h, m, s) = (secs / 86_400, (secs % 86_400) / 3_600, (secs % 3_600) / 60, secs % 60);
+    format!("{d}d {h}h {m}m {s}s")
+}
+
+// Archived rooms are read-only for everyone, so this is checked separately from mutes and freezes
+pub fn is_archived(rooms: &Rooms, room: &str) -> bool {
+    let rooms_map = lock_rooms(rooms);
+    find_room(&rooms_map, room).is_ok_and(|r| lock_room(&r).archived)
+}
+
+pub fn check_filter(rooms: &Rooms, room: &str, text: &str) -> io::Result<bool> {
     let room_arc = {
-        let rooms_map = lock_rooms(rooms)?;
+        let rooms_map = lock_rooms(rooms);
         match rooms_map.get(room) {
             Some(r) => Arc::clone(r),
-            None => return Ok(Some("Room not found".to_string())),
+            None => return Ok(false),
+        }
+    };
+
+    let rg = lock_room(&room_arc);
+    if !rg.filter_enabled || rg.blocked_words.is_empty() {
+        return Ok(false);
+    }
+
+    Ok(text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .any(|w| rg.blocked_words.contains(&w.to_lowercase())))
+}
+
+pub fn check_mute(rooms: &Rooms, room: &str, username: &str) -> io::Result<Option<String>> {
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match find_room(&rooms_map, room) {
+            Ok(r) => r,
+            Err(e) => return Ok(Some(e.to_string())),
         }
     };
 
@@ -91,7 +204,10 @@ pub fn check_mute(rooms: &Rooms, room: &str, username: &str) -> io::Result<Optio
     let mut still_muted_msg = None;
 
     {
-        let mut rg = lock_room(&room_arc)?;
+        let mut rg = lock_room(&room_arc);
+        if rg.frozen && rg.users.get(username).is_none_or(|u| u.role == "user") {
+            return Ok(Some("Chat is frozen".to_string()));
+        }
 
         if let Some(rec) = rg.users.get_mut(username) {
             if rec.muted {
@@ -110,14 +226,7 @@ pub fn check_mute(rooms: &Rooms, room: &str, username: &str) -> io::Result<Optio
                     let remaining = if rec.mute_length == 0 {
                         "Permanent".to_string()
                     } else {
-                        let mut rem = rec.mute_stamp + rec.mute_length - now;
-                        let d = rem / 86_400;
-                        rem %= 86_400;
-                        let h = rem / 3_600;
-                        rem %= 3_600;
-                        let m = rem / 60;
-                        let s = rem % 60;
-                        format!("{d}d {h}h {m}m {s}s left")
+                        format!("{} left", format_duration(rec.mute_stamp + rec.mute_length - now))
                     };
                     still_muted_msg = Some(if rec.mute_reason.is_empty() {
                         format!("You are muted ({remaining})")
@@ -136,7 +245,7 @@ pub fn check_mute(rooms: &Rooms, room: &str, username: &str) -> io::Result<Optio
     }
 
     if need_save {
-        let rooms_map = lock_rooms(rooms)?;
+        let rooms_map = lock_rooms(rooms);
         if let Err(e) = save_rooms_to_disk(&rooms_map) {
             eprintln!("Failed to save rooms: {e}");
         }
@@ -146,14 +255,117 @@ pub fn check_mute(rooms: &Rooms, room: &str, username: &str) -> io::Result<Optio
     Ok(still_muted_msg)
 }
 
+pub fn log_dm(client: &mut Client, peer: &str, text: &str, outgoing: bool) {
+    client.dm_log.push_back((peer.to_string(), text.to_string(), outgoing));
+    while client.dm_log.len() > DM_LOG_LIMIT {
+        client.dm_log.pop_front();
+    }
+}
+
+// Stores a DM for an offline user in their `mail` array in users.json. Returns false if the mailbox is full.
+// DMs are plain text to the server (only room chat is end-to-end encrypted), so the text is stored as received
+pub fn store_mail(recipient: &str, sender: &str, room: &str, text: &str) -> io::Result<bool> {
+    let _ulock = lock_users_storage();
+    let mut users_json = load_json("data/users.json")?;
+    let user_obj = match users_json.get_mut(recipient).and_then(serde_json::Value::as_object_mut) {
+        Some(obj) => obj,
+        None => return Ok(false),
+    };
+
+    // Mirror live delivery: blocked or ignored senders are dropped silently
+    let blocked = ["dm_block", "ignore"].iter().any(|key| {
+        user_obj.get(*key)
+            .and_then(serde_json::Value::as_array)
+            .is_some_and(|arr| arr.iter().any(|v| v.as_str() == Some(sender)))
+    });
+    if blocked {
+        return Ok(true);
+    }
+
+    let mail = user_obj.entry("mail").or_insert_with(|| serde_json::json!([]));
+    let arr = match mail.as_array_mut() {
+        Some(arr) => arr,
+        None => {
+            *mail = serde_json::json!([]);
+            mail.as_array_mut().expect("mail was just set to an array")
+        }
+    };
+    if arr.len() >= MAILBOX_LIMIT {
+        return Ok(false);
+    }
+
+    let sent_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    arr.push(serde_json::json!({
+        "from": sender,
+        "room": room,
+        "text": text,
+        "sent_at": sent_at
+    }));
+    save_json("data/users.json", &users_json)?;
+    Ok(true)
+}
+
+// Removes and returns the stored mail sent to `username` from `room`, as (sender, text, sent_at)
+pub fn take_mail(username: &str, room: &str) -> io::Result<Vec<(String, String, u64)>> {
+    let _ulock = lock_users_storage();
+    let mut users_json = load_json("data/users.json")?;
+    let arr = match users_json.get_mut(username).and_then(|u| u.get_mut("mail")).and_then(serde_json::Value::as_array_mut) {
+        Some(arr) => arr,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut taken = Vec::new();
+    arr.retain(|entry| {
+        if entry.get("room").and_then(serde_json::Value::as_str) != Some(room) {
+            return true;
+        }
+        let from = entry.get("from").and_then(serde_json::Value::as_str).unwrap_or("unknown").to_string();
+        let text = entry.get("text").and_then(serde_json::Value::as_str).unwrap_or_default().to_string();
+        let sent_at = entry.get("sent_at").and_then(serde_json::Value::as_u64).unwrap_or(0);
+        taken.push((from, text, sent_at));
+        false
+    });
+
+    if !taken.is_empty() {
+        save_json("data/users.json", &users_json)?;
+    }
+    Ok(taken)
+}
+
+pub fn record_history(rooms: &Rooms, room_name: &str, sender: &str) -> io::Result<()> {
+    let room_arc = {
+        let rooms_map = lock_rooms(rooms);
+        match rooms_map.get(room_name) {
+            Some(r) => Arc::clone(r),
+            None => return Ok(()),
+        }
+    };
+
+    let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(dur) => dur.as_secs(),
+        Err(_) => 0,
+    };
+
+    let mut rg = lock_room(&room_arc);
+    rg.history.push_back(HistoryEntry {
+        sender: sender.to_string(),
+        timestamp,
+    });
+    while rg.history.len() > ROOM_HISTORY_LIMIT {
+        rg.history.pop_front();
+    }
+
+    Ok(())
+}
+
 pub fn format_broadcast(rooms: &Rooms, room_name: &str, username: &str) -> io::Result<(String, String)> {
-    let rooms_map = lock_rooms(rooms)?;
+    let rooms_map = lock_rooms(rooms);
     let room_arc = match rooms_map.get(room_name) {
         Some(r) => Arc::clone(r),
         None => return Ok(("".to_string(), username.to_string())),
     };
 
-    let rg = lock_room(&room_arc)?;
+    let rg = lock_room(&room_arc);
     let user_info = rg.users.get(username);
 
     let mut prefix_colored = "".to_string();
@@ -185,45 +397,77 @@ pub fn format_broadcast(rooms: &Rooms, room_name: &str, username: &str) -> io::R
     Ok((prefix_colored, display_name))
 }
 
-pub fn lock_clients(clients: &Clients) -> std::io::Result<std::sync::MutexGuard<'_, HashMap<SocketAddr, Arc<Mutex<Client>>>>> {
-    clients.lock().map_err(|e| {
-        eprintln!("Failed to lock clients: {e}");
-        std::io::Error::other("Lock poisoned")
+// These recover from poisoning so one panicked handler doesn't take every later command down
+// with it. The maps stay structurally valid, but a handler that panics partway through a
+// multi-field update (bans, role changes) can leave that room's fields out of step with each
+// other until the next reload. The storage and audit locks guard no data, so they always recover.
+pub fn lock_clients(clients: &Clients) -> std::sync::MutexGuard<'_, HashMap<SocketAddr, Arc<Mutex<Client>>>> {
+    clients.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned clients lock: {e}");
+        e.into_inner()
     })
 }
 
-pub fn lock_client(client_arc: &Arc<Mutex<Client>>) -> std::io::Result<std::sync::MutexGuard<'_, Client>> {
-    client_arc.lock().map_err(|e| {
-        eprintln!("Failed to lock client: {e}");
-        std::io::Error::other("Lock poisoned")
+pub fn lock_client(client_arc: &Arc<Mutex<Client>>) -> std::sync::MutexGuard<'_, Client> {
+    client_arc.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned client lock: {e}");
+        e.into_inner()
     })
 }
 
-pub fn lock_rooms(rooms: &Rooms) -> std::io::Result<std::sync::MutexGuard<'_, HashMap<String, Arc<Mutex<Room>>>>> {
-    rooms.lock().map_err(|e| {
-        eprintln!("Failed to lock rooms: {e}");
-        std::io::Error::other("Lock poisoned")
+pub fn lock_rooms(rooms: &Rooms) -> std::sync::MutexGuard<'_, HashMap<String, Arc<Mutex<Room>>>> {
+    rooms.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned rooms lock: {e}");
+        e.into_inner()
     })
 }
 
-pub fn lock_room(room_arc: &Arc<Mutex<Room>>) -> std::io::Result<std::sync::MutexGuard<'_, Room>> {
-    room_arc.lock().map_err(|e| {
-        eprintln!("Failed to lock room: {e}");
-        std::io::Error::other("Lock poisoned")
+pub fn lock_room(room_arc: &Arc<Mutex<Room>>) -> std::sync::MutexGuard<'_, Room> {
+    room_arc.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned room lock: {e}");
+        e.into_inner()
     })
 }
 
-pub fn lock_users_storage<'a>() -> io::Result<MutexGuard<'a, ()>> {
-    USERS_LOCK.lock().map_err(|e| {
-        eprintln!("Failed to lock users: {e}");
-        io::Error::other("Error: Could not acquire user lock")
+pub fn lock_login_failures(failures: &LoginFailures) -> std::sync::MutexGuard<'_, HashMap<String, (u32, Instant)>> {
+    failures.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned login failures lock: {e}");
+        e.into_inner()
     })
 }
 
-pub fn lock_rooms_storage<'a>() -> io::Result<MutexGuard<'a, ()>> {
-    ROOMS_LOCK.lock().map_err(|e| {
-        eprintln!("Failed to lock rooms: {e}");
-        io::Error::other("Error: Could not acquire room lock")
+pub fn lock_resume_tokens(tokens: &ResumeTokens) -> std::sync::MutexGuard<'_, HashMap<String, ResumeEntry>> {
+    tokens.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned resume tokens lock: {e}");
+        e.into_inner()
+    })
+}
+
+pub fn find_room(rooms_map: &HashMap<String, Arc<Mutex<Room>>>, room: &str) -> error::Result<Arc<Mutex<Room>>> {
+    rooms_map.get(room).map(Arc::clone).ok_or_else(|| StreamlineError::RoomNotFound(room.to_string()))
+}
+
+pub fn issue_resume_token(client: &mut Client) -> io::Result<()> {
+    use rand::RngCore;
+    let mut bytes = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    writeln!(client.stream, "/TOKEN {token}")?;
+    client.resume_token = Some(token);
+    Ok(())
+}
+
+pub fn lock_users_storage<'a>() -> MutexGuard<'a, ()> {
+    USERS_LOCK.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned users storage lock: {e}");
+        e.into_inner()
+    })
+}
+
+pub fn lock_rooms_storage<'a>() -> MutexGuard<'a, ()> {
+    ROOMS_LOCK.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned rooms storage lock: {e}");
+        e.into_inner()
     })
 }
 
@@ -235,54 +479,90 @@ pub fn load_json(path: &str) -> io::Result<serde_json::Value> {
 }
 
 pub fn save_json(path: &str, data: &serde_json::Value) -> io::Result<()> {
-    let file = std::fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(path)?;
-    let mut writer = std::io::BufWriter::new(file);
-    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
-    let mut ser = serde_json::Serializer::with_formatter(&mut writer, formatter);
-    serde::Serialize::serialize(data, &mut ser)?;
+    retry_with_backoff(|| write_json_atomic(path, data))
+}
+
+// Heartbeat replies that arrive while a prompt is waiting are consumed here so they
+// aren't mistaken for an answer
+pub fn read_prompt_line<R: io::BufRead>(reader: &mut R, line: &mut String, client: &Arc<Mutex<Client>>) -> io::Result<usize> {
+    loop {
+        match reader.read_line(line) {
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+            Ok(n) if n > 0 => {
+                lock_client(client).last_pong = Instant::now();
+                if line.trim() == "/pong" {
+                    line.clear();
+                    continue;
+                }
+                return Ok(n);
+            }
+            res => return res,
+        }
+    }
+}
+
+fn strip_ansi(msg: &str) -> String {
+    let mut out = String::with_capacity(msg.len());
+    let mut chars = msg.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() { break; }
+            }
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn write_response(client: &mut Client, kind: &str, msg: &str, styled: &str) -> io::Result<()> {
+    if client.json_output && !msg.starts_with('/') {
+        writeln!(client.stream, "{}", serde_json::json!({ "type": kind, "msg": strip_ansi(msg) }))?;
+    } else {
+        writeln!(client.stream, "{styled}")?;
+    }
+    client.stream.flush()?;
     Ok(())
 }
 
 pub fn send_message(client_arc: &Arc<Mutex<Client>>, msg: &str) -> io::Result<()> {
-    let mut c = lock_client(client_arc)?;
-    writeln!(c.stream, "{msg}")?;
-    c.stream.flush()?;
-    Ok(())
+    let mut c = lock_client(client_arc);
+    send_message_locked(&mut c, msg)
 }
 
 pub fn send_message_locked(client: &mut Client, msg: &str) -> io::Result<()> {
-    writeln!(client.stream, "{msg}")?;
-    client.stream.flush()?;
-    Ok(())
+    write_response(client, "info", msg, msg)
 }
 
 pub fn send_error(client_arc: &Arc<Mutex<Client>>, msg: &str) -> io::Result<()> {
-    let mut c = lock_client(client_arc)?;
-    writeln!(c.stream, "{}", msg.red())?;
-    c.stream.flush()?;
-    Ok(())
+    let mut c = lock_client(client_arc);
+    send_error_locked(&mut c, msg)
 }
 
 pub fn send_error_locked(client: &mut Client, msg: &str) -> io::Result<()> {
-    writeln!(client.stream, "{}", msg.red())?;
-    client.stream.flush()?;
-    Ok(())
+    write_response(client, "error", msg, &msg.red().to_string())
+}
+
+pub fn send_failure(client_arc: &Arc<Mutex<Client>>, err: &StreamlineError) -> io::Result<()> {
+    let mut c = lock_client(client_arc);
+    send_failure_locked(&mut c, err)
+}
+
+pub fn send_failure_locked(client: &mut Client, err: &StreamlineError) -> io::Result<()> {
+    match err {
+        StreamlineError::Storage(_) => send_error_locked(client, &err.to_string()),
+        _ => send_message_locked(client, &err.to_string().yellow().to_string()),
+    }
 }
 
 pub fn send_success(client_arc: &Arc<Mutex<Client>>, msg: &str) -> io::Result<()> {
-    let mut c = lock_client(client_arc)?;
-    writeln!(c.stream, "{}", msg.green())?;
-    c.stream.flush()?;
-    Ok(())
+    let mut c = lock_client(client_arc);
+    send_success_locked(&mut c, msg)
 }
 
 pub fn send_success_locked(client: &mut Client, msg: &str) -> io::Result<()> {
-    writeln!(client.stream, "{}", msg.green())?;
-    client.stream.flush()?;
-    Ok(())
+    write_response(client, "success", msg, &msg.green().to_string())
 }
 
 pub fn log_event(peer: &SocketAddr, username: Option<&str>, room: Option<&str>, action: &str) {
@@ -292,8 +572,61 @@ pub fn log_event(peer: &SocketAddr, username: Option<&str>, room: Option<&str>,
     println!("[{}] ({}) {}{} - {}", timestamp, peer, user_display, room_display, action);
 }
 
+const AUDIT_LOG_PATH: &str = "data/audit.log";
+const AUDIT_LOG_ROTATED_PATH: &str = "data/audit.log.1";
+const AUDIT_LOG_MAX_BYTES: u64 = 1_048_576;
+
+pub fn log_audit(actor: &str, target: &str, room: &str, action: &str) {
+    let _lock = AUDIT_LOCK.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned audit log lock: {e}");
+        e.into_inner()
+    });
+
+    let rotated = match std::fs::metadata(AUDIT_LOG_PATH) {
+        Ok(meta) if meta.len() >= AUDIT_LOG_MAX_BYTES => std::fs::rename(AUDIT_LOG_PATH, AUDIT_LOG_ROTATED_PATH),
+        _ => Ok(()),
+    };
+    if let Err(e) = rotated {
+        eprintln!("Failed to rotate audit log: {e}");
+    }
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let line = format!("[{timestamp}] actor={actor} target={target} room={room} action={action}\n");
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(AUDIT_LOG_PATH)
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+    if let Err(e) = result {
+        eprintln!("Failed to write audit log: {e}");
+    }
+}
+
+// Returns the last `limit` audit entries for `room`, oldest first. The rotated log is read
+// before the current one, and missing log files count as empty.
+pub fn read_audit_log(room: &str, limit: usize) -> io::Result<Vec<String>> {
+    let _lock = AUDIT_LOCK.lock().unwrap_or_else(|e| {
+        eprintln!("Recovering poisoned audit log lock: {e}");
+        e.into_inner()
+    });
+
+    let room_field = format!(" room={room} action=");
+    let mut entries = Vec::new();
+    for path in [AUDIT_LOG_ROTATED_PATH, AUDIT_LOG_PATH] {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        entries.extend(contents.lines().filter(|l| l.contains(&room_field)).map(|l| l.replacen(&room_field, " action=", 1)));
+    }
+
+    let skip = entries.len().saturating_sub(limit);
+    Ok(entries.split_off(skip))
+}
+
 pub fn broadcast_room_list(clients: &Clients, rooms: &Rooms, username: &str) -> io::Result<()> {
-    let rooms_map = lock_rooms(rooms)?;
+    let rooms_map = lock_rooms(rooms);
     let mut visible_rooms = Vec::new();
     
     for (room_name, room_arc) in rooms_map.iter() {
@@ -312,7 +645,7 @@ pub fn broadcast_room_list(clients: &Clients, rooms: &Rooms, username: &str) ->
         .collect::<Vec<_>>()
         .join(" ");
     
-    let clients_map = lock_clients(clients)?;
+    let clients_map = lock_clients(clients);
     for client_arc in clients_map.values() {
         if let Ok(mut c) = client_arc.try_lock() {
             match &c.state {
@@ -329,7 +662,7 @@ pub fn broadcast_room_list(clients: &Clients, rooms: &Rooms, username: &str) ->
 }
 
 pub fn broadcast_room_list_to_all(clients: &Clients, rooms: &Rooms) -> io::Result<()> {
-    let clients_map = lock_clients(clients)?;
+    let clients_map = lock_clients(clients);
     let logged_in_users: Vec<String> = clients_map.values()
         .filter_map(|arc| {
             arc.lock().ok().and_then(|c| {
@@ -350,21 +683,37 @@ pub fn broadcast_room_list_to_all(clients: &Clients, rooms: &Rooms) -> io::Resul
     Ok(())
 }
 
+pub fn disconnect_all_clients(clients: &Clients, msg: &str) -> io::Result<()> {
+    let client_arcs: Vec<Arc<Mutex<Client>>> = {
+        let clients_map = lock_clients(clients);
+        clients_map.values().cloned().collect()
+    };
+
+    for arc in client_arcs {
+        if let Ok(mut c) = arc.lock() {
+            let _ = writeln!(c.stream, "{}", msg.yellow());
+            let _ = c.stream.flush();
+            let _ = c.stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+    Ok(())
+}
+
 pub fn broadcast_user_list(clients: &Clients, rooms: &Rooms, room_name: &str) -> io::Result<()> {
     let visible_usernames: Vec<String> = {
-        let rooms_map = lock_rooms(rooms)?;
+        let rooms_map = lock_rooms(rooms);
         let room_arc = match rooms_map.get(room_name) {
             Some(r) => Arc::clone(r),
             None => return Ok(()),
         };
         
-        let room = lock_room(&room_arc)?;
+        let room = lock_room(&room_arc);
         let mut usernames = Vec::new();
         
         for online_user in &room.online_users {
             if let Some(user_info) = room.users.get(online_user) {
                 if !user_info.hidden {
-                    let clients_map = lock_clients(clients)?;
+                    let clients_map = lock_clients(clients);
                     let mut is_afk = false;
                     for client_arc in clients_map.values() {
                         if let Ok(c) = client_arc.try_lock() {
@@ -401,7 +750,7 @@ pub fn broadcast_user_list(clients: &Clients, rooms: &Rooms, room_name: &str) ->
     
     let users_str = visible_users.join("\x1F");
     
-    let clients_map = lock_clients(clients)?;
+    let clients_map = lock_clients(clients);
     for client_arc in clients_map.values() {
         if let Ok(mut c) = client_arc.try_lock() {
             if let ClientState::InRoom { room: r, .. } = &c.state {
@@ -414,3 +763,51 @@ pub fn broadcast_user_list(clients: &Clients, rooms: &Rooms, room_name: &str) ->
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truecolor_from_hex_ignores_leading_hash() {
+        assert_eq!("name".truecolor_from_hex("FFFFFF"), "name".truecolor_from_hex("#FFFFFF"));
+        assert_eq!("name".truecolor_from_hex("#FFFFFF"), "name".truecolor(255, 255, 255));
+    }
+
+    #[test]
+    fn lock_clients_recovers_poisoned_lock() {
+        let clients: Clients = Arc::new(Mutex::new(HashMap::new()));
+        let poisoner = Arc::clone(&clients);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the clients lock");
+        }).join();
+
+        assert!(clients.is_poisoned());
+        assert!(lock_clients(&clients).is_empty());
+    }
+
+    #[test]
+    fn retry_with_backoff_succeeds_after_failures() {
+        let mut calls = 0;
+        let result = retry_with_backoff(|| {
+            calls += 1;
+            if calls < WRITE_ATTEMPTS { Err(io::Error::other("disk busy")) } else { Ok(calls) }
+        });
+
+        assert_eq!(result.unwrap(), WRITE_ATTEMPTS);
+        assert_eq!(calls, WRITE_ATTEMPTS);
+    }
+
+    #[test]
+    fn retry_with_backoff_surfaces_last_error() {
+        let mut calls = 0;
+        let result: io::Result<()> = retry_with_backoff(|| {
+            calls += 1;
+            Err(io::Error::other(format!("attempt {calls} failed")))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), format!("attempt {WRITE_ATTEMPTS} failed"));
+        assert_eq!(calls, WRITE_ATTEMPTS);
+    }
+}